@@ -0,0 +1,766 @@
+//! ECDSA adaptor signatures on a 2-of-2 P2WSH output.
+//!
+//! An adaptor signature (or encrypted signature) is an ECDSA signature
+//! encrypted to a point `Y`. Anyone can verify that it decrypts to a valid
+//! signature, only the holder of `y` can decrypt it, and publishing the
+//! decrypted signature reveals `y` to the party that created the encrypted
+//! one. This allows to swap without hash locks: the secret is exchanged
+//! through the signatures of a 2-of-2 output instead of `Witness::Data`.
+//!
+//! Construction: https://github.com/LLFourn/one-time-VES/blob/master/main.pdf
+use crate::bitcoin::witness::{UnlockParameters, Witness, SEQUENCE_ALLOW_NTIMELOCK_NO_RBF};
+use hex_literal::hex;
+use rust_bitcoin::{
+    blockdata::{opcodes::all::*, script::Builder},
+    hashes::{sha256, Hash, HashEngine},
+    network::constants::Network,
+    secp256k1::{self, Message, PublicKey, Secp256k1, SecretKey, Signature},
+    Address, Script, SigHashType, Transaction,
+};
+use std::{convert::TryFrom, fmt};
+
+/// The order of the secp256k1 group.
+const ORDER: [u8; 32] = hex!("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141");
+
+/// The order of the secp256k1 group minus two, the exponent which inverts a
+/// scalar.
+const ORDER_MINUS_TWO: [u8; 32] =
+    hex!("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd036413f");
+
+/// The generator of the secp256k1 group, compressed.
+const GENERATOR: [u8; 33] =
+    hex!("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798");
+
+const NONCE_TAG: &[u8] = b"blockchain_contracts/adaptor/nonce";
+const DLEQ_NONCE_TAG: &[u8] = b"blockchain_contracts/adaptor/dleq_nonce";
+const DLEQ_CHALLENGE_TAG: &[u8] = b"blockchain_contracts/adaptor/dleq_challenge";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Error {
+    MalformedEncryptedSignature,
+    InvalidEncryptedSignature,
+    InvalidProof,
+    UnrelatedSignature,
+    UnknownKey,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MalformedEncryptedSignature => {
+                write!(f, "bytes are not a well-formed encrypted signature")
+            }
+            Error::InvalidEncryptedSignature => {
+                write!(
+                    f,
+                    "encrypted signature does not decrypt to a valid signature"
+                )
+            }
+            Error::InvalidProof => write!(f, "encryption proof is invalid"),
+            Error::UnrelatedSignature => {
+                write!(
+                    f,
+                    "signature is not the decryption of the encrypted signature"
+                )
+            }
+            Error::UnknownKey => write!(f, "key is not part of the 2-of-2 output"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Proof that `R = k*G` and `R' = k*Y` share the same discrete logarithm.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct DleqProof {
+    challenge: SecretKey,
+    response: SecretKey,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EncryptedSignature {
+    /// The nonce `k*G`
+    r: PublicKey,
+    /// The nonce `k*Y`, whose x-coordinate is the `r` of the decrypted
+    /// signature
+    r_prime: PublicKey,
+    s_prime: SecretKey,
+    proof: DleqProof,
+}
+
+impl EncryptedSignature {
+    /// The length of the encoding of `to_bytes`: the nonces `R` and `R'` as
+    /// compressed points, followed by the scalar `s'` and the challenge and
+    /// response of the proof.
+    pub const LEN: usize = 2 * 33 + 3 * 32;
+
+    /// Signs `message` with `signing_key` and encrypts the signature to
+    /// `encryption_key`.
+    pub fn encrypt<C: secp256k1::Signing + secp256k1::Verification>(
+        secp: &Secp256k1<C>,
+        signing_key: &SecretKey,
+        message: &Message,
+        encryption_key: &PublicKey,
+    ) -> Self {
+        let (k, r, r_prime, r_scalar) = (0u32..)
+            .find_map(|counter| {
+                let k = hash_to_scalar(
+                    NONCE_TAG,
+                    &[
+                        &signing_key[..],
+                        &message[..],
+                        &encryption_key.serialize(),
+                        &counter.to_be_bytes(),
+                    ],
+                )?;
+                let r = PublicKey::from_secret_key(secp, &k);
+                let r_prime = mul_point(secp, encryption_key, &k);
+                let r_scalar = x_coordinate_scalar(&r_prime)?;
+
+                Some((k, r, r_prime, r_scalar))
+            })
+            .expect("a valid nonce is found with overwhelming probability");
+
+        // s' = k^-1 * (m + r * x)
+        let mut s_prime = r_scalar;
+        s_prime
+            .mul_assign(&signing_key[..])
+            .expect("product of two valid scalars is valid");
+        s_prime
+            .add_assign(&reduce(message_bytes(message)))
+            .expect("sum is not zero with overwhelming probability");
+        s_prime
+            .mul_assign(&invert(&k)[..])
+            .expect("product of two valid scalars is valid");
+
+        let proof = DleqProof::prove(secp, &k, encryption_key, &r, &r_prime);
+
+        EncryptedSignature {
+            r,
+            r_prime,
+            s_prime,
+            proof,
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut bytes = [0u8; Self::LEN];
+        bytes[..33].copy_from_slice(&self.r.serialize());
+        bytes[33..66].copy_from_slice(&self.r_prime.serialize());
+        bytes[66..98].copy_from_slice(&self.s_prime[..]);
+        bytes[98..130].copy_from_slice(&self.proof.challenge[..]);
+        bytes[130..].copy_from_slice(&self.proof.response[..]);
+
+        bytes
+    }
+
+    /// Decodes the encoding of `to_bytes`, checking that it holds valid
+    /// points and scalars.
+    ///
+    /// This does not verify the encrypted signature, see `verify`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != Self::LEN {
+            return Err(Error::MalformedEncryptedSignature);
+        }
+
+        let point =
+            |bytes| PublicKey::from_slice(bytes).map_err(|_| Error::MalformedEncryptedSignature);
+        let scalar =
+            |bytes| SecretKey::from_slice(bytes).map_err(|_| Error::MalformedEncryptedSignature);
+
+        let r_prime = point(&bytes[33..66])?;
+        // `decrypt` needs the x-coordinate of `R'` to be a valid `r`
+        x_coordinate_scalar(&r_prime).ok_or(Error::MalformedEncryptedSignature)?;
+
+        Ok(EncryptedSignature {
+            r: point(&bytes[..33])?,
+            r_prime,
+            s_prime: scalar(&bytes[66..98])?,
+            proof: DleqProof {
+                challenge: scalar(&bytes[98..130])?,
+                response: scalar(&bytes[130..])?,
+            },
+        })
+    }
+
+    /// Verifies that the encrypted signature decrypts, with the discrete
+    /// logarithm of `encryption_key`, to a valid signature of `message` under
+    /// `verification_key`.
+    pub fn verify<C: secp256k1::Verification>(
+        &self,
+        secp: &Secp256k1<C>,
+        verification_key: &PublicKey,
+        message: &Message,
+        encryption_key: &PublicKey,
+    ) -> Result<(), Error> {
+        let r_scalar =
+            x_coordinate_scalar(&self.r_prime).ok_or(Error::InvalidEncryptedSignature)?;
+        let s_prime_inverse = invert(&self.s_prime);
+
+        // R = (m * s'^-1) * G + (r * s'^-1) * X
+        let mut u1 = s_prime_inverse;
+        u1.mul_assign(&reduce(message_bytes(message)))
+            .map_err(|_| Error::InvalidEncryptedSignature)?;
+        let mut u2 = r_scalar;
+        u2.mul_assign(&s_prime_inverse[..])
+            .map_err(|_| Error::InvalidEncryptedSignature)?;
+
+        let expected_r = mul_point(secp, &generator(), &u1)
+            .combine(&mul_point(secp, verification_key, &u2))
+            .map_err(|_| Error::InvalidEncryptedSignature)?;
+
+        if expected_r != self.r {
+            return Err(Error::InvalidEncryptedSignature);
+        }
+
+        self.proof
+            .verify(secp, encryption_key, &self.r, &self.r_prime)
+    }
+
+    /// Decrypts the encrypted signature with the discrete logarithm of the
+    /// encryption key.
+    pub fn decrypt(&self, decryption_key: &SecretKey) -> Signature {
+        let r_scalar = x_coordinate_scalar(&self.r_prime)
+            .expect("encrypted signatures are constructed with a valid r");
+
+        let mut s = self.s_prime;
+        s.mul_assign(&invert(decryption_key)[..])
+            .expect("product of two valid scalars is valid");
+
+        let mut compact = [0u8; 64];
+        compact[..32].copy_from_slice(&r_scalar[..]);
+        compact[32..].copy_from_slice(&s[..]);
+
+        let mut signature = Signature::from_compact(&compact).expect("r and s are valid scalars");
+        signature.normalize_s();
+
+        signature
+    }
+
+    /// Recovers the decryption key from a signature that was published after
+    /// decrypting this encrypted signature.
+    pub fn recover<C: secp256k1::Verification>(
+        &self,
+        secp: &Secp256k1<C>,
+        signature: &Signature,
+        encryption_key: &PublicKey,
+    ) -> Result<SecretKey, Error> {
+        let compact = signature.serialize_compact();
+        let r_scalar = x_coordinate_scalar(&self.r_prime).ok_or(Error::UnrelatedSignature)?;
+
+        if compact[..32] != r_scalar[..] {
+            return Err(Error::UnrelatedSignature);
+        }
+
+        let s = SecretKey::from_slice(&compact[32..]).map_err(|_| Error::UnrelatedSignature)?;
+
+        // y = s' * s^-1, up to the sign flipped by `normalize_s`
+        let mut decryption_key = self.s_prime;
+        decryption_key
+            .mul_assign(&invert(&s)[..])
+            .map_err(|_| Error::UnrelatedSignature)?;
+
+        if mul_point(secp, &generator(), &decryption_key) == *encryption_key {
+            return Ok(decryption_key);
+        }
+
+        decryption_key.negate_assign();
+
+        if mul_point(secp, &generator(), &decryption_key) == *encryption_key {
+            return Ok(decryption_key);
+        }
+
+        Err(Error::UnrelatedSignature)
+    }
+}
+
+impl DleqProof {
+    fn prove<C: secp256k1::Signing + secp256k1::Verification>(
+        secp: &Secp256k1<C>,
+        k: &SecretKey,
+        encryption_key: &PublicKey,
+        r: &PublicKey,
+        r_prime: &PublicKey,
+    ) -> Self {
+        let a = (0u32..)
+            .find_map(|counter| {
+                hash_to_scalar(
+                    DLEQ_NONCE_TAG,
+                    &[
+                        &k[..],
+                        &encryption_key.serialize(),
+                        &r_prime.serialize(),
+                        &counter.to_be_bytes(),
+                    ],
+                )
+            })
+            .expect("a valid nonce is found with overwhelming probability");
+
+        let a_g = PublicKey::from_secret_key(secp, &a);
+        let a_y = mul_point(secp, encryption_key, &a);
+        let challenge = challenge(encryption_key, r, r_prime, &a_g, &a_y);
+
+        // z = a + c * k
+        let mut response = challenge;
+        response
+            .mul_assign(&k[..])
+            .expect("product of two valid scalars is valid");
+        response
+            .add_assign(&a[..])
+            .expect("sum is not zero with overwhelming probability");
+
+        DleqProof {
+            challenge,
+            response,
+        }
+    }
+
+    fn verify<C: secp256k1::Verification>(
+        &self,
+        secp: &Secp256k1<C>,
+        encryption_key: &PublicKey,
+        r: &PublicKey,
+        r_prime: &PublicKey,
+    ) -> Result<(), Error> {
+        // A_G = z * G - c * R
+        let mut c_r = mul_point(secp, r, &self.challenge);
+        c_r.negate_assign(secp);
+        let a_g = mul_point(secp, &generator(), &self.response)
+            .combine(&c_r)
+            .map_err(|_| Error::InvalidProof)?;
+
+        // A_Y = z * Y - c * R'
+        let mut c_r_prime = mul_point(secp, r_prime, &self.challenge);
+        c_r_prime.negate_assign(secp);
+        let a_y = mul_point(secp, encryption_key, &self.response)
+            .combine(&c_r_prime)
+            .map_err(|_| Error::InvalidProof)?;
+
+        if challenge(encryption_key, r, r_prime, &a_g, &a_y) != self.challenge {
+            return Err(Error::InvalidProof);
+        }
+
+        Ok(())
+    }
+}
+
+/// A 2-of-2 multisig P2WSH output.
+///
+/// The public keys are sorted so both parties compute the same script
+/// regardless of the order in which they pass the keys.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TwoOfTwo {
+    public_keys: [PublicKey; 2],
+}
+
+impl TwoOfTwo {
+    pub fn new(public_key_1: PublicKey, public_key_2: PublicKey) -> Self {
+        let public_keys = if public_key_1.serialize() <= public_key_2.serialize() {
+            [public_key_1, public_key_2]
+        } else {
+            [public_key_2, public_key_1]
+        };
+
+        TwoOfTwo { public_keys }
+    }
+
+    pub fn compute_address(&self, network: Network) -> Address {
+        Address::p2wsh(&self.script(), network)
+    }
+
+    /// The parameters needed to compute the signature hash of a transaction
+    /// spending this output, through `PrimedTransaction::signature_hash`,
+    /// before any signature is known.
+    ///
+    /// These parameters cannot be used to sign the transaction.
+    pub fn sighash_parameters(self) -> UnlockParameters {
+        UnlockParameters {
+            witness: vec![],
+            sequence: SEQUENCE_ALLOW_NTIMELOCK_NO_RBF,
+            locktime: 0,
+            prev_script: self.into_script(),
+        }
+    }
+
+    /// Unlocks the output with our `secret_key` and a signature of the
+    /// counterparty, usually the result of `EncryptedSignature::decrypt`.
+    pub fn unlock_with_signature<C: secp256k1::Signing>(
+        self,
+        secp: &Secp256k1<C>,
+        secret_key: SecretKey,
+        counterparty_signature: Signature,
+    ) -> Result<UnlockParameters, Error> {
+        let public_key = PublicKey::from_secret_key(secp, &secret_key);
+
+        let mut counterparty_signature = counterparty_signature.serialize_der().to_vec();
+        counterparty_signature.push(SigHashType::All as u8);
+
+        let own = Witness::Signature(secret_key.into());
+        let counterparty = Witness::Data(counterparty_signature);
+
+        let signatures = match self.position(&public_key)? {
+            0 => vec![own, counterparty],
+            _ => vec![counterparty, own],
+        };
+
+        let mut witness = Vec::with_capacity(4);
+        // CHECKMULTISIG consumes an extra element from the stack
+        witness.push(Witness::Empty);
+        witness.extend(signatures);
+        witness.push(Witness::PrevScript);
+
+        Ok(UnlockParameters {
+            witness,
+            sequence: SEQUENCE_ALLOW_NTIMELOCK_NO_RBF,
+            locktime: 0,
+            prev_script: self.into_script(),
+        })
+    }
+
+    /// Extracts the signature of `public_key` from a transaction spending this
+    /// output, to be passed to `EncryptedSignature::recover`.
+    pub fn extract_signature(
+        &self,
+        transaction: &Transaction,
+        input_index: usize,
+        public_key: &PublicKey,
+    ) -> Result<Signature, Error> {
+        let position = self.position(public_key)?;
+
+        transaction
+            .input
+            .get(input_index)
+            .and_then(|input| input.witness.get(position + 1))
+            .and_then(|signature| signature.split_last())
+            .and_then(|(_sighash_type, der)| Signature::from_der(der).ok())
+            .ok_or(Error::UnrelatedSignature)
+    }
+
+    fn position(&self, public_key: &PublicKey) -> Result<usize, Error> {
+        self.public_keys
+            .iter()
+            .position(|key| key == public_key)
+            .ok_or(Error::UnknownKey)
+    }
+
+    fn script(&self) -> Script {
+        Builder::new()
+            .push_opcode(OP_PUSHNUM_2)
+            .push_slice(&self.public_keys[0].serialize())
+            .push_slice(&self.public_keys[1].serialize())
+            .push_opcode(OP_PUSHNUM_2)
+            .push_opcode(OP_CHECKMULTISIG)
+            .into_script()
+    }
+
+    fn into_script(self) -> Script {
+        self.script()
+    }
+}
+
+fn challenge(
+    encryption_key: &PublicKey,
+    r: &PublicKey,
+    r_prime: &PublicKey,
+    a_g: &PublicKey,
+    a_y: &PublicKey,
+) -> SecretKey {
+    hash_to_scalar(
+        DLEQ_CHALLENGE_TAG,
+        &[
+            &encryption_key.serialize(),
+            &r.serialize(),
+            &r_prime.serialize(),
+            &a_g.serialize(),
+            &a_y.serialize(),
+        ],
+    )
+    .expect("hash is a valid scalar with overwhelming probability")
+}
+
+fn hash_to_scalar(tag: &[u8], data: &[&[u8]]) -> Option<SecretKey> {
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag);
+    for item in data {
+        engine.input(item);
+    }
+    let hash = sha256::Hash::from_engine(engine);
+
+    SecretKey::from_slice(&reduce(hash.into_inner())).ok()
+}
+
+fn generator() -> PublicKey {
+    PublicKey::from_slice(&GENERATOR).expect("generator is a valid point")
+}
+
+fn mul_point<C: secp256k1::Verification>(
+    secp: &Secp256k1<C>,
+    point: &PublicKey,
+    scalar: &SecretKey,
+) -> PublicKey {
+    let mut point = *point;
+    point
+        .mul_assign(secp, &scalar[..])
+        .expect("valid scalar times a valid point is a valid point");
+    point
+}
+
+fn message_bytes(message: &Message) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&message[..]);
+    bytes
+}
+
+/// The x-coordinate of `point` reduced modulo the group order, as used for
+/// the `r` value of ECDSA signatures.
+fn x_coordinate_scalar(point: &PublicKey) -> Option<SecretKey> {
+    let mut x = [0u8; 32];
+    x.copy_from_slice(&point.serialize()[1..]);
+
+    SecretKey::from_slice(&reduce(x)).ok()
+}
+
+/// Reduces a 256 bit big endian number modulo the group order.
+///
+/// A single subtraction is enough because 2^256 < 2 * ORDER.
+fn reduce(mut bytes: [u8; 32]) -> [u8; 32] {
+    if bytes < ORDER {
+        return bytes;
+    }
+
+    let mut borrow = 0i16;
+    for (byte, order_byte) in bytes.iter_mut().zip(ORDER.iter()).rev() {
+        let mut difference = i16::from(*byte) - i16::from(*order_byte) - borrow;
+        borrow = if difference < 0 {
+            difference += 256;
+            1
+        } else {
+            0
+        };
+        *byte = u8::try_from(difference).expect("difference is in byte range");
+    }
+
+    bytes
+}
+
+/// Computes the multiplicative inverse of a scalar through Fermat's little
+/// theorem.
+fn invert(scalar: &SecretKey) -> SecretKey {
+    let mut one = [0u8; 32];
+    one[31] = 1;
+    let mut result = SecretKey::from_slice(&one).expect("one is a valid scalar");
+
+    for byte in ORDER_MINUS_TWO.iter() {
+        for bit in (0..8).rev() {
+            let current = result;
+            result
+                .mul_assign(&current[..])
+                .expect("product of two valid scalars is valid");
+
+            if (byte >> bit) & 1 == 1 {
+                result
+                    .mul_assign(&scalar[..])
+                    .expect("product of two valid scalars is valid");
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin::witness::{PrimedInput, PrimedTransaction};
+    use rust_bitcoin::{hashes::sha256d, Amount, OutPoint};
+    use std::str::FromStr;
+
+    fn secret_key(byte: u8) -> SecretKey {
+        SecretKey::from_slice(&[byte; 32]).unwrap()
+    }
+
+    #[test]
+    fn decrypted_signature_is_valid_and_reveals_decryption_key() {
+        let secp = Secp256k1::new();
+        let signing_key = secret_key(1);
+        let verification_key = PublicKey::from_secret_key(&secp, &signing_key);
+        let decryption_key = secret_key(2);
+        let encryption_key = PublicKey::from_secret_key(&secp, &decryption_key);
+        let message = Message::from_slice(&[42u8; 32]).unwrap();
+
+        let encrypted_signature =
+            EncryptedSignature::encrypt(&secp, &signing_key, &message, &encryption_key);
+
+        assert_eq!(
+            encrypted_signature.verify(&secp, &verification_key, &message, &encryption_key),
+            Ok(())
+        );
+
+        let signature = encrypted_signature.decrypt(&decryption_key);
+        assert!(secp.verify(&message, &signature, &verification_key).is_ok());
+
+        let recovered_key = encrypted_signature
+            .recover(&secp, &signature, &encryption_key)
+            .unwrap();
+        assert_eq!(recovered_key, decryption_key);
+    }
+
+    #[test]
+    fn encrypted_signature_round_trips_through_bytes() {
+        let secp = Secp256k1::new();
+        let encryption_key = PublicKey::from_secret_key(&secp, &secret_key(2));
+        let message = Message::from_slice(&[42u8; 32]).unwrap();
+
+        let encrypted_signature =
+            EncryptedSignature::encrypt(&secp, &secret_key(1), &message, &encryption_key);
+
+        assert_eq!(
+            EncryptedSignature::from_bytes(&encrypted_signature.to_bytes()),
+            Ok(encrypted_signature)
+        );
+    }
+
+    #[test]
+    fn malformed_encrypted_signatures_are_rejected() {
+        let secp = Secp256k1::new();
+        let encryption_key = PublicKey::from_secret_key(&secp, &secret_key(2));
+        let message = Message::from_slice(&[42u8; 32]).unwrap();
+        let bytes = EncryptedSignature::encrypt(&secp, &secret_key(1), &message, &encryption_key)
+            .to_bytes();
+
+        let mut not_a_point = bytes;
+        not_a_point[33] = 0x04;
+        let mut zero_scalar = bytes;
+        zero_scalar[66..98].copy_from_slice(&[0u8; 32]);
+        let mut scalar_above_order = bytes;
+        scalar_above_order[130..].copy_from_slice(&[0xffu8; 32]);
+
+        for malformed in &[
+            &bytes[1..],
+            &[&bytes[..], &[0u8]].concat()[..],
+            &not_a_point[..],
+            &zero_scalar[..],
+            &scalar_above_order[..],
+        ] {
+            assert_eq!(
+                EncryptedSignature::from_bytes(malformed),
+                Err(Error::MalformedEncryptedSignature)
+            );
+        }
+    }
+
+    #[test]
+    fn encrypted_signature_does_not_verify_for_another_encryption_key() {
+        let secp = Secp256k1::new();
+        let signing_key = secret_key(1);
+        let verification_key = PublicKey::from_secret_key(&secp, &signing_key);
+        let encryption_key = PublicKey::from_secret_key(&secp, &secret_key(2));
+        let other_encryption_key = PublicKey::from_secret_key(&secp, &secret_key(3));
+        let message = Message::from_slice(&[42u8; 32]).unwrap();
+
+        let encrypted_signature =
+            EncryptedSignature::encrypt(&secp, &signing_key, &message, &encryption_key);
+
+        assert_eq!(
+            encrypted_signature.verify(&secp, &verification_key, &message, &other_encryption_key),
+            Err(Error::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn encrypted_signature_does_not_verify_for_another_message() {
+        let secp = Secp256k1::new();
+        let signing_key = secret_key(1);
+        let verification_key = PublicKey::from_secret_key(&secp, &signing_key);
+        let encryption_key = PublicKey::from_secret_key(&secp, &secret_key(2));
+        let message = Message::from_slice(&[42u8; 32]).unwrap();
+        let other_message = Message::from_slice(&[43u8; 32]).unwrap();
+
+        let encrypted_signature =
+            EncryptedSignature::encrypt(&secp, &signing_key, &message, &encryption_key);
+
+        assert_eq!(
+            encrypted_signature.verify(&secp, &verification_key, &other_message, &encryption_key),
+            Err(Error::InvalidEncryptedSignature)
+        );
+    }
+
+    #[test]
+    fn redeem_two_of_two_with_decrypted_signature() {
+        let secp = Secp256k1::new();
+        let alice_secret_key = secret_key(1);
+        let alice_public_key = PublicKey::from_secret_key(&secp, &alice_secret_key);
+        let bob_secret_key = secret_key(2);
+        let bob_public_key = PublicKey::from_secret_key(&secp, &bob_secret_key);
+        let decryption_key = secret_key(3);
+        let encryption_key = PublicKey::from_secret_key(&secp, &decryption_key);
+
+        let two_of_two = TwoOfTwo::new(alice_public_key, bob_public_key);
+        let outpoint = OutPoint {
+            txid: sha256d::Hash::default().into(),
+            vout: 0,
+        };
+        let value = Amount::from_sat(100_000_000);
+        let fee = Amount::from_sat(1_000);
+        let output_address =
+            Address::from_str("bc1q87v7fjxcs29xvtz8kdu79u2tjfn3ppu0c3e6cl").unwrap();
+
+        // Bob encrypts his signature of the redeem transaction to Alice's point
+        let message = PrimedTransaction {
+            inputs: vec![PrimedInput::new(
+                outpoint,
+                value,
+                two_of_two.sighash_parameters(),
+            )],
+            output_address: output_address.clone(),
+        }
+        .signature_hash(0, fee)
+        .unwrap();
+        let encrypted_signature =
+            EncryptedSignature::encrypt(&secp, &bob_secret_key, &message, &encryption_key);
+
+        // Alice verifies and decrypts it, then redeems
+        encrypted_signature
+            .verify(&secp, &bob_public_key, &message, &encryption_key)
+            .unwrap();
+        let bob_signature = encrypted_signature.decrypt(&decryption_key);
+        let redeem_transaction = PrimedTransaction {
+            inputs: vec![PrimedInput::new(
+                outpoint,
+                value,
+                two_of_two
+                    .unlock_with_signature(&secp, alice_secret_key, bob_signature)
+                    .unwrap(),
+            )],
+            output_address,
+        }
+        .sign_with_fee(&secp, fee);
+
+        let alice_signature = two_of_two
+            .extract_signature(&redeem_transaction, 0, &alice_public_key)
+            .unwrap();
+        assert!(secp
+            .verify(&message, &alice_signature, &alice_public_key)
+            .is_ok());
+
+        // Bob learns Alice's secret from the published transaction
+        let published_signature = two_of_two
+            .extract_signature(&redeem_transaction, 0, &bob_public_key)
+            .unwrap();
+        let recovered_key = encrypted_signature
+            .recover(&secp, &published_signature, &encryption_key)
+            .unwrap();
+        assert_eq!(recovered_key, decryption_key);
+    }
+
+    #[test]
+    fn reduce_subtracts_order_once() {
+        let mut order_plus_one = ORDER;
+        order_plus_one[31] += 1;
+        let mut one = [0u8; 32];
+        one[31] = 1;
+
+        assert_eq!(reduce(order_plus_one), one);
+        assert_eq!(reduce(one), one);
+    }
+}
@@ -0,0 +1,210 @@
+//! Contracts of the `atomicswap` tooling used by Decred and many altcoins.
+//!
+//! These contracts have the same layout as hbit contracts, but encode their
+//! locktime as a minimal script number and are locked in P2SH outputs. They
+//! are spent with the `UnlockParameters` of `hbit::Htlc` in a
+//! `chain::ChainTransaction` using `ChainParams::without_segwit`.
+use crate::bitcoin::{
+    chain::ChainParams,
+    hbit::{Htlc, UnknownContract},
+    Identity,
+};
+use rust_bitcoin::{
+    blockdata::{opcodes::all::*, script::Builder},
+    Script,
+};
+
+const SECRET_LEN: i64 = 32;
+
+/// The parameters of a contract, as reported by the `auditcontract` command
+/// of the `atomicswap` tooling.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Audit {
+    pub contract_address: String,
+    pub secret_hash: [u8; 32],
+    pub recipient_identity: Identity,
+    pub refund_identity: Identity,
+    /// A block height or a timestamp, like a transaction locktime.
+    pub locktime: u32,
+}
+
+/// Builds the contract the `initiate` and `participate` commands of the
+/// `atomicswap` tooling build.
+pub fn contract(
+    secret_hash: [u8; 32],
+    recipient_identity: Identity,
+    refund_identity: Identity,
+    locktime: u32,
+) -> Htlc {
+    let script = Builder::new()
+        .push_opcode(OP_IF)
+        .push_opcode(OP_SIZE)
+        .push_int(SECRET_LEN)
+        .push_opcode(OP_EQUALVERIFY)
+        .push_opcode(OP_SHA256)
+        .push_slice(&secret_hash)
+        .push_opcode(OP_EQUALVERIFY)
+        .push_opcode(OP_DUP)
+        .push_opcode(OP_HASH160)
+        .push_slice(&recipient_identity.pubkey_hash())
+        .push_opcode(OP_ELSE)
+        .push_int(i64::from(locktime))
+        .push_opcode(OP_CLTV)
+        .push_opcode(OP_DROP)
+        .push_opcode(OP_DUP)
+        .push_opcode(OP_HASH160)
+        .push_slice(&refund_identity.pubkey_hash())
+        .push_opcode(OP_ENDIF)
+        .push_opcode(OP_EQUALVERIFY)
+        .push_opcode(OP_CHECKSIG)
+        .into_script();
+
+    Htlc::from_script(&script).expect("contract is an hbit contract")
+}
+
+/// The P2SH address of the contract on `chain`.
+pub fn contract_address(contract: &Htlc, chain: &ChainParams) -> String {
+    chain.without_segwit().script_address(&contract.script())
+}
+
+/// The P2SH output script locking funds in the contract.
+pub fn contract_script_pubkey(contract: &Htlc) -> Script {
+    contract.script().to_p2sh()
+}
+
+/// Reports the parameters of a contract received from a counterparty, to be
+/// checked before funding the other side of the swap.
+pub fn audit(contract: &Script, chain: &ChainParams) -> Result<Audit, UnknownContract> {
+    let htlc = Htlc::from_script(contract)?;
+
+    Ok(Audit {
+        contract_address: contract_address(&htlc, chain),
+        secret_hash: htlc.secret_hash(),
+        recipient_identity: htlc.redeem_identity(),
+        refund_identity: htlc.refund_identity(),
+        locktime: htlc.expiry(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin::chain::{BITCOIN, LITECOIN};
+    use hex_literal::hex;
+    use rust_bitcoin::{
+        hashes::{hash160, Hash},
+        network::constants::Network,
+        secp256k1::{Secp256k1, SecretKey},
+        Address,
+    };
+
+    const SECRET_HASH: [u8; 32] = [9u8; 32];
+
+    fn identity(byte: u8) -> Identity {
+        Identity::from_secret_key(
+            &Secp256k1::signing_only(),
+            &SecretKey::from_slice(&[byte; 32]).unwrap(),
+        )
+    }
+
+    fn contract_bytes(locktime_push: &[u8]) -> Vec<u8> {
+        [
+            &hex!("6382012088a820")[..],
+            &SECRET_HASH,
+            &hex!("8876a914"),
+            &identity(1).pubkey_hash(),
+            &hex!("67"),
+            locktime_push,
+            &hex!("b17576a914"),
+            &identity(2).pubkey_hash(),
+            &hex!("6888ac"),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn block_height_locktime_is_a_minimal_script_number() {
+        let contract = contract(SECRET_HASH, identity(1), identity(2), 650_000);
+
+        assert_eq!(
+            contract.script().into_bytes(),
+            contract_bytes(&hex!("0310eb09"))
+        );
+    }
+
+    #[test]
+    fn timestamp_contract_is_hbit_contract() {
+        let contract = contract(SECRET_HASH, identity(1), identity(2), 1_600_000_000);
+        let htlc = Htlc::new(1_600_000_000, identity(2), identity(1), SECRET_HASH);
+
+        assert_eq!(contract.script(), htlc.script());
+        assert_eq!(
+            contract.script().into_bytes(),
+            contract_bytes(&hex!("0400105e5f"))
+        );
+    }
+
+    #[test]
+    fn small_locktime_is_a_number_opcode() {
+        let one = contract(SECRET_HASH, identity(1), identity(2), 1);
+        let sixteen = contract(SECRET_HASH, identity(1), identity(2), 16);
+
+        assert_eq!(one.script().into_bytes(), contract_bytes(&hex!("51")));
+        assert_eq!(one.expiry(), 1);
+        assert_eq!(sixteen.script().into_bytes(), contract_bytes(&hex!("60")));
+        assert_eq!(sixteen.expiry(), 16);
+    }
+
+    #[test]
+    fn locktime_past_i32_max_is_a_five_byte_script_number() {
+        let contract = contract(SECRET_HASH, identity(1), identity(2), 1 << 31);
+
+        assert_eq!(
+            contract.script().into_bytes(),
+            contract_bytes(&hex!("050000008000"))
+        );
+        assert_eq!(contract.expiry(), 1 << 31);
+    }
+
+    #[test]
+    fn audit_reports_contract_parameters() {
+        let script = Script::from(contract_bytes(&hex!("0310eb09")));
+
+        let audit = audit(&script, &LITECOIN).unwrap();
+
+        assert_eq!(
+            audit,
+            Audit {
+                contract_address: LITECOIN.without_segwit().script_address(&script),
+                secret_hash: SECRET_HASH,
+                recipient_identity: identity(1),
+                refund_identity: identity(2),
+                locktime: 650_000,
+            }
+        );
+        assert!(audit.contract_address.starts_with('M'));
+    }
+
+    #[test]
+    fn contract_address_is_p2sh() {
+        let contract = contract(SECRET_HASH, identity(1), identity(2), 650_000);
+
+        assert_eq!(
+            contract_address(&contract, &BITCOIN),
+            Address::p2sh(&contract.script(), Network::Bitcoin).to_string()
+        );
+        assert_eq!(
+            contract_script_pubkey(&contract),
+            Script::new_p2sh(&hash160::Hash::hash(contract.script().as_bytes()).into())
+        );
+    }
+
+    #[test]
+    fn other_scripts_are_not_contracts() {
+        let mut bytes = contract_bytes(&hex!("0310eb09"));
+        // Replace OP_SHA256 by OP_HASH256
+        bytes[5] = 0xaa;
+
+        assert_eq!(audit(&Script::from(bytes), &BITCOIN), Err(UnknownContract));
+    }
+}
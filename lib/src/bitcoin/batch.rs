@@ -0,0 +1,374 @@
+//! Planning the batched redeem and refund of many HTLCs.
+//!
+//! A transaction can only be mined once its locktime, the highest locktime
+//! of its inputs, has passed. Batching an input which is time-critical, such
+//! as a redeem that has to be mined before the counterparty can refund, with
+//! an input whose locktime is later would delay the former past its
+//! deadline. The planner only batches inputs whose locktimes and deadlines
+//! are compatible and returns the fewest transactions that satisfy all of
+//! them.
+use crate::bitcoin::witness::{PrimedInput, PrimedTransaction, SEQUENCE_DISALLOW_NTIMELOCK_NO_RBF};
+use rust_bitcoin::Address;
+use std::fmt;
+
+/// Locktimes below this value are block heights, above it they are
+/// timestamps.
+pub const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Error {
+    /// The input has a locktime but its sequence disables locktime checks.
+    LocktimeDisabled { spend_index: usize },
+    /// The deadline is a block height and the locktime a timestamp, or the
+    /// other way around.
+    DeadlineUnitMismatch { spend_index: usize },
+    /// The input only becomes valid at or after its deadline.
+    DeadlineBeforeLocktime { spend_index: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::LocktimeDisabled { spend_index } => write!(
+                f,
+                "spend {} has a locktime but its sequence disables it",
+                spend_index
+            ),
+            Error::DeadlineUnitMismatch { spend_index } => write!(
+                f,
+                "deadline and locktime of spend {} do not have the same unit",
+                spend_index
+            ),
+            Error::DeadlineBeforeLocktime { spend_index } => write!(
+                f,
+                "spend {} cannot be mined before its deadline",
+                spend_index
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// An HTLC input to be spent, e.g. from `hbit::Htlc::unlock_with_secret` or
+/// `hbit::Htlc::unlock_after_timeout`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spend {
+    pub input: PrimedInput,
+    /// The block height or timestamp, encoded like a locktime, before which
+    /// the spend must be mined, e.g. the expiry of the counterparty's refund
+    /// path for a redeem.
+    pub deadline: Option<u32>,
+}
+
+/// A transaction of the plan.
+#[derive(Clone, Debug)]
+pub struct Batch {
+    pub transaction: PrimedTransaction,
+    /// The locktime of the transaction, before which it cannot be mined.
+    pub locktime: u32,
+    /// The earliest deadline of the batched spends.
+    pub deadline: Option<u32>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Unit {
+    BlockHeight,
+    Timestamp,
+}
+
+impl Unit {
+    fn of(locktime: u32) -> Option<Self> {
+        match locktime {
+            0 => None,
+            locktime if locktime < LOCKTIME_THRESHOLD => Some(Unit::BlockHeight),
+            _ => Some(Unit::Timestamp),
+        }
+    }
+}
+
+/// Groups `spends` in the fewest transactions paying to `output_address`,
+/// such that every spend can be mined before its deadline, and returns them
+/// ordered by deadline, most urgent first.
+pub fn plan(spends: Vec<Spend>, output_address: Address) -> Result<Vec<Batch>, Error> {
+    let mut by_unit: [Vec<(usize, Spend)>; 2] = [Vec::new(), Vec::new()];
+    let mut unconstrained = Vec::new();
+
+    for (spend_index, spend) in spends.into_iter().enumerate() {
+        let locktime = spend.input.locktime();
+
+        if locktime != 0 && spend.input.sequence() == SEQUENCE_DISALLOW_NTIMELOCK_NO_RBF {
+            return Err(Error::LocktimeDisabled { spend_index });
+        }
+
+        let unit = match (Unit::of(locktime), spend.deadline.and_then(Unit::of)) {
+            (Some(locktime_unit), Some(deadline_unit)) if locktime_unit != deadline_unit => {
+                return Err(Error::DeadlineUnitMismatch { spend_index })
+            }
+            (Some(unit), _) | (None, Some(unit)) => unit,
+            (None, None) => {
+                unconstrained.push(spend);
+                continue;
+            }
+        };
+
+        match spend.deadline {
+            Some(deadline) if deadline <= locktime => {
+                return Err(Error::DeadlineBeforeLocktime { spend_index })
+            }
+            _ => {}
+        }
+
+        match unit {
+            Unit::BlockHeight => by_unit[0].push((spend_index, spend)),
+            Unit::Timestamp => by_unit[1].push((spend_index, spend)),
+        }
+    }
+
+    let mut groups = by_unit
+        .iter_mut()
+        .flat_map(|spends| group(std::mem::take(spends)))
+        .collect::<Vec<_>>();
+
+    // Spends without locktime or deadline join the batch that is valid first
+    match groups.iter_mut().min_by_key(|group| locktime(group)) {
+        Some(group) => group.extend(unconstrained),
+        None if !unconstrained.is_empty() => groups.push(unconstrained),
+        None => {}
+    }
+
+    let mut batches = groups
+        .into_iter()
+        .map(|spends| Batch {
+            locktime: locktime(&spends),
+            deadline: spends.iter().filter_map(|spend| spend.deadline).min(),
+            transaction: PrimedTransaction {
+                inputs: spends.into_iter().map(|spend| spend.input).collect(),
+                output_address: output_address.clone(),
+            },
+        })
+        .collect::<Vec<_>>();
+
+    batches.sort_by_key(|batch| (batch.deadline.unwrap_or(u32::MAX), batch.locktime));
+
+    Ok(batches)
+}
+
+/// Groups spends of the same unit in the fewest groups whose locktime is
+/// before the deadline of every member.
+///
+/// Each spend can be mined in the interval between its locktime and its
+/// deadline; this is the classic minimum set of points stabbing all
+/// intervals, solved greedily by picking the earliest deadline of the spends
+/// left as the next point.
+fn group(mut spends: Vec<(usize, Spend)>) -> Vec<Vec<Spend>> {
+    spends.sort_by_key(|(spend_index, spend)| (spend.deadline.unwrap_or(u32::MAX), *spend_index));
+
+    let mut groups = Vec::new();
+    while !spends.is_empty() {
+        // If the first spend has no deadline, none of the remaining ones has
+        let deadline = spends[0].1.deadline;
+
+        let (group, rest): (Vec<_>, Vec<_>) = spends.into_iter().partition(|(_, spend)| {
+            deadline.is_none_or(|deadline| spend.input.locktime() < deadline)
+        });
+
+        groups.push(group.into_iter().map(|(_, spend)| spend).collect());
+        spends = rest;
+    }
+
+    groups
+}
+
+fn locktime(spends: &[Spend]) -> u32 {
+    spends
+        .iter()
+        .map(|spend| spend.input.locktime())
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin::{hbit::Htlc, Identity};
+    use rust_bitcoin::{
+        hashes::sha256d,
+        secp256k1::{Secp256k1, SecretKey},
+        Amount, OutPoint,
+    };
+    use std::str::FromStr;
+
+    const NOW: u32 = 1_600_000_000;
+
+    fn secret_key() -> SecretKey {
+        SecretKey::from_slice(&[1u8; 32]).unwrap()
+    }
+
+    fn htlc(expiry: u32) -> Htlc {
+        let identity = Identity::from_secret_key(&Secp256k1::signing_only(), &secret_key());
+
+        Htlc::new(expiry, identity, identity, [0u8; 32])
+    }
+
+    fn outpoint(vout: u32) -> OutPoint {
+        OutPoint {
+            txid: sha256d::Hash::default().into(),
+            vout,
+        }
+    }
+
+    fn redeem(vout: u32, deadline: Option<u32>) -> Spend {
+        let parameters = htlc(NOW)
+            .unlock_with_secret(&Secp256k1::signing_only(), secret_key(), [0u8; 32])
+            .unwrap();
+
+        Spend {
+            input: PrimedInput::new(outpoint(vout), Amount::from_sat(100_000), parameters),
+            deadline,
+        }
+    }
+
+    fn refund(vout: u32, expiry: u32, deadline: Option<u32>) -> Spend {
+        let parameters = htlc(expiry)
+            .unlock_after_timeout(&Secp256k1::signing_only(), secret_key())
+            .unwrap();
+
+        Spend {
+            input: PrimedInput::new(outpoint(vout), Amount::from_sat(100_000), parameters),
+            deadline,
+        }
+    }
+
+    fn output_address() -> Address {
+        Address::from_str("bc1q87v7fjxcs29xvtz8kdu79u2tjfn3ppu0c3e6cl").unwrap()
+    }
+
+    fn vouts(batch: &Batch) -> Vec<u32> {
+        batch
+            .transaction
+            .inputs
+            .iter()
+            .map(|input| input.to_txin_without_signature().previous_output.vout)
+            .collect()
+    }
+
+    #[test]
+    fn redeem_is_not_delayed_behind_later_refund() {
+        let batches = plan(
+            vec![
+                refund(0, NOW + 7_200, None),
+                redeem(1, Some(NOW + 3_600)),
+                redeem(2, None),
+            ],
+            output_address(),
+        )
+        .unwrap();
+
+        assert_eq!(batches.len(), 2);
+        assert_eq!(vouts(&batches[0]), vec![1, 2]);
+        assert_eq!(batches[0].locktime, 0);
+        assert_eq!(batches[0].deadline, Some(NOW + 3_600));
+        assert_eq!(vouts(&batches[1]), vec![0]);
+        assert_eq!(batches[1].locktime, NOW + 7_200);
+    }
+
+    #[test]
+    fn compatible_spends_are_batched_together() {
+        let batches = plan(
+            vec![
+                refund(0, NOW, Some(NOW + 7_200)),
+                redeem(1, Some(NOW + 3_600)),
+                refund(2, NOW + 1_800, None),
+            ],
+            output_address(),
+        )
+        .unwrap();
+
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].locktime, NOW + 1_800);
+        assert_eq!(batches[0].deadline, Some(NOW + 3_600));
+    }
+
+    #[test]
+    fn block_height_and_timestamp_locktimes_are_not_mixed() {
+        let batches = plan(
+            vec![refund(0, 650_000, None), refund(1, NOW, None)],
+            output_address(),
+        )
+        .unwrap();
+
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].locktime, 650_000);
+        assert_eq!(batches[1].locktime, NOW);
+    }
+
+    #[test]
+    fn mismatched_deadline_unit_is_rejected() {
+        let result = plan(vec![refund(0, NOW, Some(650_000))], output_address());
+
+        assert_eq!(
+            result.unwrap_err(),
+            Error::DeadlineUnitMismatch { spend_index: 0 }
+        );
+    }
+
+    #[test]
+    fn deadline_before_locktime_is_rejected() {
+        let result = plan(
+            vec![redeem(0, None), refund(1, NOW, Some(NOW - 1))],
+            output_address(),
+        );
+
+        assert_eq!(
+            result.unwrap_err(),
+            Error::DeadlineBeforeLocktime { spend_index: 1 }
+        );
+    }
+
+    #[test]
+    fn deadline_at_locktime_is_rejected() {
+        let result = plan(vec![refund(0, NOW, Some(NOW))], output_address());
+
+        assert_eq!(
+            result.unwrap_err(),
+            Error::DeadlineBeforeLocktime { spend_index: 0 }
+        );
+    }
+
+    #[test]
+    fn spend_is_not_batched_with_locktime_at_its_deadline() {
+        let batches = plan(
+            vec![
+                refund(0, NOW + 3_600, None),
+                redeem(1, Some(NOW + 3_600)),
+                refund(2, NOW + 3_599, None),
+            ],
+            output_address(),
+        )
+        .unwrap();
+
+        assert_eq!(batches.len(), 2);
+        assert_eq!(vouts(&batches[0]), vec![1, 2]);
+        assert_eq!(batches[0].locktime, NOW + 3_599);
+        assert_eq!(vouts(&batches[1]), vec![0]);
+        assert_eq!(batches[1].locktime, NOW + 3_600);
+    }
+
+    #[test]
+    fn disabled_locktime_is_rejected() {
+        let mut spend = refund(0, NOW, None);
+        let mut parameters = htlc(NOW)
+            .unlock_after_timeout(&Secp256k1::signing_only(), secret_key())
+            .unwrap();
+        parameters.sequence = SEQUENCE_DISALLOW_NTIMELOCK_NO_RBF;
+        spend.input = PrimedInput::new(outpoint(0), Amount::from_sat(100_000), parameters);
+
+        let result = plan(vec![spend], output_address());
+
+        assert_eq!(
+            result.unwrap_err(),
+            Error::LocktimeDisabled { spend_index: 0 }
+        );
+    }
+}
@@ -0,0 +1,617 @@
+//! Parameters of the chains derived from Bitcoin on which hbit can be used.
+//!
+//! The hbit script only relies on opcodes all these chains share. They differ
+//! in how outputs paying to a script are encoded and in how transactions are
+//! signed: chains with segwit lock the script in a P2WSH output and sign with
+//! BIP143, the others lock it in a P2SH output and sign with the original
+//! algorithm, or with BIP143 and `SIGHASH_FORKID` on Bitcoin Cash.
+use crate::bitcoin::{
+    witness::{
+        sign_inputs, size::PLACEHOLDER_SIGNATURE_LEN, Error, PrimedInput, SigningKey, Witness,
+    },
+    Identity,
+};
+use rust_bitcoin::{
+    bech32::{self, u5, ToBase32},
+    blockdata::script::Builder,
+    hashes::{hash160, sha256, sha256d, Hash},
+    secp256k1::{self, Message, Secp256k1},
+    util::{base58, bip143::SigHashCache},
+    Amount, Network, Script, SigHashType, Transaction, TxIn, TxOut,
+};
+
+/// The `SIGHASH_FORKID` flag with a fork id of 0.
+const SIGHASH_FORKID: u8 = 0x40;
+
+/// How outputs paying to a script are built, encoded and spent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpendRules {
+    /// P2WSH outputs with bech32 addresses, signed with BIP143.
+    Segwit { bech32_hrp: &'static str },
+    /// P2SH outputs with base58 addresses, signed with the original signature
+    /// hash algorithm.
+    Legacy,
+    /// P2SH outputs with CashAddr addresses, signed with BIP143 and
+    /// `SIGHASH_FORKID`.
+    ForkId { cashaddr_prefix: &'static str },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChainParams {
+    pub p2pkh_prefix: u8,
+    pub p2sh_prefix: u8,
+    pub rules: SpendRules,
+}
+
+pub const BITCOIN: ChainParams = ChainParams {
+    p2pkh_prefix: 0x00,
+    p2sh_prefix: 0x05,
+    rules: SpendRules::Segwit { bech32_hrp: "bc" },
+};
+
+pub const BITCOIN_TESTNET: ChainParams = ChainParams {
+    p2pkh_prefix: 0x6f,
+    p2sh_prefix: 0xc4,
+    rules: SpendRules::Segwit { bech32_hrp: "tb" },
+};
+
+pub const BITCOIN_REGTEST: ChainParams = ChainParams {
+    p2pkh_prefix: 0x6f,
+    p2sh_prefix: 0xc4,
+    rules: SpendRules::Segwit { bech32_hrp: "bcrt" },
+};
+
+pub const LITECOIN: ChainParams = ChainParams {
+    p2pkh_prefix: 0x30,
+    p2sh_prefix: 0x32,
+    rules: SpendRules::Segwit { bech32_hrp: "ltc" },
+};
+
+pub const LITECOIN_TESTNET: ChainParams = ChainParams {
+    p2pkh_prefix: 0x6f,
+    p2sh_prefix: 0x3a,
+    rules: SpendRules::Segwit { bech32_hrp: "tltc" },
+};
+
+pub const LITECOIN_REGTEST: ChainParams = ChainParams {
+    p2pkh_prefix: 0x6f,
+    p2sh_prefix: 0x3a,
+    rules: SpendRules::Segwit { bech32_hrp: "rltc" },
+};
+
+pub const DOGECOIN: ChainParams = ChainParams {
+    p2pkh_prefix: 0x1e,
+    p2sh_prefix: 0x16,
+    rules: SpendRules::Legacy,
+};
+
+pub const DOGECOIN_TESTNET: ChainParams = ChainParams {
+    p2pkh_prefix: 0x71,
+    p2sh_prefix: 0xc4,
+    rules: SpendRules::Legacy,
+};
+
+pub const BITCOIN_CASH: ChainParams = ChainParams {
+    p2pkh_prefix: 0x00,
+    p2sh_prefix: 0x05,
+    rules: SpendRules::ForkId {
+        cashaddr_prefix: "bitcoincash",
+    },
+};
+
+pub const BITCOIN_CASH_TESTNET: ChainParams = ChainParams {
+    p2pkh_prefix: 0x6f,
+    p2sh_prefix: 0xc4,
+    rules: SpendRules::ForkId {
+        cashaddr_prefix: "bchtest",
+    },
+};
+
+pub const BITCOIN_CASH_REGTEST: ChainParams = ChainParams {
+    p2pkh_prefix: 0x6f,
+    p2sh_prefix: 0xc4,
+    rules: SpendRules::ForkId {
+        cashaddr_prefix: "bchreg",
+    },
+};
+
+impl From<Network> for ChainParams {
+    fn from(network: Network) -> Self {
+        match network {
+            Network::Bitcoin => BITCOIN,
+            Network::Testnet => BITCOIN_TESTNET,
+            Network::Regtest => BITCOIN_REGTEST,
+        }
+    }
+}
+
+impl ChainParams {
+    /// The parameters to lock funds in P2SH outputs on this chain, e.g. to
+    /// use contracts of tooling which does not support segwit.
+    pub fn without_segwit(self) -> Self {
+        match self.rules {
+            SpendRules::Segwit { .. } => ChainParams {
+                rules: SpendRules::Legacy,
+                ..self
+            },
+            SpendRules::Legacy | SpendRules::ForkId { .. } => self,
+        }
+    }
+
+    /// The output script locking funds in `redeem_script`.
+    pub fn script_pubkey(&self, redeem_script: &Script) -> Script {
+        match self.rules {
+            SpendRules::Segwit { .. } => redeem_script.to_v0_p2wsh(),
+            SpendRules::Legacy | SpendRules::ForkId { .. } => redeem_script.to_p2sh(),
+        }
+    }
+
+    /// The address of the output locking funds in `redeem_script`.
+    pub fn script_address(&self, redeem_script: &Script) -> String {
+        match self.rules {
+            SpendRules::Segwit { bech32_hrp } => {
+                let program = sha256::Hash::hash(redeem_script.as_bytes());
+                let mut data = vec![u5::try_from_u8(0).expect("0 fits into 5 bits")];
+                data.extend(program.to_base32());
+
+                bech32::encode(bech32_hrp, data).expect("human readable part is valid")
+            }
+            SpendRules::Legacy => {
+                let mut prefixed = vec![self.p2sh_prefix];
+                prefixed.extend_from_slice(&hash160::Hash::hash(redeem_script.as_bytes()));
+
+                base58::check_encode_slice(&prefixed)
+            }
+            SpendRules::ForkId { cashaddr_prefix } => cashaddr::encode(
+                cashaddr_prefix,
+                cashaddr::SCRIPT_HASH,
+                &hash160::Hash::hash(redeem_script.as_bytes()),
+            ),
+        }
+    }
+
+    /// The address paying to the key behind `identity`, e.g. to redeem or
+    /// refund to: P2WPKH on segwit chains and P2PKH otherwise.
+    pub fn identity_address(&self, identity: &Identity) -> String {
+        let pubkey_hash = identity.pubkey_hash();
+
+        match self.rules {
+            SpendRules::Segwit { bech32_hrp } => {
+                let mut data = vec![u5::try_from_u8(0).expect("0 fits into 5 bits")];
+                data.extend(pubkey_hash.to_base32());
+
+                bech32::encode(bech32_hrp, data).expect("human readable part is valid")
+            }
+            SpendRules::Legacy => {
+                let mut prefixed = vec![self.p2pkh_prefix];
+                prefixed.extend_from_slice(&pubkey_hash);
+
+                base58::check_encode_slice(&prefixed)
+            }
+            SpendRules::ForkId { cashaddr_prefix } => {
+                cashaddr::encode(cashaddr_prefix, cashaddr::PUBKEY_HASH, &pubkey_hash)
+            }
+        }
+    }
+
+    fn sighash_type(&self, sighash_type: SigHashType) -> u8 {
+        match self.rules {
+            SpendRules::Segwit { .. } | SpendRules::Legacy => sighash_type as u8,
+            SpendRules::ForkId { .. } => sighash_type as u8 | SIGHASH_FORKID,
+        }
+    }
+
+    fn signature_hash(
+        &self,
+        transaction: &Transaction,
+        input_index: usize,
+        input: &PrimedInput,
+        sighash_type: SigHashType,
+    ) -> Message {
+        let prev_script = &input.unlock_parameters().prev_script;
+
+        let hash = match self.rules {
+            SpendRules::Segwit { .. } => SigHashCache::new(transaction)
+                .signature_hash(
+                    input_index,
+                    prev_script,
+                    input.value().as_sat(),
+                    sighash_type,
+                )
+                .into_inner(),
+            SpendRules::Legacy => transaction
+                .signature_hash(input_index, prev_script, sighash_type.as_u32())
+                .into_inner(),
+            SpendRules::ForkId { .. } => {
+                let mut data = Vec::new();
+                SigHashCache::new(transaction)
+                    .encode_signing_data_to(
+                        &mut data,
+                        input_index,
+                        prev_script,
+                        input.value().as_sat(),
+                        sighash_type,
+                    )
+                    .expect("writing to a vector does not fail");
+
+                // The digest only differs from BIP143 by the trailing hash type
+                let hash_type_position = data.len() - 4;
+                data[hash_type_position..]
+                    .copy_from_slice(&u32::from(self.sighash_type(sighash_type)).to_le_bytes());
+
+                sha256d::Hash::hash(&data).into_inner()
+            }
+        };
+
+        Message::from_slice(&hash).expect("Should not fail because it is a hash")
+    }
+}
+
+/// A transaction spending `inputs` to `output_script` on the given chain.
+///
+/// This is the counterpart of `PrimedTransaction` for chains other than
+/// Bitcoin: the same `UnlockParameters` are put in the witness on segwit
+/// chains and in the `script_sig` otherwise.
+#[derive(Clone, Debug)]
+pub struct ChainTransaction {
+    pub chain: ChainParams,
+    pub inputs: Vec<PrimedInput>,
+    pub output_script: Script,
+}
+
+impl ChainTransaction {
+    /// `fee_per_vbyte` is in the smallest unit of the chain per virtual byte.
+    pub fn sign_with_rate<C: secp256k1::Signing>(
+        self,
+        secp: &Secp256k1<C>,
+        fee_per_vbyte: Amount,
+    ) -> Result<Transaction, Error> {
+        let fee = fee_per_vbyte
+            .checked_mul(self.estimate_size() as u64)
+            .ok_or(Error::OverflowingFee)?;
+
+        self.sign_with_fee(secp, fee)
+    }
+
+    pub fn sign_with_fee<C: secp256k1::Signing>(
+        self,
+        secp: &Secp256k1<C>,
+        fee: Amount,
+    ) -> Result<Transaction, Error> {
+        if self.total_input_value() < fee {
+            return Err(Error::FeeHigherThanInputValue);
+        }
+
+        let mut transaction = self.transaction_without_signatures();
+        transaction.output[0].value = (self.total_input_value() - fee).as_sat();
+
+        match self.chain.rules {
+            SpendRules::Segwit { .. } => sign_inputs(secp, &self.inputs, &mut transaction),
+            SpendRules::Legacy | SpendRules::ForkId { .. } => {
+                let script_sigs = self
+                    .inputs
+                    .iter()
+                    .enumerate()
+                    .map(|(input_index, input)| {
+                        script_sig(input, |signing_key, sighash_type| {
+                            let message = self.chain.signature_hash(
+                                &transaction,
+                                input_index,
+                                input,
+                                sighash_type,
+                            );
+
+                            let mut signature = secp
+                                .sign(&message, &signing_key.secret_key())
+                                .serialize_der()
+                                .to_vec();
+                            signature.push(self.chain.sighash_type(sighash_type));
+                            signature
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                for (txin, script_sig) in transaction.input.iter_mut().zip(script_sigs) {
+                    txin.script_sig = script_sig;
+                }
+            }
+        }
+
+        Ok(transaction)
+    }
+
+    pub fn estimate_size(&self) -> usize {
+        self.transaction_without_signatures().get_size()
+    }
+
+    pub fn total_input_value(&self) -> Amount {
+        self.inputs
+            .iter()
+            .fold(Amount::ZERO, |acc, input| acc + input.value())
+    }
+
+    fn transaction_without_signatures(&self) -> Transaction {
+        let input = self
+            .inputs
+            .iter()
+            .map(|input| match self.chain.rules {
+                SpendRules::Segwit { .. } => input.to_txin_without_signature(),
+                SpendRules::Legacy | SpendRules::ForkId { .. } => TxIn {
+                    previous_output: input.previous_output(),
+                    // We can't sign it yet so we put a placeholder
+                    // value of the most likely signature length
+                    script_sig: script_sig(input, |_, _| vec![0u8; PLACEHOLDER_SIGNATURE_LEN]),
+                    sequence: input.sequence(),
+                    witness: Vec::new(),
+                },
+            })
+            .collect();
+
+        Transaction {
+            version: 2,
+            lock_time: self
+                .inputs
+                .iter()
+                .map(PrimedInput::locktime)
+                .max()
+                .unwrap_or(0),
+            input,
+            output: vec![TxOut {
+                value: 0,
+                script_pubkey: self.output_script.clone(),
+            }],
+        }
+    }
+}
+
+/// Pushes the witness stack of `input` in a `script_sig`, using `signature`
+/// to produce the signatures.
+fn script_sig(
+    input: &PrimedInput,
+    signature: impl Fn(&SigningKey, SigHashType) -> Vec<u8>,
+) -> Script {
+    let parameters = input.unlock_parameters();
+
+    parameters
+        .witness
+        .iter()
+        .fold(Builder::new(), |builder, witness| match witness {
+            Witness::Data(data) => builder.push_slice(data),
+            Witness::Preimage(preimage) => builder.push_slice(preimage.as_bytes()),
+            Witness::Signature(signing_key) => {
+                builder.push_slice(&signature(signing_key, SigHashType::All))
+            }
+            Witness::SignatureWithHashType(signing_key, sighash_type) => {
+                builder.push_slice(&signature(signing_key, *sighash_type))
+            }
+            Witness::PublicKey(public_key) => builder.push_slice(&public_key.serialize()),
+            // Pushing the numbers keeps the pushes minimal
+            Witness::Bool(true) => builder.push_int(1),
+            Witness::Bool(false) | Witness::Empty => builder.push_int(0),
+            Witness::Placeholder { len } => builder.push_slice(&vec![0u8; *len]),
+            Witness::PrevScript => builder.push_slice(parameters.prev_script.as_bytes()),
+        })
+        .into_script()
+}
+
+/// The CashAddr format of Bitcoin Cash addresses.
+///
+/// See https://github.com/bitcoincashorg/bitcoincash.org/blob/master/spec/cashaddr.md
+mod cashaddr {
+    use rust_bitcoin::bech32::ToBase32;
+
+    pub const PUBKEY_HASH: u8 = 0;
+    pub const SCRIPT_HASH: u8 = 1;
+
+    const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+    /// Encodes a 160 bit hash of the given type.
+    pub fn encode(prefix: &str, hash_type: u8, hash: &[u8]) -> String {
+        // The size bits of a 160 bit hash are 0
+        let mut payload = vec![hash_type << 3];
+        payload.extend_from_slice(hash);
+        let payload = payload.to_base32();
+
+        let checksum_input = prefix
+            .bytes()
+            .map(|byte| byte & 0x1f)
+            .chain(std::iter::once(0))
+            .chain(payload.iter().map(|value| value.to_u8()))
+            .chain([0u8; 8].iter().copied())
+            .collect::<Vec<_>>();
+        let checksum = polymod(&checksum_input);
+
+        let encoded = payload
+            .iter()
+            .map(|value| value.to_u8())
+            .chain((0..8).map(|i| ((checksum >> (5 * (7 - i))) & 0x1f) as u8))
+            .map(|value| CHARSET[value as usize] as char)
+            .collect::<String>();
+
+        format!("{}:{}", prefix, encoded)
+    }
+
+    fn polymod(values: &[u8]) -> u64 {
+        const GENERATORS: [u64; 5] = [
+            0x98_f2bc_8e61,
+            0x79_b76d_99e2,
+            0xf3_3e5f_b3c4,
+            0xae_2eab_e2a8,
+            0x1e_4f43_e470,
+        ];
+
+        let checksum = values.iter().fold(1u64, |checksum, value| {
+            let top = checksum >> 35;
+            let checksum = ((checksum & 0x07_ffff_ffff) << 5) ^ u64::from(*value);
+
+            GENERATORS
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| (top >> i) & 1 == 1)
+                .fold(checksum, |checksum, (_, generator)| checksum ^ generator)
+        });
+
+        checksum ^ 1
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use hex_literal::hex;
+
+        #[test]
+        fn encodes_specification_test_vectors() {
+            let hash = hex!("76a04053bda0a88bda5177b86a15c3b29f559873");
+
+            assert_eq!(
+                encode("bitcoincash", PUBKEY_HASH, &hash),
+                "bitcoincash:qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6a"
+            );
+            assert_eq!(
+                encode("bitcoincash", SCRIPT_HASH, &hash),
+                "bitcoincash:ppm2qsznhks23z7629mms6s4cwef74vcwvn0h829pq"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin::hbit::Htlc;
+    use rust_bitcoin::{hashes::sha256d, secp256k1::SecretKey, Address, OutPoint, PublicKey};
+
+    fn secret_key() -> SecretKey {
+        SecretKey::from_slice(&[1u8; 32]).unwrap()
+    }
+
+    fn htlc() -> Htlc {
+        let identity = Identity::from_secret_key(&Secp256k1::signing_only(), &secret_key());
+
+        Htlc::new(1_600_000_000, identity, identity, [0u8; 32])
+    }
+
+    fn redeem_transaction(chain: ChainParams) -> ChainTransaction {
+        let parameters = htlc()
+            .unlock_with_secret(&Secp256k1::signing_only(), secret_key(), [0u8; 32])
+            .unwrap();
+
+        ChainTransaction {
+            chain,
+            inputs: vec![PrimedInput::new(
+                OutPoint {
+                    txid: sha256d::Hash::default().into(),
+                    vout: 0,
+                },
+                Amount::from_sat(100_000),
+                parameters,
+            )],
+            output_script: Script::new(),
+        }
+    }
+
+    #[test]
+    fn bitcoin_addresses_match_rust_bitcoin() {
+        let script = Script::from(vec![0x51]);
+
+        assert_eq!(
+            ChainParams::from(Network::Regtest).script_address(&script),
+            Address::p2wsh(&script, Network::Regtest).to_string()
+        );
+        assert_eq!(
+            ChainParams {
+                rules: SpendRules::Legacy,
+                ..BITCOIN
+            }
+            .script_address(&script),
+            Address::p2sh(&script, Network::Bitcoin).to_string()
+        );
+    }
+
+    #[test]
+    fn identity_addresses_match_rust_bitcoin() {
+        let public_key = PublicKey {
+            compressed: true,
+            key: secp256k1::PublicKey::from_secret_key(&Secp256k1::signing_only(), &secret_key()),
+        };
+        let identity = Identity::from_public_key(&public_key.key);
+
+        assert_eq!(
+            ChainParams::from(Network::Regtest).identity_address(&identity),
+            Address::p2wpkh(&public_key, Network::Regtest)
+                .unwrap()
+                .to_string()
+        );
+        assert_eq!(
+            BITCOIN.without_segwit().identity_address(&identity),
+            Address::p2pkh(&public_key, Network::Bitcoin).to_string()
+        );
+    }
+
+    #[test]
+    fn identity_addresses_use_prefixes_of_chain() {
+        let identity = Identity::from_secret_key(&Secp256k1::signing_only(), &secret_key());
+
+        assert!(LITECOIN.identity_address(&identity).starts_with("ltc1q"));
+        assert!(DOGECOIN.identity_address(&identity).starts_with('D'));
+        assert!(BITCOIN_CASH
+            .identity_address(&identity)
+            .starts_with("bitcoincash:q"));
+    }
+
+    #[test]
+    fn htlc_addresses_use_prefixes_of_chain() {
+        let htlc = htlc();
+
+        assert!(htlc.compute_address_on(&LITECOIN).starts_with("ltc1q"));
+        assert!(htlc.compute_address_on(&DOGECOIN).starts_with('A'));
+        assert!(htlc
+            .compute_address_on(&BITCOIN_CASH)
+            .starts_with("bitcoincash:p"));
+    }
+
+    #[test]
+    fn redeem_without_segwit_is_signed_in_script_sig() {
+        for chain in &[DOGECOIN, BITCOIN_CASH] {
+            let secp = Secp256k1::new();
+            let unsigned = redeem_transaction(*chain);
+            let input = unsigned.inputs[0].clone();
+
+            let transaction = unsigned
+                .sign_with_fee(&secp, Amount::from_sat(1_000))
+                .unwrap();
+
+            let script_sig = transaction.input[0].script_sig.as_bytes();
+            let signature_len = script_sig[0] as usize;
+            let signature = &script_sig[1..=signature_len];
+            let (hash_type, der_signature) = signature.split_last().unwrap();
+
+            assert!(transaction.input[0].witness.is_empty());
+            assert!(script_sig.ends_with(input.unlock_parameters().prev_script.as_bytes()));
+            assert_eq!(*hash_type, chain.sighash_type(SigHashType::All));
+            assert!(secp
+                .verify(
+                    &chain.signature_hash(&transaction, 0, &input, SigHashType::All),
+                    &secp256k1::Signature::from_der(der_signature).unwrap(),
+                    &secp256k1::PublicKey::from_secret_key(&secp, &secret_key()),
+                )
+                .is_ok());
+            assert_eq!(transaction.output[0].value, 99_000);
+        }
+    }
+
+    #[test]
+    fn estimated_size_is_close_to_signed_size() {
+        for chain in &[LITECOIN, DOGECOIN, BITCOIN_CASH] {
+            let transaction = redeem_transaction(*chain);
+            let estimated_size = transaction.estimate_size();
+
+            let signed_size = transaction
+                .sign_with_fee(&Secp256k1::signing_only(), Amount::from_sat(1_000))
+                .unwrap()
+                .get_size();
+
+            assert!(estimated_size + 1 >= signed_size && estimated_size <= signed_size + 1);
+        }
+    }
+}
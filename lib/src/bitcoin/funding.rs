@@ -0,0 +1,511 @@
+//! Building the transaction that funds one or several HTLCs from wallet
+//! UTXOs.
+//!
+//! Inputs are selected with branch-and-bound, looking for a set of UTXOs
+//! which pays the outputs and the fee without needing a change output. If
+//! there is none, a knapsack heuristic picks either the smallest UTXO that
+//! covers the target on its own or a set of smaller UTXOs, whichever spends
+//! less, and the remainder is sent to the change address.
+use crate::bitcoin::{
+    hbit,
+    witness::{dust_limit, sign_inputs, PrimedInput},
+};
+use rust_bitcoin::{
+    consensus::encode::serialize,
+    secp256k1::{self, Secp256k1},
+    Address, Amount, Transaction, TxOut,
+};
+use std::fmt;
+
+/// Bounds the number of branches explored by branch-and-bound, as done by
+/// Bitcoin Core.
+const BNB_TOTAL_TRIES: usize = 100_000;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Error {
+    OverflowingFee,
+    InsufficientFunds,
+    NoHtlcOutput,
+    DustHtlcOutput,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::OverflowingFee => write!(f, "provided fee causes overflow"),
+            Error::InsufficientFunds => {
+                write!(f, "UTXOs are not enough to pay the outputs and the fee")
+            }
+            Error::NoHtlcOutput => write!(f, "at least one HTLC has to be funded"),
+            Error::DustHtlcOutput => write!(f, "HTLC output is below the dust limit"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A transaction funding HTLCs that's ready for input selection and signing.
+///
+/// The outputs of the signed transaction are the HTLCs in the order of
+/// `htlcs`, followed by the change output if there is one.
+#[derive(Debug)]
+pub struct FundingTransaction {
+    pub utxos: Vec<PrimedInput>,
+    pub htlcs: Vec<(hbit::Htlc, Amount)>,
+    pub change_address: Address,
+}
+
+impl FundingTransaction {
+    pub fn sign_with_rate<C: secp256k1::Signing>(
+        self,
+        secp: &Secp256k1<C>,
+        fee_per_vbyte: Amount,
+    ) -> Result<Transaction, Error> {
+        if self.htlcs.is_empty() {
+            return Err(Error::NoHtlcOutput);
+        }
+
+        let htlc_outputs = self
+            .htlcs
+            .iter()
+            .map(|(htlc, amount)| TxOut {
+                value: amount.as_sat(),
+                script_pubkey: htlc.script_pubkey(),
+            })
+            .collect::<Vec<_>>();
+
+        if htlc_outputs
+            .iter()
+            .any(|output| output.value < dust_limit(&output.script_pubkey).as_sat())
+        {
+            return Err(Error::DustHtlcOutput);
+        }
+
+        let change_output = TxOut {
+            value: 0,
+            script_pubkey: self.change_address.script_pubkey(),
+        };
+
+        let selection = Selection::new(
+            &self.utxos,
+            &htlc_outputs,
+            &change_output,
+            fee_per_vbyte.as_sat(),
+        )?;
+        let (selected, with_change) = selection.select()?;
+
+        let mut utxos = self.utxos.into_iter().map(Some).collect::<Vec<_>>();
+        let inputs = selected
+            .into_iter()
+            .map(|i| utxos[i].take().expect("each UTXO is selected once"))
+            .collect::<Vec<_>>();
+
+        let mut output = htlc_outputs;
+        if with_change {
+            output.push(change_output);
+        }
+
+        let mut transaction = Transaction {
+            version: 2,
+            lock_time: inputs.iter().map(PrimedInput::locktime).max().unwrap_or(0),
+            input: inputs
+                .iter()
+                .map(PrimedInput::to_txin_without_signature)
+                .collect(),
+            output,
+        };
+
+        let fee = fee_per_vbyte
+            .as_sat()
+            .checked_mul(transaction.get_size() as u64)
+            .ok_or(Error::OverflowingFee)?;
+        let total_input_value = inputs
+            .iter()
+            .try_fold(0u64, |acc, input| acc.checked_add(input.value().as_sat()))
+            .ok_or(Error::OverflowingFee)?;
+        let total_htlc_value = transaction
+            .output
+            .iter()
+            .take(self.htlcs.len())
+            .try_fold(0u64, |acc, output| acc.checked_add(output.value))
+            .ok_or(Error::OverflowingFee)?;
+        let remainder = total_input_value
+            .checked_sub(total_htlc_value)
+            .and_then(|remainder| remainder.checked_sub(fee))
+            .ok_or(Error::InsufficientFunds)?;
+
+        if with_change {
+            transaction.output[self.htlcs.len()].value = remainder;
+        }
+
+        sign_inputs(secp, &inputs, &mut transaction);
+
+        Ok(transaction)
+    }
+}
+
+/// The input of the coin selection, with all values in satoshis.
+#[derive(Debug)]
+struct Selection {
+    /// The index of each spendable UTXO in the original list and its value
+    /// minus the fee to spend it, sorted by descending effective value
+    effective_values: Vec<(usize, u64)>,
+    /// The value of the outputs plus the fee of the transaction without any
+    /// input or change output
+    target: u64,
+    /// The fee for adding a change output
+    change_fee: u64,
+    /// The minimum value of a change output
+    min_change: u64,
+}
+
+impl Selection {
+    fn new(
+        utxos: &[PrimedInput],
+        htlc_outputs: &[TxOut],
+        change_output: &TxOut,
+        fee_per_vbyte: u64,
+    ) -> Result<Self, Error> {
+        let fee = |size: usize| {
+            fee_per_vbyte
+                .checked_mul(size as u64)
+                .ok_or(Error::OverflowingFee)
+        };
+
+        let mut effective_values = Vec::with_capacity(utxos.len());
+        for (i, utxo) in utxos.iter().enumerate() {
+            let input_fee = fee(input_size(utxo))?;
+            if let Some(effective_value) = utxo.value().as_sat().checked_sub(input_fee) {
+                if effective_value > 0 {
+                    effective_values.push((i, effective_value));
+                }
+            }
+        }
+        effective_values.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        let transaction_without_inputs = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![],
+            output: htlc_outputs.to_vec(),
+        };
+        // Segwit marker and flag are not serialized without inputs
+        let base_fee = fee(transaction_without_inputs.get_size() + 2)?;
+        let target = htlc_outputs
+            .iter()
+            .try_fold(base_fee, |acc, output| acc.checked_add(output.value))
+            .ok_or(Error::OverflowingFee)?;
+
+        Ok(Selection {
+            effective_values,
+            target,
+            change_fee: fee(serialize(change_output).len())?,
+            min_change: dust_limit(&change_output.script_pubkey).as_sat(),
+        })
+    }
+
+    /// Returns the indices of the selected UTXOs and whether a change output
+    /// is needed.
+    fn select(&self) -> Result<(Vec<usize>, bool), Error> {
+        if let Some(selected) = self.branch_and_bound() {
+            return Ok((selected, false));
+        }
+
+        self.knapsack()
+    }
+
+    /// Looks for the set of UTXOs whose value exceeds the target by the least
+    /// amount, as long as the excess is not worth creating a change output.
+    fn branch_and_bound(&self) -> Option<Vec<usize>> {
+        let cost_of_change = self.change_fee + self.min_change;
+        let upper_bound = self.target.checked_add(cost_of_change)?;
+
+        let mut search = BranchAndBound {
+            effective_values: &self.effective_values,
+            target: self.target,
+            upper_bound,
+            tries: 0,
+            current: Vec::new(),
+            best: None,
+        };
+        let available = self.effective_values.iter().map(|(_, value)| value).sum();
+        search.explore(0, 0, available);
+
+        search.best.map(|(_, selected)| {
+            selected
+                .into_iter()
+                .map(|position| self.effective_values[position].0)
+                .collect()
+        })
+    }
+
+    fn knapsack(&self) -> Result<(Vec<usize>, bool), Error> {
+        let target_with_change = self
+            .target
+            .checked_add(self.change_fee + self.min_change)
+            .ok_or(Error::OverflowingFee)?;
+
+        let lowest_larger = self
+            .effective_values
+            .iter()
+            .rev()
+            .find(|(_, value)| *value >= target_with_change);
+
+        let mut smaller = Vec::new();
+        let mut smaller_total = 0u64;
+        for (i, value) in self
+            .effective_values
+            .iter()
+            .filter(|(_, value)| *value < target_with_change)
+        {
+            if smaller_total >= target_with_change {
+                break;
+            }
+            smaller.push(*i);
+            smaller_total += value;
+        }
+
+        match lowest_larger {
+            Some((i, value)) if smaller_total < target_with_change || *value <= smaller_total => {
+                Ok((vec![*i], true))
+            }
+            _ if smaller_total >= target_with_change => Ok((smaller, true)),
+            // Without a change output the UTXOs may still pay the target
+            _ if smaller_total >= self.target => Ok((smaller, false)),
+            _ => Err(Error::InsufficientFunds),
+        }
+    }
+}
+
+struct BranchAndBound<'a> {
+    effective_values: &'a [(usize, u64)],
+    target: u64,
+    upper_bound: u64,
+    tries: usize,
+    /// Positions in `effective_values` of the UTXOs included in the branch
+    current: Vec<usize>,
+    /// The excess over the target and the positions of the best selection
+    best: Option<(u64, Vec<usize>)>,
+}
+
+impl<'a> BranchAndBound<'a> {
+    fn explore(&mut self, position: usize, current_value: u64, remaining_value: u64) {
+        self.tries += 1;
+
+        if self.tries > BNB_TOTAL_TRIES
+            || current_value > self.upper_bound
+            || current_value + remaining_value < self.target
+        {
+            return;
+        }
+
+        if current_value >= self.target {
+            let excess = current_value - self.target;
+            let is_better = match &self.best {
+                Some((best, _)) => excess < *best,
+                None => true,
+            };
+            if is_better {
+                self.best = Some((excess, self.current.clone()));
+            }
+            return;
+        }
+
+        if position == self.effective_values.len() {
+            return;
+        }
+
+        let (_, value) = self.effective_values[position];
+
+        self.current.push(position);
+        self.explore(position + 1, current_value + value, remaining_value - value);
+        self.current.pop();
+
+        self.explore(position + 1, current_value, remaining_value - value);
+    }
+}
+
+/// The size an input adds to the transaction, witness included.
+fn input_size(utxo: &PrimedInput) -> usize {
+    let txin = utxo.to_txin_without_signature();
+
+    serialize(&txin).len() + serialize(&txin.witness).len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin::{witness::UnlockP2wpkh, Identity};
+    use rust_bitcoin::{
+        hashes::sha256d,
+        secp256k1::{PublicKey, SecretKey},
+        OutPoint, PrivateKey,
+    };
+    use std::str::FromStr;
+
+    const SECRET_HASH: [u8; 32] = [1u8; 32];
+
+    fn secret_key() -> SecretKey {
+        PrivateKey::from_str("L4nZrdzNnawCtaEcYGWuPqagQA3dJxVPgN8ARTXaMLCxiYCy89wm")
+            .unwrap()
+            .key
+    }
+
+    fn utxo(vout: u32, sats: u64) -> PrimedInput {
+        PrimedInput::new(
+            OutPoint {
+                txid: sha256d::Hash::default().into(),
+                vout,
+            },
+            Amount::from_sat(sats),
+            secret_key().p2wpkh_unlock_parameters(&Secp256k1::signing_only()),
+        )
+    }
+
+    fn htlc() -> hbit::Htlc {
+        let secp = Secp256k1::signing_only();
+        let identity = Identity::from(PublicKey::from_secret_key(&secp, &secret_key()));
+
+        hbit::Htlc::new(2_000_000_000, identity, identity, SECRET_HASH)
+    }
+
+    fn change_address() -> Address {
+        Address::from_str("bc1q87v7fjxcs29xvtz8kdu79u2tjfn3ppu0c3e6cl").unwrap()
+    }
+
+    fn fee_paid(transaction: &Transaction, utxos: &[PrimedInput]) -> u64 {
+        let input_value: u64 = transaction
+            .input
+            .iter()
+            .map(|input| {
+                utxos
+                    .iter()
+                    .find(|utxo| {
+                        utxo.to_txin_without_signature().previous_output == input.previous_output
+                    })
+                    .unwrap()
+                    .value()
+                    .as_sat()
+            })
+            .sum();
+        let output_value: u64 = transaction.output.iter().map(|output| output.value).sum();
+
+        input_value - output_value
+    }
+
+    #[test]
+    fn funds_htlc_with_change_and_pays_rate() {
+        let secp = Secp256k1::signing_only();
+        let utxos = vec![utxo(0, 50_000), utxo(1, 1_000_000), utxo(2, 20_000)];
+        let rate = Amount::from_sat(10);
+
+        let transaction = FundingTransaction {
+            utxos: utxos.clone(),
+            htlcs: vec![(htlc(), Amount::from_sat(400_000))],
+            change_address: change_address(),
+        }
+        .sign_with_rate(&secp, rate)
+        .unwrap();
+
+        assert_eq!(transaction.input.len(), 1);
+        assert_eq!(transaction.input[0].previous_output.vout, 1);
+        assert_eq!(transaction.output.len(), 2);
+        assert_eq!(transaction.output[0].value, 400_000);
+        assert_eq!(transaction.output[0].script_pubkey, htlc().script_pubkey());
+        assert_eq!(
+            transaction.output[1].script_pubkey,
+            change_address().script_pubkey()
+        );
+        // Actual signatures can be a byte shorter or longer than the placeholder
+        let fee = fee_paid(&transaction, &utxos);
+        let fee_for_actual_size = rate.as_sat() * transaction.get_size() as u64;
+        assert!(fee + rate.as_sat() >= fee_for_actual_size);
+        assert!(fee <= fee_for_actual_size + rate.as_sat());
+    }
+
+    #[test]
+    fn branch_and_bound_prefers_exact_match_without_change() {
+        let selection = Selection {
+            effective_values: vec![(0, 1_000_000), (1, 60_000), (2, 40_100)],
+            target: 100_000,
+            change_fee: 31,
+            min_change: 294,
+        };
+
+        assert_eq!(selection.select(), Ok((vec![1, 2], false)));
+    }
+
+    #[test]
+    fn knapsack_prefers_smallest_sufficient_utxo() {
+        let selection = Selection {
+            effective_values: vec![(0, 1_000_000), (1, 200_000), (2, 60_000), (3, 20_000)],
+            target: 100_000,
+            change_fee: 31,
+            min_change: 294,
+        };
+
+        assert_eq!(selection.select(), Ok((vec![1], true)));
+    }
+
+    #[test]
+    fn knapsack_combines_smaller_utxos() {
+        let selection = Selection {
+            effective_values: vec![(0, 1_000_000), (1, 90_000), (2, 60_000), (3, 20_000)],
+            target: 100_000,
+            change_fee: 31,
+            min_change: 294,
+        };
+
+        assert_eq!(selection.select(), Ok((vec![1, 2], true)));
+    }
+
+    #[test]
+    fn batch_funds_several_htlcs() {
+        let secp = Secp256k1::signing_only();
+        let utxos = vec![utxo(0, 300_000), utxo(1, 300_000), utxo(2, 300_000)];
+
+        let transaction = FundingTransaction {
+            utxos,
+            htlcs: vec![
+                (htlc(), Amount::from_sat(250_000)),
+                (htlc(), Amount::from_sat(250_000)),
+            ],
+            change_address: change_address(),
+        }
+        .sign_with_rate(&secp, Amount::from_sat(5))
+        .unwrap();
+
+        assert_eq!(transaction.input.len(), 2);
+        assert_eq!(transaction.output.len(), 3);
+        assert_eq!(transaction.output[0].value, 250_000);
+        assert_eq!(transaction.output[1].value, 250_000);
+    }
+
+    #[test]
+    fn insufficient_funds_are_reported() {
+        let secp = Secp256k1::signing_only();
+
+        let result = FundingTransaction {
+            utxos: vec![utxo(0, 50_000), utxo(1, 50_000)],
+            htlcs: vec![(htlc(), Amount::from_sat(100_000))],
+            change_address: change_address(),
+        }
+        .sign_with_rate(&secp, Amount::from_sat(1));
+
+        assert_eq!(result, Err(Error::InsufficientFunds));
+    }
+
+    #[test]
+    fn dust_htlc_output_is_rejected() {
+        let secp = Secp256k1::signing_only();
+
+        let result = FundingTransaction {
+            utxos: vec![utxo(0, 50_000)],
+            htlcs: vec![(htlc(), Amount::from_sat(300))],
+            change_address: change_address(),
+        }
+        .sign_with_rate(&secp, Amount::from_sat(1));
+
+        assert_eq!(result, Err(Error::DustHtlcOutput));
+    }
+}
@@ -1,30 +1,57 @@
 use crate::{
-    bitcoin::witness::{UnlockParameters, Witness, SEQUENCE_ALLOW_NTIMELOCK_NO_RBF},
+    bitcoin::{
+        chain::ChainParams,
+        identity::{Identity, IdentityMismatch},
+        witness::{
+            dust_limit,
+            size::{single_input_transaction_size, witness_weight, PLACEHOLDER_SIGNATURE_LEN},
+            Error, UnlockParameters, Witness, SEQUENCE_ALLOW_NTIMELOCK_NO_RBF,
+        },
+    },
     fit_into_placeholder_slice::{BitcoinTimestamp, FitIntoPlaceholderSlice},
     SecretHash,
 };
 use hex_literal::hex;
 use rust_bitcoin::{
-    hashes::hash160,
+    blockdata::{opcodes::all::*, script::Instruction},
+    hashes::{hash160, Hash},
     network::constants::Network,
     secp256k1::{self, Secp256k1, SecretKey},
-    Address, Script,
+    Address, Amount, Script,
 };
+use std::{convert::TryFrom, fmt};
 
 // contract template RFC: https://github.com/comit-network/RFCs/blob/master/RFC-005-SWAP-Basic-Bitcoin.adoc#contract
 pub const CONTRACT_TEMPLATE: [u8;97] = hex!("6382012088a82010000000000000000000000000000000000000000000000000000000000000018876a9143000000000000000000000000000000000000003670420000002b17576a91440000000000000000000000000000000000000046888ac");
 
+const PUBLIC_KEY_LEN: usize = 33;
+const SECRET_LEN: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UnknownContract;
+
+impl fmt::Display for UnknownContract {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "script is not an hbit contract")
+    }
+}
+
+impl std::error::Error for UnknownContract {}
+
 #[derive(Debug)]
 pub struct Htlc {
     script: Vec<u8>,
     expiry: u32,
+    redeem_identity: Identity,
+    refund_identity: Identity,
+    secret_hash: [u8; 32],
 }
 
 impl Htlc {
     pub fn new(
         expiry: u32,
-        refund_identity: hash160::Hash,
-        redeem_identity: hash160::Hash,
+        refund_identity: Identity,
+        redeem_identity: Identity,
         secret_hash: [u8; 32],
     ) -> Self {
         let mut contract = CONTRACT_TEMPLATE.to_vec();
@@ -36,43 +63,123 @@ impl Htlc {
         Htlc {
             script: contract,
             expiry,
+            redeem_identity,
+            refund_identity,
+            secret_hash,
         }
     }
 
+    /// Recognizes an hbit contract, including the ones whose expiry is not
+    /// encoded on 4 bytes like in `CONTRACT_TEMPLATE`, e.g. contracts of the
+    /// `atomicswap` tooling with a block height expiry. The expiry can be any
+    /// script number `OP_CHECKLOCKTIMEVERIFY` accepts as a locktime.
+    pub fn from_script(script: &Script) -> Result<Self, UnknownContract> {
+        let instructions = script
+            .instructions_minimal()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| UnknownContract)?;
+
+        use Instruction::{Op, PushBytes};
+        let (secret_hash, redeem_identity, expiry, refund_identity) = match instructions.as_slice()
+        {
+            [Op(OP_IF), Op(OP_SIZE), PushBytes(secret_len), Op(OP_EQUALVERIFY), Op(OP_SHA256), PushBytes(secret_hash), Op(OP_EQUALVERIFY), Op(OP_DUP), Op(OP_HASH160), PushBytes(redeem_identity), Op(OP_ELSE), expiry, Op(OP_CLTV), Op(OP_DROP), Op(OP_DUP), Op(OP_HASH160), PushBytes(refund_identity), Op(OP_ENDIF), Op(OP_EQUALVERIFY), Op(OP_CHECKSIG)]
+                // The secret length pushed by the template
+                if **secret_len == CONTRACT_TEMPLATE[3..4] =>
+            {
+                (secret_hash, redeem_identity, expiry, refund_identity)
+            }
+            _ => return Err(UnknownContract),
+        };
+
+        let identity = |bytes: &[u8]| {
+            hash160::Hash::from_slice(bytes)
+                .map(Identity::from_pubkey_hash)
+                .map_err(|_| UnknownContract)
+        };
+
+        Ok(Htlc {
+            script: script.to_bytes(),
+            expiry: read_locktime(expiry).ok_or(UnknownContract)?,
+            redeem_identity: identity(redeem_identity)?,
+            refund_identity: identity(refund_identity)?,
+            secret_hash: <[u8; 32]>::try_from(*secret_hash).map_err(|_| UnknownContract)?,
+        })
+    }
+
+    pub fn expiry(&self) -> u32 {
+        self.expiry
+    }
+
+    pub fn redeem_identity(&self) -> Identity {
+        self.redeem_identity
+    }
+
+    pub fn refund_identity(&self) -> Identity {
+        self.refund_identity
+    }
+
+    pub fn secret_hash(&self) -> [u8; 32] {
+        self.secret_hash
+    }
+
+    pub(crate) fn script(&self) -> Script {
+        Script::from(self.script.clone())
+    }
+
     pub fn compute_address(&self, network: Network) -> Address {
         Address::p2wsh(&Script::from(self.script.clone()), network)
     }
 
+    /// The address of the HTLC on a chain derived from Bitcoin, P2WSH on
+    /// chains with segwit and P2SH otherwise.
+    pub fn compute_address_on(&self, chain: &ChainParams) -> String {
+        chain.script_address(&Script::from(self.script.clone()))
+    }
+
+    /// The output script locking funds in this HTLC on `chain`.
+    pub fn script_pubkey_on(&self, chain: &ChainParams) -> Script {
+        chain.script_pubkey(&Script::from(self.script.clone()))
+    }
+
+    /// The P2WSH output script locking funds in this HTLC.
+    pub fn script_pubkey(&self) -> Script {
+        Script::from(self.script.clone()).to_v0_p2wsh()
+    }
+
     pub fn unlock_with_secret<C: secp256k1::Signing>(
         self,
         secp: &Secp256k1<C>,
         secret_key: SecretKey,
         secret: [u8; 32],
-    ) -> UnlockParameters {
+    ) -> Result<UnlockParameters, IdentityMismatch> {
         let public_key = secp256k1::PublicKey::from_secret_key(secp, &secret_key);
-        UnlockParameters {
+        self.redeem_identity.ensure_matches(&public_key)?;
+
+        Ok(UnlockParameters {
             witness: vec![
-                Witness::Signature(secret_key),
+                Witness::Signature(secret_key.into()),
                 Witness::PublicKey(public_key),
-                Witness::Data(secret.to_vec()),
+                Witness::Preimage(secret.into()),
                 Witness::Bool(true),
                 Witness::PrevScript,
             ],
             sequence: SEQUENCE_ALLOW_NTIMELOCK_NO_RBF,
             locktime: 0,
             prev_script: self.into_script(),
-        }
+        })
     }
 
     pub fn unlock_after_timeout<C: secp256k1::Signing>(
         self,
         secp: &Secp256k1<C>,
         secret_key: SecretKey,
-    ) -> UnlockParameters {
+    ) -> Result<UnlockParameters, IdentityMismatch> {
         let public_key = secp256k1::PublicKey::from_secret_key(secp, &secret_key);
-        UnlockParameters {
+        self.refund_identity.ensure_matches(&public_key)?;
+
+        Ok(UnlockParameters {
             witness: vec![
-                Witness::Signature(secret_key),
+                Witness::Signature(secret_key.into()),
                 Witness::PublicKey(public_key),
                 Witness::Bool(false),
                 Witness::PrevScript,
@@ -80,7 +187,58 @@ impl Htlc {
             sequence: SEQUENCE_ALLOW_NTIMELOCK_NO_RBF,
             locktime: self.expiry,
             prev_script: self.into_script(),
-        }
+        })
+    }
+
+    /// The weight of the witness produced by `unlock_with_secret`, with the
+    /// signature counted at the length `PrimedTransaction` estimates it.
+    pub fn redeem_witness_weight(&self) -> usize {
+        witness_weight(&[
+            PLACEHOLDER_SIGNATURE_LEN,
+            PUBLIC_KEY_LEN,
+            SECRET_LEN,
+            1,
+            self.script.len(),
+        ])
+    }
+
+    /// The weight of the witness produced by `unlock_after_timeout`, with the
+    /// signature counted at the length `PrimedTransaction` estimates it.
+    pub fn refund_witness_weight(&self) -> usize {
+        witness_weight(&[
+            PLACEHOLDER_SIGNATURE_LEN,
+            PUBLIC_KEY_LEN,
+            0,
+            self.script.len(),
+        ])
+    }
+
+    /// The minimum amount the HTLC has to be funded with for a redeem
+    /// transaction paying `fee_per_vbyte` to `output_address` to be relayed,
+    /// i.e. for its output to stay above the dust limit.
+    ///
+    /// The fee is computed the same way `PrimedTransaction::sign_with_rate`
+    /// computes it.
+    pub fn min_redeem_funding(
+        &self,
+        output_address: &Address,
+        fee_per_vbyte: Amount,
+    ) -> Result<Amount, Error> {
+        min_funding(self.redeem_witness_weight(), output_address, fee_per_vbyte)
+    }
+
+    /// The minimum amount the HTLC has to be funded with for a refund
+    /// transaction paying `fee_per_vbyte` to `output_address` to be relayed,
+    /// i.e. for its output to stay above the dust limit.
+    ///
+    /// The fee is computed the same way `PrimedTransaction::sign_with_rate`
+    /// computes it.
+    pub fn min_refund_funding(
+        &self,
+        output_address: &Address,
+        fee_per_vbyte: Amount,
+    ) -> Result<Amount, Error> {
+        min_funding(self.refund_witness_weight(), output_address, fee_per_vbyte)
     }
 
     fn into_script(self) -> Script {
@@ -88,11 +246,63 @@ impl Htlc {
     }
 }
 
+fn min_funding(
+    witness_weight: usize,
+    output_address: &Address,
+    fee_per_vbyte: Amount,
+) -> Result<Amount, Error> {
+    let script_pubkey = output_address.script_pubkey();
+    let size = single_input_transaction_size(witness_weight, &script_pubkey);
+
+    fee_per_vbyte
+        .checked_mul(size as u64)
+        .and_then(|fee| fee.checked_add(dust_limit(&script_pubkey)))
+        .ok_or(Error::OverflowingFee)
+}
+
+/// Reads the locktime pushed before `OP_CHECKLOCKTIMEVERIFY`: a small number
+/// opcode or a minimally encoded, non-negative script number of up to 5 bytes.
+fn read_locktime(instruction: &Instruction<'_>) -> Option<u32> {
+    let bytes = match instruction {
+        Instruction::Op(op) => {
+            let op = op.into_u8();
+            let small_numbers = OP_PUSHNUM_1.into_u8()..=OP_PUSHNUM_16.into_u8();
+
+            return if small_numbers.contains(&op) {
+                Some(u32::from(op - OP_PUSHNUM_1.into_u8() + 1))
+            } else {
+                None
+            };
+        }
+        Instruction::PushBytes(bytes) => bytes,
+    };
+
+    if bytes.len() > 5 {
+        return None;
+    }
+    if let Some((&last, rest)) = bytes.split_last() {
+        let is_negative = last & 0x80 != 0;
+        let is_padded = last == 0 && rest.last().is_none_or(|byte| byte & 0x80 == 0);
+        if is_negative || is_padded {
+            return None;
+        }
+    }
+
+    let locktime = bytes
+        .iter()
+        .rev()
+        .fold(0u64, |locktime, &byte| locktime << 8 | u64::from(byte));
+
+    u32::try_from(locktime).ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bitcoin::witness::{PrimedInput, PrimedTransaction};
     use regex::bytes::Regex;
-    use rust_bitcoin::hashes::hash160;
+    use rust_bitcoin::{hashes::sha256d, secp256k1::PublicKey, OutPoint};
+    use std::str::FromStr;
 
     const SECRET_HASH: [u8; 32] = [
         0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9,
@@ -101,14 +311,17 @@ mod tests {
 
     const SECRET_HASH_REGEX: &str = "\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x00\x01";
 
+    fn identity(byte: u8) -> Identity {
+        Identity::from_secret_key(&Secp256k1::signing_only(), &secret_key(byte))
+    }
+
+    fn secret_key(byte: u8) -> SecretKey {
+        SecretKey::from_slice(&[byte; 32]).unwrap()
+    }
+
     #[test]
     fn compiled_contract_is_same_length_as_template() {
-        let htlc = Htlc::new(
-            3_000_000,
-            hash160::Hash::default(),
-            hash160::Hash::default(),
-            SECRET_HASH,
-        );
+        let htlc = Htlc::new(3_000_000, identity(1), identity(2), SECRET_HASH);
 
         assert_eq!(
             htlc.script.len(),
@@ -119,12 +332,7 @@ mod tests {
 
     #[test]
     fn given_input_data_when_compiled_should_contain_given_data() {
-        let htlc = Htlc::new(
-            2_000_000_000,
-            hash160::Hash::default(),
-            hash160::Hash::default(),
-            SECRET_HASH,
-        );
+        let htlc = Htlc::new(2_000_000_000, identity(1), identity(2), SECRET_HASH);
 
         // Allowed because `str::contains` (clippy's suggestion) does not apply to bytes
         // array
@@ -134,4 +342,144 @@ mod tests {
             .find(&htlc.script)
             .expect("Could not find secret hash in hex code");
     }
+
+    #[test]
+    fn contract_is_recognized_from_its_script() {
+        let htlc = Htlc::new(2_000_000_000, identity(1), identity(2), SECRET_HASH);
+
+        let parsed = Htlc::from_script(&htlc.script()).unwrap();
+
+        assert_eq!(parsed.script, htlc.script);
+        assert_eq!(parsed.expiry(), 2_000_000_000);
+        assert_eq!(parsed.refund_identity(), identity(1));
+        assert_eq!(parsed.redeem_identity(), identity(2));
+        assert_eq!(parsed.secret_hash(), SECRET_HASH);
+        assert_eq!(
+            Htlc::from_script(&Script::from(CONTRACT_TEMPLATE[1..].to_vec())).unwrap_err(),
+            UnknownContract
+        );
+    }
+
+    #[test]
+    fn negative_or_padded_expiry_is_not_recognized() {
+        let with_expiry = |push: &[u8]| {
+            let mut script = CONTRACT_TEMPLATE[..64].to_vec();
+            script.extend_from_slice(push);
+            script.extend_from_slice(&CONTRACT_TEMPLATE[69..]);
+            Script::from(script)
+        };
+
+        assert_eq!(
+            Htlc::from_script(&with_expiry(&hex!("0310eb09")))
+                .unwrap()
+                .expiry(),
+            650_000
+        );
+        assert_eq!(
+            Htlc::from_script(&with_expiry(&hex!("0310eb89"))).unwrap_err(),
+            UnknownContract
+        );
+        assert_eq!(
+            Htlc::from_script(&with_expiry(&hex!("0410eb0900"))).unwrap_err(),
+            UnknownContract
+        );
+    }
+
+    #[test]
+    fn unlock_with_key_of_other_identity_fails() {
+        let secp = Secp256k1::signing_only();
+        let htlc = || Htlc::new(2_000_000_000, identity(1), identity(2), SECRET_HASH);
+
+        assert!(htlc()
+            .unlock_with_secret(&secp, secret_key(2), [0u8; 32])
+            .is_ok());
+        assert!(htlc().unlock_after_timeout(&secp, secret_key(1)).is_ok());
+
+        assert_eq!(
+            htlc().unlock_with_secret(&secp, secret_key(1), [0u8; 32]),
+            Err(IdentityMismatch {
+                expected: identity(2),
+                actual: Identity::from(PublicKey::from_secret_key(&secp, &secret_key(1))),
+            })
+        );
+        assert!(htlc().unlock_after_timeout(&secp, secret_key(2)).is_err());
+    }
+
+    #[test]
+    fn min_redeem_funding_leaves_dust_limit_after_fee() {
+        let secp = Secp256k1::signing_only();
+        let htlc = || Htlc::new(2_000_000_000, identity(1), identity(2), SECRET_HASH);
+        let output_address =
+            Address::from_str("bc1q87v7fjxcs29xvtz8kdu79u2tjfn3ppu0c3e6cl").unwrap();
+        let rate = Amount::from_sat(42);
+
+        let min_funding = htlc().min_redeem_funding(&output_address, rate).unwrap();
+
+        let primed_transaction = PrimedTransaction {
+            inputs: vec![PrimedInput::new(
+                OutPoint {
+                    txid: sha256d::Hash::default().into(),
+                    vout: 0,
+                },
+                min_funding,
+                htlc()
+                    .unlock_with_secret(&secp, secret_key(2), [0u8; 32])
+                    .unwrap(),
+            )],
+            output_address: output_address.clone(),
+        };
+
+        assert_eq!(
+            primed_transaction.estimate_size(),
+            single_input_transaction_size(
+                htlc().redeem_witness_weight(),
+                &output_address.script_pubkey()
+            )
+        );
+
+        let transaction = primed_transaction.sign_with_rate(&secp, rate).unwrap();
+
+        assert_eq!(
+            transaction.output[0].value,
+            dust_limit(&output_address.script_pubkey()).as_sat()
+        );
+    }
+
+    #[test]
+    fn min_refund_funding_leaves_dust_limit_after_fee() {
+        let secp = Secp256k1::signing_only();
+        let htlc = || Htlc::new(2_000_000_000, identity(1), identity(2), SECRET_HASH);
+        let output_address =
+            Address::from_str("bc1q87v7fjxcs29xvtz8kdu79u2tjfn3ppu0c3e6cl").unwrap();
+        let rate = Amount::from_sat(10);
+
+        let min_funding = htlc().min_refund_funding(&output_address, rate).unwrap();
+
+        let transaction = PrimedTransaction {
+            inputs: vec![PrimedInput::new(
+                OutPoint {
+                    txid: sha256d::Hash::default().into(),
+                    vout: 0,
+                },
+                min_funding,
+                htlc().unlock_after_timeout(&secp, secret_key(1)).unwrap(),
+            )],
+            output_address: output_address.clone(),
+        }
+        .sign_with_rate(&secp, rate)
+        .unwrap();
+
+        assert_eq!(
+            transaction.output[0].value,
+            dust_limit(&output_address.script_pubkey()).as_sat()
+        );
+    }
+
+    #[test]
+    fn witness_weights_match_hbit_witness_stacks() {
+        let htlc = Htlc::new(2_000_000_000, identity(1), identity(2), SECRET_HASH);
+
+        assert_eq!(htlc.redeem_witness_weight(), 240);
+        assert_eq!(htlc.refund_witness_weight(), 206);
+    }
 }
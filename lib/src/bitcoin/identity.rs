@@ -0,0 +1,153 @@
+use crate::fit_into_placeholder_slice::FitIntoPlaceholderSlice;
+use rust_bitcoin::{
+    hashes::{hash160, Hash},
+    secp256k1::{self, PublicKey, Secp256k1, SecretKey},
+};
+use std::{convert::TryFrom, fmt};
+
+/// Identifies a party of a Bitcoin HTLC through the hash of its public key.
+///
+/// An `Identity` can only be built from a compressed public key, which is the
+/// serialization the HTLCs expect when `HASH160` is applied to the public key
+/// of the witness.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Identity(hash160::Hash);
+
+impl Identity {
+    pub fn from_public_key(public_key: &PublicKey) -> Self {
+        Identity(hash160::Hash::hash(&public_key.serialize()))
+    }
+
+    pub fn from_secret_key<C: secp256k1::Signing>(
+        secp: &Secp256k1<C>,
+        secret_key: &SecretKey,
+    ) -> Self {
+        Self::from_public_key(&PublicKey::from_secret_key(secp, secret_key))
+    }
+
+    /// The caller vouches for `pubkey_hash` being the hash of a compressed
+    /// public key, e.g. because it was found in an hbit contract.
+    pub(crate) fn from_pubkey_hash(pubkey_hash: hash160::Hash) -> Self {
+        Identity(pubkey_hash)
+    }
+
+    pub fn pubkey_hash(&self) -> hash160::Hash {
+        self.0
+    }
+
+    /// Fails if `public_key` is not the key behind this identity.
+    pub fn ensure_matches(&self, public_key: &PublicKey) -> Result<(), IdentityMismatch> {
+        let actual = Identity::from_public_key(public_key);
+
+        if actual != *self {
+            return Err(IdentityMismatch {
+                expected: *self,
+                actual,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl From<PublicKey> for Identity {
+    fn from(public_key: PublicKey) -> Self {
+        Identity::from_public_key(&public_key)
+    }
+}
+
+impl TryFrom<rust_bitcoin::PublicKey> for Identity {
+    type Error = UncompressedPublicKey;
+
+    fn try_from(public_key: rust_bitcoin::PublicKey) -> Result<Self, Self::Error> {
+        if !public_key.compressed {
+            return Err(UncompressedPublicKey);
+        }
+
+        Ok(Identity::from_public_key(&public_key.key))
+    }
+}
+
+impl FitIntoPlaceholderSlice for Identity {
+    fn fit_into_placeholder_slice(self, buf: &mut [u8]) {
+        self.0.fit_into_placeholder_slice(buf)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UncompressedPublicKey;
+
+impl fmt::Display for UncompressedPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "identities can only be built from compressed public keys"
+        )
+    }
+}
+
+impl std::error::Error for UncompressedPublicKey {}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IdentityMismatch {
+    pub expected: Identity,
+    pub actual: Identity,
+}
+
+impl fmt::Display for IdentityMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "key does not match identity: expected {}, got {}",
+            self.expected.0, self.actual.0
+        )
+    }
+}
+
+impl std::error::Error for IdentityMismatch {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_of_uncompressed_key_is_rejected() {
+        let secp = Secp256k1::signing_only();
+        let public_key = rust_bitcoin::PublicKey {
+            compressed: false,
+            key: PublicKey::from_secret_key(&secp, &SecretKey::from_slice(&[1u8; 32]).unwrap()),
+        };
+
+        assert_eq!(Identity::try_from(public_key), Err(UncompressedPublicKey));
+    }
+
+    #[test]
+    fn identity_is_hash_of_compressed_key() {
+        let secp = Secp256k1::signing_only();
+        let secret_key = SecretKey::from_slice(&[1u8; 32]).unwrap();
+        let public_key = PublicKey::from_secret_key(&secp, &secret_key);
+        let bitcoin_public_key = rust_bitcoin::PublicKey {
+            compressed: true,
+            key: public_key,
+        };
+
+        assert_eq!(
+            Identity::from_secret_key(&secp, &secret_key).pubkey_hash(),
+            bitcoin_public_key.pubkey_hash().as_hash()
+        );
+    }
+
+    #[test]
+    fn identity_does_not_match_other_key() {
+        let secp = Secp256k1::signing_only();
+        let public_key =
+            PublicKey::from_secret_key(&secp, &SecretKey::from_slice(&[1u8; 32]).unwrap());
+        let other_public_key =
+            PublicKey::from_secret_key(&secp, &SecretKey::from_slice(&[2u8; 32]).unwrap());
+
+        let identity = Identity::from(public_key);
+
+        assert!(identity.ensure_matches(&public_key).is_ok());
+        assert!(identity.ensure_matches(&other_public_key).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! Proofs that a party controls the key behind an `Identity`, as signed
+//! messages following BIP137.
+//!
+//! Identities are hashes of compressed public keys, hence messages are signed
+//! with the header of compressed P2PKH keys, which Bitcoin Core's
+//! `verifymessage` accepts.
+use crate::bitcoin::identity::{Identity, IdentityMismatch};
+use rust_bitcoin::{
+    secp256k1::{
+        self,
+        recovery::{RecoverableSignature, RecoveryId},
+        Message, Secp256k1, SecretKey,
+    },
+    util::misc::{signed_msg_hash, MessageSignature},
+};
+use std::fmt;
+
+/// The BIP137 headers of signatures from compressed keys, whatever the type
+/// of the address: P2PKH, P2SH-P2WPKH and P2WPKH.
+const COMPRESSED_HEADERS: std::ops::RangeInclusive<u8> = 31..=42;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Error {
+    InvalidSignature,
+    IdentityMismatch(IdentityMismatch),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidSignature => write!(f, "signature is not a valid message signature"),
+            Error::IdentityMismatch(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<IdentityMismatch> for Error {
+    fn from(e: IdentityMismatch) -> Self {
+        Error::IdentityMismatch(e)
+    }
+}
+
+/// Signs `message` with the key behind an identity, producing the 65 bytes
+/// signature usually exchanged in base64.
+pub fn sign_message<C: secp256k1::Signing>(
+    secp: &Secp256k1<C>,
+    secret_key: &SecretKey,
+    message: &str,
+) -> [u8; 65] {
+    let signature: RecoverableSignature = secp.sign_recoverable(&message_hash(message), secret_key);
+
+    MessageSignature::new(signature, true).serialize()
+}
+
+/// Verifies that `signature` signs `message` with the key behind `identity`.
+pub fn verify_message<C: secp256k1::Verification>(
+    secp: &Secp256k1<C>,
+    identity: &Identity,
+    message: &str,
+    signature: &[u8],
+) -> Result<(), Error> {
+    let (header, signature) = match signature.split_first() {
+        Some((&header, signature)) if COMPRESSED_HEADERS.contains(&header) => (header, signature),
+        _ => return Err(Error::InvalidSignature),
+    };
+    // Each type of address has four headers, one per recovery id
+    let recovery_id = RecoveryId::from_i32(i32::from((header - 27) & 3))
+        .expect("Should not fail because it is at most 3");
+    let signature = RecoverableSignature::from_compact(signature, recovery_id)
+        .map_err(|_| Error::InvalidSignature)?;
+
+    let public_key = secp
+        .recover(&message_hash(message), &signature)
+        .map_err(|_| Error::InvalidSignature)?;
+
+    identity.ensure_matches(&public_key)?;
+
+    Ok(())
+}
+
+fn message_hash(message: &str) -> Message {
+    Message::from_slice(&signed_msg_hash(message)).expect("Should not fail because it is a hash")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_bitcoin::{Address, Network, PublicKey};
+
+    fn secret_key(byte: u8) -> SecretKey {
+        SecretKey::from_slice(&[byte; 32]).unwrap()
+    }
+
+    fn identity(byte: u8) -> Identity {
+        Identity::from_secret_key(&Secp256k1::signing_only(), &secret_key(byte))
+    }
+
+    #[test]
+    fn signature_verifies_for_identity_of_key() {
+        let secp = Secp256k1::new();
+        let signature = sign_message(&secp, &secret_key(1), "swap 42");
+
+        assert_eq!(
+            verify_message(&secp, &identity(1), "swap 42", &signature),
+            Ok(())
+        );
+        match verify_message(&secp, &identity(2), "swap 42", &signature) {
+            Err(Error::IdentityMismatch(_)) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+        match verify_message(&secp, &identity(1), "swap 43", &signature) {
+            Err(Error::IdentityMismatch(_)) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn signature_is_bitcoin_signed_message_of_p2pkh_address() {
+        let secp = Secp256k1::new();
+        let signature = sign_message(&secp, &secret_key(1), "swap 42");
+        let address = Address::p2pkh(
+            &PublicKey {
+                compressed: true,
+                key: secp256k1::PublicKey::from_secret_key(&secp, &secret_key(1)),
+            },
+            Network::Bitcoin,
+        );
+
+        assert!(MessageSignature::from_slice(&signature)
+            .unwrap()
+            .is_signed_by_address(&secp, &address, signed_msg_hash("swap 42"))
+            .unwrap());
+    }
+
+    #[test]
+    fn signatures_with_headers_of_compressed_keys_verify() {
+        let secp = Secp256k1::new();
+        let signature = sign_message(&secp, &secret_key(1), "swap 42");
+
+        // P2SH-P2WPKH and P2WPKH headers are four and eight after P2PKH ones
+        for offset in &[4, 8] {
+            let mut signature = signature;
+            signature[0] += offset;
+
+            assert_eq!(
+                verify_message(&secp, &identity(1), "swap 42", &signature),
+                Ok(())
+            );
+        }
+    }
+
+    #[test]
+    fn uncompressed_and_malformed_signatures_are_rejected() {
+        let secp = Secp256k1::new();
+        let signature = sign_message(&secp, &secret_key(1), "swap 42");
+
+        assert_eq!(
+            verify_message(&secp, &identity(1), "swap 42", &signature[1..]),
+            Err(Error::InvalidSignature)
+        );
+        assert_eq!(
+            verify_message(&secp, &identity(1), "swap 42", &signature[..64]),
+            Err(Error::InvalidSignature)
+        );
+
+        for header in (0..31).chain(43..=255) {
+            let mut signature = signature;
+            signature[0] = header;
+
+            assert_eq!(
+                verify_message(&secp, &identity(1), "swap 42", &signature),
+                Err(Error::InvalidSignature),
+                "header {}",
+                header
+            );
+        }
+    }
+}
@@ -1,2 +1,13 @@
+pub mod adaptor;
+pub mod atomicswap;
+pub mod batch;
+pub mod chain;
+pub mod funding;
 pub mod hbit;
+mod identity;
+pub mod message_signing;
+pub mod multi_secret;
+pub mod submarine;
 pub mod witness;
+
+pub use identity::{Identity, IdentityMismatch, UncompressedPublicKey};
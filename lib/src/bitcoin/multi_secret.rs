@@ -0,0 +1,304 @@
+//! An hbit HTLC whose redeem path requires several secrets, e.g. the one of
+//! the counterparty and the one of an arbiter.
+//!
+//! With a single secret hash the contract is the hbit contract.
+//! ```text
+//! IF
+//!     SIZE 32 EQUALVERIFY SHA256 [secret hash 1] EQUALVERIFY
+//!     ...
+//!     SIZE 32 EQUALVERIFY SHA256 [secret hash k] EQUALVERIFY
+//!     DUP HASH160 [redeem identity]
+//! ELSE
+//!     [expiry] CHECKLOCKTIMEVERIFY DROP
+//!     DUP HASH160 [refund identity]
+//! ENDIF
+//! EQUALVERIFY
+//! CHECKSIG
+//! ```
+use crate::bitcoin::{
+    identity::{Identity, IdentityMismatch},
+    witness::{UnlockParameters, Witness, SEQUENCE_ALLOW_NTIMELOCK_NO_RBF},
+};
+use rust_bitcoin::{
+    blockdata::{opcodes::all::*, script::Builder},
+    hashes::{sha256, Hash},
+    network::constants::Network,
+    secp256k1::{self, Secp256k1, SecretKey},
+    Address, Script,
+};
+use std::fmt;
+
+const SECRET_LEN: i64 = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Error {
+    NoSecretHash,
+    IdentityMismatch(IdentityMismatch),
+    SecretCountMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    /// The secret at `index` does not hash to the secret hash at `index`.
+    SecretMismatch {
+        index: usize,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NoSecretHash => write!(f, "at least one secret hash is required"),
+            Error::IdentityMismatch(e) => write!(f, "{}", e),
+            Error::SecretCountMismatch { expected, actual } => {
+                write!(f, "expected {} secrets, got {}", expected, actual)
+            }
+            Error::SecretMismatch { index } => {
+                write!(f, "secret {} does not match its secret hash", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<IdentityMismatch> for Error {
+    fn from(e: IdentityMismatch) -> Self {
+        Error::IdentityMismatch(e)
+    }
+}
+
+#[derive(Debug)]
+pub struct MultiSecretHtlc {
+    script: Script,
+    expiry: u32,
+    redeem_identity: Identity,
+    refund_identity: Identity,
+    secret_hashes: Vec<[u8; 32]>,
+}
+
+impl MultiSecretHtlc {
+    pub fn new(
+        expiry: u32,
+        refund_identity: Identity,
+        redeem_identity: Identity,
+        secret_hashes: Vec<[u8; 32]>,
+    ) -> Result<Self, Error> {
+        if secret_hashes.is_empty() {
+            return Err(Error::NoSecretHash);
+        }
+
+        let builder =
+            secret_hashes
+                .iter()
+                .fold(Builder::new().push_opcode(OP_IF), |builder, secret_hash| {
+                    builder
+                        .push_opcode(OP_SIZE)
+                        .push_int(SECRET_LEN)
+                        .push_opcode(OP_EQUALVERIFY)
+                        .push_opcode(OP_SHA256)
+                        .push_slice(secret_hash)
+                        .push_opcode(OP_EQUALVERIFY)
+                });
+
+        let script = builder
+            .push_opcode(OP_DUP)
+            .push_opcode(OP_HASH160)
+            .push_slice(&redeem_identity.pubkey_hash())
+            .push_opcode(OP_ELSE)
+            // Encoded on 4 bytes like in the hbit contract
+            .push_slice(&expiry.to_le_bytes())
+            .push_opcode(OP_CLTV)
+            .push_opcode(OP_DROP)
+            .push_opcode(OP_DUP)
+            .push_opcode(OP_HASH160)
+            .push_slice(&refund_identity.pubkey_hash())
+            .push_opcode(OP_ENDIF)
+            .push_opcode(OP_EQUALVERIFY)
+            .push_opcode(OP_CHECKSIG)
+            .into_script();
+
+        Ok(MultiSecretHtlc {
+            script,
+            expiry,
+            redeem_identity,
+            refund_identity,
+            secret_hashes,
+        })
+    }
+
+    pub fn compute_address(&self, network: Network) -> Address {
+        Address::p2wsh(&self.script, network)
+    }
+
+    /// The P2WSH output script locking funds in this HTLC.
+    pub fn script_pubkey(&self) -> Script {
+        self.script.to_v0_p2wsh()
+    }
+
+    /// `secrets` are the preimages of the secret hashes, in the same order.
+    pub fn unlock_with_secrets<C: secp256k1::Signing>(
+        self,
+        secp: &Secp256k1<C>,
+        secret_key: SecretKey,
+        secrets: Vec<[u8; 32]>,
+    ) -> Result<UnlockParameters, Error> {
+        let public_key = secp256k1::PublicKey::from_secret_key(secp, &secret_key);
+        self.redeem_identity.ensure_matches(&public_key)?;
+
+        if secrets.len() != self.secret_hashes.len() {
+            return Err(Error::SecretCountMismatch {
+                expected: self.secret_hashes.len(),
+                actual: secrets.len(),
+            });
+        }
+
+        if let Some(index) = secrets
+            .iter()
+            .zip(&self.secret_hashes)
+            .position(|(secret, secret_hash)| sha256::Hash::hash(secret)[..] != secret_hash[..])
+        {
+            return Err(Error::SecretMismatch { index });
+        }
+
+        // The first secret hash is checked against the top of the stack
+        let mut witness = vec![
+            Witness::Signature(secret_key.into()),
+            Witness::PublicKey(public_key),
+        ];
+        witness.extend(
+            secrets
+                .iter()
+                .rev()
+                .map(|secret| Witness::Preimage((*secret).into())),
+        );
+        witness.extend(vec![Witness::Bool(true), Witness::PrevScript]);
+
+        Ok(UnlockParameters {
+            witness,
+            sequence: SEQUENCE_ALLOW_NTIMELOCK_NO_RBF,
+            locktime: 0,
+            prev_script: self.script,
+        })
+    }
+
+    pub fn unlock_after_timeout<C: secp256k1::Signing>(
+        self,
+        secp: &Secp256k1<C>,
+        secret_key: SecretKey,
+    ) -> Result<UnlockParameters, IdentityMismatch> {
+        let public_key = secp256k1::PublicKey::from_secret_key(secp, &secret_key);
+        self.refund_identity.ensure_matches(&public_key)?;
+
+        Ok(UnlockParameters {
+            witness: vec![
+                Witness::Signature(secret_key.into()),
+                Witness::PublicKey(public_key),
+                Witness::Bool(false),
+                Witness::PrevScript,
+            ],
+            sequence: SEQUENCE_ALLOW_NTIMELOCK_NO_RBF,
+            locktime: self.expiry,
+            prev_script: self.script,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin::hbit::Htlc;
+
+    fn identity(byte: u8) -> Identity {
+        Identity::from_secret_key(&Secp256k1::signing_only(), &secret_key(byte))
+    }
+
+    fn secret_key(byte: u8) -> SecretKey {
+        SecretKey::from_slice(&[byte; 32]).unwrap()
+    }
+
+    fn secret_hash(secret: [u8; 32]) -> [u8; 32] {
+        sha256::Hash::hash(&secret).into_inner()
+    }
+
+    #[test]
+    fn single_secret_htlc_is_hbit_htlc() {
+        let htlc = MultiSecretHtlc::new(
+            2_000_000_000,
+            identity(1),
+            identity(2),
+            vec![secret_hash([3u8; 32])],
+        )
+        .unwrap();
+        let hbit = Htlc::new(
+            2_000_000_000,
+            identity(1),
+            identity(2),
+            secret_hash([3u8; 32]),
+        );
+
+        assert_eq!(htlc.script_pubkey(), hbit.script_pubkey());
+    }
+
+    #[test]
+    fn first_secret_is_on_top_of_the_stack() {
+        let secrets = vec![[3u8; 32], [4u8; 32]];
+        let htlc = MultiSecretHtlc::new(
+            2_000_000_000,
+            identity(1),
+            identity(2),
+            secrets.iter().copied().map(secret_hash).collect(),
+        )
+        .unwrap();
+
+        let parameters = htlc
+            .unlock_with_secrets(&Secp256k1::signing_only(), secret_key(2), secrets)
+            .unwrap();
+
+        assert_eq!(
+            parameters.witness[2..5],
+            [
+                Witness::Preimage([4u8; 32].into()),
+                Witness::Preimage([3u8; 32].into()),
+                Witness::Bool(true)
+            ]
+        );
+    }
+
+    #[test]
+    fn unlock_checks_secrets() {
+        let secp = Secp256k1::signing_only();
+        let htlc = || {
+            MultiSecretHtlc::new(
+                2_000_000_000,
+                identity(1),
+                identity(2),
+                vec![secret_hash([3u8; 32]), secret_hash([4u8; 32])],
+            )
+            .unwrap()
+        };
+
+        assert_eq!(
+            htlc()
+                .unlock_with_secrets(&secp, secret_key(2), vec![[3u8; 32]])
+                .unwrap_err(),
+            Error::SecretCountMismatch {
+                expected: 2,
+                actual: 1
+            }
+        );
+        assert_eq!(
+            htlc()
+                .unlock_with_secrets(&secp, secret_key(2), vec![[3u8; 32], [3u8; 32]])
+                .unwrap_err(),
+            Error::SecretMismatch { index: 1 }
+        );
+        match htlc().unlock_with_secrets(&secp, secret_key(1), vec![[3u8; 32], [4u8; 32]]) {
+            Err(Error::IdentityMismatch(_)) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert_eq!(
+            MultiSecretHtlc::new(2_000_000_000, identity(1), identity(2), vec![]).unwrap_err(),
+            Error::NoSecretHash
+        );
+    }
+}
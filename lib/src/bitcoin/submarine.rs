@@ -0,0 +1,417 @@
+//! The swap scripts of submarine swaps between on-chain bitcoin and
+//! Lightning, as used by Boltz.
+//!
+//! Unlike hbit, these scripts commit to the public keys themselves and check
+//! the `HASH160` of the preimage. The timeout is a block height.
+//!
+//! Submarine swap:
+//! ```text
+//! OP_HASH160 <preimage_hash> OP_EQUAL
+//! OP_IF
+//!     <claim_public_key>
+//! OP_ELSE
+//!     <timeout_block_height> OP_CHECKLOCKTIMEVERIFY OP_DROP
+//!     <refund_public_key>
+//! OP_ENDIF
+//! OP_CHECKSIG
+//! ```
+//!
+//! Reverse swap:
+//! ```text
+//! OP_SIZE 32 OP_EQUAL
+//! OP_IF
+//!     OP_HASH160 <preimage_hash> OP_EQUALVERIFY
+//!     <claim_public_key>
+//! OP_ELSE
+//!     OP_DROP
+//!     <timeout_block_height> OP_CHECKLOCKTIMEVERIFY OP_DROP
+//!     <refund_public_key>
+//! OP_ENDIF
+//! OP_CHECKSIG
+//! ```
+use crate::bitcoin::witness::{UnlockParameters, Witness, SEQUENCE_ALLOW_NTIMELOCK_NO_RBF};
+use rust_bitcoin::{
+    blockdata::{
+        opcodes::all::*,
+        script::{read_scriptint, Builder, Instruction},
+    },
+    hashes::{hash160, ripemd160, Hash},
+    network::constants::Network,
+    secp256k1::{self, PublicKey, Secp256k1, SecretKey},
+    Address, Script,
+};
+use std::{convert::TryFrom, fmt};
+
+const PREIMAGE_LEN: u8 = 32;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Error {
+    /// The script is neither a submarine nor a reverse swap script.
+    UnknownScript,
+    /// The key does not match the public key of the spending path.
+    KeyMismatch,
+    /// The preimage does not match the preimage hash of the script.
+    PreimageMismatch,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnknownScript => write!(f, "script is not a swap script"),
+            Error::KeyMismatch => write!(f, "key does not match the swap script"),
+            Error::PreimageMismatch => write!(f, "preimage does not match the swap script"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Kind {
+    /// Locks on-chain funds until the claimer paid a Lightning invoice.
+    Submarine,
+    /// Locks on-chain funds until the claimer revealed the preimage of the
+    /// Lightning invoice they are paid with.
+    Reverse,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SwapScript {
+    pub kind: Kind,
+    /// The `HASH160` of the preimage.
+    pub preimage_hash: hash160::Hash,
+    pub claim_public_key: PublicKey,
+    pub refund_public_key: PublicKey,
+    pub timeout_block_height: u32,
+}
+
+impl SwapScript {
+    /// `payment_hash` is the SHA-256 hash of the preimage, as found in
+    /// Lightning invoices.
+    pub fn submarine(
+        payment_hash: [u8; 32],
+        claim_public_key: PublicKey,
+        refund_public_key: PublicKey,
+        timeout_block_height: u32,
+    ) -> Self {
+        SwapScript {
+            kind: Kind::Submarine,
+            preimage_hash: preimage_hash(payment_hash),
+            claim_public_key,
+            refund_public_key,
+            timeout_block_height,
+        }
+    }
+
+    /// `payment_hash` is the SHA-256 hash of the preimage, as found in
+    /// Lightning invoices.
+    pub fn reverse(
+        payment_hash: [u8; 32],
+        claim_public_key: PublicKey,
+        refund_public_key: PublicKey,
+        timeout_block_height: u32,
+    ) -> Self {
+        SwapScript {
+            kind: Kind::Reverse,
+            preimage_hash: preimage_hash(payment_hash),
+            claim_public_key,
+            refund_public_key,
+            timeout_block_height,
+        }
+    }
+
+    /// Parses a swap script, e.g. the redeem script returned by a swap
+    /// provider, to check it before funding or spending it.
+    ///
+    /// Only the encoding `to_script` produces is accepted, so that the
+    /// parsed parameters yield the same script and address.
+    pub fn from_script(script: &Script) -> Result<Self, Error> {
+        let instructions = script
+            .instructions()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| Error::UnknownScript)?;
+
+        use Instruction::{Op, PushBytes};
+        let swap_script = match instructions.as_slice() {
+            [Op(OP_HASH160), PushBytes(hash), Op(OP_EQUAL), Op(OP_IF), PushBytes(claim), Op(OP_ELSE), PushBytes(timeout), Op(OP_CLTV), Op(OP_DROP), PushBytes(refund), Op(OP_ENDIF), Op(OP_CHECKSIG)] => {
+                Self::from_parts(Kind::Submarine, hash, claim, timeout, refund)
+            }
+            [Op(OP_SIZE), PushBytes(size), Op(OP_EQUAL), Op(OP_IF), Op(OP_HASH160), PushBytes(hash), Op(OP_EQUALVERIFY), PushBytes(claim), Op(OP_ELSE), Op(OP_DROP), PushBytes(timeout), Op(OP_CLTV), Op(OP_DROP), PushBytes(refund), Op(OP_ENDIF), Op(OP_CHECKSIG)]
+                if *size == [PREIMAGE_LEN] =>
+            {
+                Self::from_parts(Kind::Reverse, hash, claim, timeout, refund)
+            }
+            _ => Err(Error::UnknownScript),
+        }?;
+
+        if swap_script.to_script() != *script {
+            return Err(Error::UnknownScript);
+        }
+
+        Ok(swap_script)
+    }
+
+    fn from_parts(
+        kind: Kind,
+        hash: &[u8],
+        claim: &[u8],
+        timeout: &[u8],
+        refund: &[u8],
+    ) -> Result<Self, Error> {
+        let timeout_block_height = read_scriptint(timeout)
+            .ok()
+            .and_then(|timeout| u32::try_from(timeout).ok())
+            .ok_or(Error::UnknownScript)?;
+
+        Ok(SwapScript {
+            kind,
+            preimage_hash: hash160::Hash::from_slice(hash).map_err(|_| Error::UnknownScript)?,
+            claim_public_key: PublicKey::from_slice(claim).map_err(|_| Error::UnknownScript)?,
+            refund_public_key: PublicKey::from_slice(refund).map_err(|_| Error::UnknownScript)?,
+            timeout_block_height,
+        })
+    }
+
+    pub fn to_script(&self) -> Script {
+        let builder = match self.kind {
+            Kind::Submarine => Builder::new()
+                .push_opcode(OP_HASH160)
+                .push_slice(&self.preimage_hash)
+                .push_opcode(OP_EQUAL)
+                .push_opcode(OP_IF)
+                .push_slice(&self.claim_public_key.serialize())
+                .push_opcode(OP_ELSE),
+            Kind::Reverse => Builder::new()
+                .push_opcode(OP_SIZE)
+                .push_int(i64::from(PREIMAGE_LEN))
+                .push_opcode(OP_EQUAL)
+                .push_opcode(OP_IF)
+                .push_opcode(OP_HASH160)
+                .push_slice(&self.preimage_hash)
+                .push_opcode(OP_EQUALVERIFY)
+                .push_slice(&self.claim_public_key.serialize())
+                .push_opcode(OP_ELSE)
+                .push_opcode(OP_DROP),
+        };
+
+        builder
+            .push_int(i64::from(self.timeout_block_height))
+            .push_opcode(OP_CLTV)
+            .push_opcode(OP_DROP)
+            .push_slice(&self.refund_public_key.serialize())
+            .push_opcode(OP_ENDIF)
+            .push_opcode(OP_CHECKSIG)
+            .into_script()
+    }
+
+    pub fn compute_address(&self, network: Network) -> Address {
+        Address::p2wsh(&self.to_script(), network)
+    }
+
+    /// The P2WSH output script locking funds in this swap.
+    pub fn script_pubkey(&self) -> Script {
+        self.to_script().to_v0_p2wsh()
+    }
+
+    pub fn unlock_with_preimage<C: secp256k1::Signing>(
+        self,
+        secp: &Secp256k1<C>,
+        secret_key: SecretKey,
+        preimage: [u8; 32],
+    ) -> Result<UnlockParameters, Error> {
+        self.ensure_key_matches(secp, &secret_key, &self.claim_public_key)?;
+
+        if hash160::Hash::hash(&preimage) != self.preimage_hash {
+            return Err(Error::PreimageMismatch);
+        }
+
+        Ok(UnlockParameters {
+            witness: vec![
+                Witness::Signature(secret_key.into()),
+                Witness::Preimage(preimage.into()),
+                Witness::PrevScript,
+            ],
+            sequence: SEQUENCE_ALLOW_NTIMELOCK_NO_RBF,
+            locktime: 0,
+            prev_script: self.to_script(),
+        })
+    }
+
+    pub fn unlock_after_timeout<C: secp256k1::Signing>(
+        self,
+        secp: &Secp256k1<C>,
+        secret_key: SecretKey,
+    ) -> Result<UnlockParameters, Error> {
+        self.ensure_key_matches(secp, &secret_key, &self.refund_public_key)?;
+
+        Ok(UnlockParameters {
+            witness: vec![
+                Witness::Signature(secret_key.into()),
+                // Fails the preimage check of both scripts, which selects the refund path
+                Witness::Data(vec![]),
+                Witness::PrevScript,
+            ],
+            sequence: SEQUENCE_ALLOW_NTIMELOCK_NO_RBF,
+            locktime: self.timeout_block_height,
+            prev_script: self.to_script(),
+        })
+    }
+
+    fn ensure_key_matches<C: secp256k1::Signing>(
+        &self,
+        secp: &Secp256k1<C>,
+        secret_key: &SecretKey,
+        public_key: &PublicKey,
+    ) -> Result<(), Error> {
+        if PublicKey::from_secret_key(secp, secret_key) != *public_key {
+            return Err(Error::KeyMismatch);
+        }
+
+        Ok(())
+    }
+}
+
+fn preimage_hash(payment_hash: [u8; 32]) -> hash160::Hash {
+    hash160::Hash::from_inner(ripemd160::Hash::hash(&payment_hash).into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin::hbit::Htlc;
+    use crate::bitcoin::Identity;
+    use rust_bitcoin::hashes::sha256;
+
+    const PREIMAGE: [u8; 32] = [7u8; 32];
+
+    fn secret_key(byte: u8) -> SecretKey {
+        SecretKey::from_slice(&[byte; 32]).unwrap()
+    }
+
+    fn public_key(byte: u8) -> PublicKey {
+        PublicKey::from_secret_key(&Secp256k1::signing_only(), &secret_key(byte))
+    }
+
+    fn payment_hash() -> [u8; 32] {
+        sha256::Hash::hash(&PREIMAGE).into_inner()
+    }
+
+    #[test]
+    fn submarine_script_has_boltz_layout() {
+        let script = SwapScript::submarine(payment_hash(), public_key(1), public_key(2), 650_000);
+
+        let expected = [
+            vec![0xa9, 0x14],
+            hash160::Hash::hash(&PREIMAGE).to_vec(),
+            vec![0x87, 0x63, 0x21],
+            public_key(1).serialize().to_vec(),
+            // 650_000 is 0x09eb10
+            vec![0x67, 0x03, 0x10, 0xeb, 0x09, 0xb1, 0x75, 0x21],
+            public_key(2).serialize().to_vec(),
+            vec![0x68, 0xac],
+        ]
+        .concat();
+
+        assert_eq!(script.to_script().into_bytes(), expected);
+    }
+
+    #[test]
+    fn reverse_script_has_boltz_layout() {
+        let script = SwapScript::reverse(payment_hash(), public_key(1), public_key(2), 650_000);
+
+        let expected = [
+            vec![0x82, 0x01, 0x20, 0x87, 0x63, 0xa9, 0x14],
+            hash160::Hash::hash(&PREIMAGE).to_vec(),
+            vec![0x88, 0x21],
+            public_key(1).serialize().to_vec(),
+            vec![0x67, 0x75, 0x03, 0x10, 0xeb, 0x09, 0xb1, 0x75, 0x21],
+            public_key(2).serialize().to_vec(),
+            vec![0x68, 0xac],
+        ]
+        .concat();
+
+        assert_eq!(script.to_script().into_bytes(), expected);
+    }
+
+    #[test]
+    fn swap_scripts_are_parsed_back() {
+        for script in &[
+            SwapScript::submarine(payment_hash(), public_key(1), public_key(2), 650_000),
+            SwapScript::reverse(payment_hash(), public_key(1), public_key(2), 100),
+        ] {
+            assert_eq!(SwapScript::from_script(&script.to_script()), Ok(*script));
+        }
+    }
+
+    #[test]
+    fn non_canonical_swap_scripts_are_rejected() {
+        let swap_script =
+            SwapScript::submarine(payment_hash(), public_key(1), public_key(2), 650_000);
+        let canonical = swap_script.to_script().into_bytes();
+        // Pushes 650_000 with OP_PUSHDATA1 instead of a direct push
+        let non_minimal_push = [&canonical[..59], &[0x4c], &canonical[59..]].concat();
+        let uncompressed_key = Builder::new()
+            .push_opcode(OP_HASH160)
+            .push_slice(&swap_script.preimage_hash)
+            .push_opcode(OP_EQUAL)
+            .push_opcode(OP_IF)
+            .push_slice(&public_key(1).serialize_uncompressed())
+            .push_opcode(OP_ELSE)
+            .push_int(650_000)
+            .push_opcode(OP_CLTV)
+            .push_opcode(OP_DROP)
+            .push_slice(&public_key(2).serialize())
+            .push_opcode(OP_ENDIF)
+            .push_opcode(OP_CHECKSIG)
+            .into_script();
+
+        assert_eq!(
+            SwapScript::from_script(&Script::from(non_minimal_push)),
+            Err(Error::UnknownScript)
+        );
+        assert_eq!(
+            SwapScript::from_script(&uncompressed_key),
+            Err(Error::UnknownScript)
+        );
+    }
+
+    #[test]
+    fn hbit_script_is_not_a_swap_script() {
+        let identity = Identity::from_public_key(&public_key(1));
+        let htlc = Htlc::new(650_000, identity, identity, payment_hash());
+        let parameters = htlc
+            .unlock_after_timeout(&Secp256k1::signing_only(), secret_key(1))
+            .unwrap();
+
+        assert_eq!(
+            SwapScript::from_script(&parameters.prev_script),
+            Err(Error::UnknownScript)
+        );
+    }
+
+    #[test]
+    fn unlock_checks_key_and_preimage() {
+        let secp = Secp256k1::signing_only();
+        let script = SwapScript::reverse(payment_hash(), public_key(1), public_key(2), 650_000);
+
+        assert_eq!(
+            script.unlock_with_preimage(&secp, secret_key(2), PREIMAGE),
+            Err(Error::KeyMismatch)
+        );
+        assert_eq!(
+            script.unlock_with_preimage(&secp, secret_key(1), [0u8; 32]),
+            Err(Error::PreimageMismatch)
+        );
+        assert_eq!(
+            script.unlock_after_timeout(&secp, secret_key(1)),
+            Err(Error::KeyMismatch)
+        );
+        assert_eq!(
+            script
+                .unlock_after_timeout(&secp, secret_key(2))
+                .unwrap()
+                .locktime,
+            650_000
+        );
+    }
+}
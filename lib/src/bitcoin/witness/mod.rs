@@ -3,29 +3,76 @@
 /// max sequence number (0xffffffff) or a sequence number of
 /// (0xffffffff-1) when then also want to use locktime.
 pub const SEQUENCE_ALLOW_NTIMELOCK_NO_RBF: u32 = 0xFFFF_FFFE;
-#[allow(dead_code)]
 pub const SEQUENCE_DISALLOW_NTIMELOCK_NO_RBF: u32 = 0xFFFF_FFFF;
 
 mod p2wpkh;
 mod primed_transaction;
+mod secret;
+pub(crate) mod size;
 
 pub use p2wpkh::UnlockP2wpkh;
+pub(crate) use primed_transaction::sign_inputs;
 pub use primed_transaction::{Error, PrimedInput, PrimedTransaction};
+pub use secret::{Preimage, SigningKey};
+pub use size::dust_limit;
 
-use rust_bitcoin::{
-    secp256k1::{PublicKey, SecretKey},
-    Script,
-};
+use rust_bitcoin::{secp256k1::PublicKey, Script, SigHashType};
+use std::fmt;
 
+/// An item of the witness stack of an input.
+///
+/// Segwit v0 scripts only verify ECDSA signatures, hence there is no item for
+/// Schnorr signatures.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Witness {
     Data(Vec<u8>),
-    Signature(SecretKey),
+    /// The preimage revealed to unlock a hash lock.
+    Preimage(Preimage),
+    /// A `SIGHASH_ALL` signature with the key.
+    Signature(SigningKey),
+    /// A signature with the key committing to the transaction as per the
+    /// signature hash type.
+    SignatureWithHashType(SigningKey, SigHashType),
     PublicKey(PublicKey),
     Bool(bool),
+    /// An empty item, e.g. the extra item consumed by CHECKMULTISIG.
+    Empty,
+    /// Data only known once the transaction is built, e.g. the signature of a
+    /// counterparty over its signature hash.
+    ///
+    /// `len` bytes stand for it to estimate the size of the transaction until
+    /// `UnlockParameters::fill_placeholder` replaces it with the data.
+    Placeholder {
+        len: usize,
+    },
     PrevScript,
 }
 
+impl Witness {
+    /// The key and the signature hash type of a signature item.
+    pub(crate) fn signature(&self) -> Option<(&SigningKey, SigHashType)> {
+        match self {
+            Witness::Signature(signing_key) => Some((signing_key, SigHashType::All)),
+            Witness::SignatureWithHashType(signing_key, sighash_type) => {
+                Some((signing_key, *sighash_type))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The witness has no `Witness::Placeholder` left to fill.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NoPlaceholder;
+
+impl fmt::Display for NoPlaceholder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "witness has no placeholder left to fill")
+    }
+}
+
+impl std::error::Error for NoPlaceholder {}
+
 /// In order to properly describe how to unlock an output you need to know
 /// several things:
 /// * The witness data (which produces the unlocking script)
@@ -42,3 +89,67 @@ pub struct UnlockParameters {
     pub locktime: u32,
     pub prev_script: Script,
 }
+
+impl UnlockParameters {
+    /// Unlocks a P2WSH output locked by `prev_script` with the given witness
+    /// stack, without relative or absolute timelock.
+    pub fn p2wsh(prev_script: Script, witness: Vec<Witness>) -> Self {
+        UnlockParameters {
+            witness,
+            sequence: SEQUENCE_DISALLOW_NTIMELOCK_NO_RBF,
+            locktime: 0,
+            prev_script,
+        }
+    }
+
+    /// Replaces the first `Witness::Placeholder` of the witness with `data`.
+    pub fn fill_placeholder(&mut self, data: Vec<u8>) -> Result<(), NoPlaceholder> {
+        let placeholder = self
+            .witness
+            .iter_mut()
+            .find(|witness| matches!(witness, Witness::Placeholder { .. }))
+            .ok_or(NoPlaceholder)?;
+        *placeholder = Witness::Data(data);
+
+        Ok(())
+    }
+
+    /// The weight of the witness, with signatures of the most likely length
+    /// and placeholders of their given length.
+    pub fn witness_weight(&self) -> usize {
+        let item_lengths = self
+            .placeholder_witness()
+            .iter()
+            .map(Vec::len)
+            .collect::<Vec<_>>();
+
+        size::witness_weight(&item_lengths)
+    }
+
+    /// The witness stack with a placeholder in place of each signature.
+    pub(crate) fn placeholder_witness(&self) -> Vec<Vec<u8>> {
+        self.witness
+            .iter()
+            .map(|witness| match witness {
+                Witness::Data(data) => data.clone(),
+                Witness::Preimage(preimage) => preimage.as_bytes().to_vec(),
+                // We can't sign it yet so we put a placeholder
+                // value of the most likely signature length
+                Witness::Signature(_) | Witness::SignatureWithHashType(..) => {
+                    vec![0u8; size::PLACEHOLDER_SIGNATURE_LEN]
+                }
+                Witness::PublicKey(public_key) => public_key.serialize().to_vec(),
+                Witness::Bool(_bool) => {
+                    if *_bool {
+                        vec![1u8]
+                    } else {
+                        vec![]
+                    }
+                }
+                Witness::Empty => vec![],
+                Witness::Placeholder { len } => vec![0u8; *len],
+                Witness::PrevScript => self.prev_script.clone().into_bytes(),
+            })
+            .collect()
+    }
+}
@@ -1,22 +1,23 @@
-use crate::bitcoin::witness::{UnlockParameters, Witness};
+use crate::bitcoin::{
+    witness::{UnlockParameters, Witness},
+    Identity, IdentityMismatch,
+};
 use rust_bitcoin::{
-    hashes::hash160,
-    hashes::Hash,
     secp256k1::{self, PublicKey, SecretKey},
     Script,
 };
 
 /// Utility function to generate the `prev_script` for a p2wpkh adddress.
 /// A standard p2wpkh locking script of:
-/// 00 14 <public_key_hash>
+/// 00 14 <identity>
 /// becomes
-/// 19 76 a9 14 <public_key_hash> 88 ac
+/// 19 76 a9 14 <identity> 88 ac
 /// in the unlocking script. See BIP 143.
 /// This function simply returns the latter as a Script.
-fn generate_prev_script(public_key_hash: hash160::Hash) -> Script {
+fn generate_prev_script(identity: Identity) -> Script {
     let mut prev_script = vec![0x76, 0xa9, 0x14];
 
-    prev_script.append(&mut public_key_hash[..].to_vec());
+    prev_script.append(&mut identity.pubkey_hash()[..].to_vec());
     prev_script.push(0x88);
     prev_script.push(0xac);
 
@@ -28,6 +29,14 @@ pub trait UnlockP2wpkh {
         self,
         secp: &secp256k1::Secp256k1<C>,
     ) -> UnlockParameters;
+
+    /// Like `p2wpkh_unlock_parameters` but fails if the key does not belong
+    /// to the `identity` the output was locked to.
+    fn p2wpkh_unlock_parameters_for<C: secp256k1::Signing>(
+        self,
+        secp: &secp256k1::Secp256k1<C>,
+        identity: Identity,
+    ) -> Result<UnlockParameters, IdentityMismatch>;
 }
 
 impl UnlockP2wpkh for SecretKey {
@@ -37,12 +46,25 @@ impl UnlockP2wpkh for SecretKey {
     ) -> UnlockParameters {
         let public_key = PublicKey::from_secret_key(secp, &self);
         UnlockParameters {
-            witness: vec![Witness::Signature(self), Witness::PublicKey(public_key)],
+            witness: vec![
+                Witness::Signature(self.into()),
+                Witness::PublicKey(public_key),
+            ],
             sequence: super::SEQUENCE_ALLOW_NTIMELOCK_NO_RBF,
             locktime: 0,
-            prev_script: generate_prev_script(hash160::Hash::hash(&public_key.serialize())),
+            prev_script: generate_prev_script(Identity::from_public_key(&public_key)),
         }
     }
+
+    fn p2wpkh_unlock_parameters_for<C: secp256k1::Signing>(
+        self,
+        secp: &secp256k1::Secp256k1<C>,
+        identity: Identity,
+    ) -> Result<UnlockParameters, IdentityMismatch> {
+        identity.ensure_matches(&PublicKey::from_secret_key(secp, &self))?;
+
+        Ok(self.p2wpkh_unlock_parameters(secp))
+    }
 }
 
 #[cfg(test)]
@@ -64,4 +86,25 @@ mod test {
             "prev_script should be a p2pkh"
         );
     }
+
+    #[test]
+    fn unlock_parameters_for_other_identity_fail() {
+        let secp: Secp256k1<secp256k1::All> = Secp256k1::new();
+        let private_key =
+            PrivateKey::from_str("L4r4Zn5sy3o5mjiAdezhThkU37mcdN4eGp4aeVM4ZpotGTcnWc6k").unwrap();
+        let other_private_key =
+            PrivateKey::from_str("L4nZrdzNnawCtaEcYGWuPqagQA3dJxVPgN8ARTXaMLCxiYCy89wm").unwrap();
+        let identity = Identity::from_secret_key(&secp, &private_key.key);
+
+        assert_eq!(
+            private_key
+                .key
+                .p2wpkh_unlock_parameters_for(&secp, identity),
+            Ok(private_key.key.p2wpkh_unlock_parameters(&secp))
+        );
+        assert!(other_private_key
+            .key
+            .p2wpkh_unlock_parameters_for(&secp, identity)
+            .is_err());
+    }
 }
@@ -1,4 +1,4 @@
-use crate::bitcoin::witness::{UnlockParameters, Witness};
+use crate::bitcoin::witness::UnlockParameters;
 use rust_bitcoin::{
     hashes::Hash,
     secp256k1::{self, Message, Secp256k1},
@@ -11,6 +11,7 @@ use std::fmt;
 pub enum Error {
     OverflowingFee,
     FeeHigherThanInputValue,
+    InputIndexOutOfRange,
 }
 
 impl fmt::Display for Error {
@@ -18,6 +19,7 @@ impl fmt::Display for Error {
         match self {
             Error::OverflowingFee => write!(f, "provided fee causes overflow"),
             Error::FeeHigherThanInputValue => write!(f, "fee is higher than input value"),
+            Error::InputIndexOutOfRange => write!(f, "transaction has no input at this index"),
         }
     }
 }
@@ -44,35 +46,32 @@ impl PrimedInput {
         }
     }
 
-    fn encode_witness_for_txin(&self, witness: &Witness) -> Vec<u8> {
-        match witness {
-            Witness::Data(data) => data.clone(),
-            // We can't sign it yet so we put a placeholder
-            // value of the most likely signature length
-            Witness::Signature(_) => vec![0u8; 71],
-            Witness::PublicKey(public_key) => public_key.serialize().to_vec(),
-            Witness::Bool(_bool) => {
-                if *_bool {
-                    vec![1u8]
-                } else {
-                    vec![]
-                }
-            }
-            Witness::PrevScript => self.input_parameters.prev_script.clone().into_bytes(),
-        }
+    pub fn value(&self) -> Amount {
+        self.value
+    }
+
+    pub fn locktime(&self) -> u32 {
+        self.input_parameters.locktime
+    }
+
+    pub fn sequence(&self) -> u32 {
+        self.input_parameters.sequence
+    }
+
+    pub(crate) fn unlock_parameters(&self) -> &UnlockParameters {
+        &self.input_parameters
+    }
+
+    pub(crate) fn previous_output(&self) -> OutPoint {
+        self.previous_output
     }
 
-    fn to_txin_without_signature(&self) -> TxIn {
+    pub(crate) fn to_txin_without_signature(&self) -> TxIn {
         TxIn {
             previous_output: self.previous_output,
             script_sig: Script::new(),
             sequence: self.input_parameters.sequence,
-            witness: self
-                .input_parameters
-                .witness
-                .iter()
-                .map(|witness| self.encode_witness_for_txin(witness))
-                .collect(),
+            witness: self.input_parameters.placeholder_witness(),
         }
     }
 }
@@ -86,29 +85,7 @@ pub struct PrimedTransaction {
 
 impl PrimedTransaction {
     fn _sign<C: secp256k1::Signing>(self, secp: &Secp256k1<C>, transaction: &mut Transaction) {
-        for (i, primed_input) in self.inputs.into_iter().enumerate() {
-            let input_parameters = primed_input.input_parameters;
-            for (j, witness) in input_parameters.witness.iter().enumerate() {
-                if let Witness::Signature(secret_key) = witness {
-                    let mut sighash_cache = SigHashCache::new(transaction as &Transaction);
-                    let hash_to_sign = sighash_cache.signature_hash(
-                        i,
-                        &input_parameters.prev_script,
-                        primed_input.value.as_sat(),
-                        SigHashType::All,
-                    );
-                    // `from` should be used instead of `from_slice` once `ThirtyTwoByteHash` is
-                    // implemented for Hashes See https://github.com/rust-bitcoin/rust-secp256k1/issues/106
-                    let message_to_sign = Message::from_slice(&hash_to_sign.into_inner())
-                        .expect("Should not fail because it is a hash");
-                    let signature = secp.sign(&message_to_sign, secret_key);
-
-                    let mut serialized_signature = signature.serialize_der().to_vec();
-                    serialized_signature.push(SigHashType::All as u8);
-                    transaction.input[i].witness[j] = serialized_signature;
-                }
-            }
-        }
+        sign_inputs(secp, &self.inputs, transaction)
     }
 
     fn max_locktime(&self) -> Option<u32> {
@@ -157,6 +134,36 @@ impl PrimedTransaction {
         transaction
     }
 
+    /// Computes the `SIGHASH_ALL` message that `sign_with_fee` signs for the
+    /// input at `input_index`.
+    ///
+    /// The witness data of the inputs is not committed to by the signature
+    /// hash, which allows parties to agree on it before any signature exists,
+    /// e.g. to exchange adaptor signatures.
+    pub fn signature_hash(&self, input_index: usize, fee: Amount) -> Result<Message, Error> {
+        let input = self
+            .inputs
+            .get(input_index)
+            .ok_or(Error::InputIndexOutOfRange)?;
+
+        if self.total_input_value() < fee {
+            return Err(Error::FeeHigherThanInputValue);
+        };
+
+        let mut transaction = self._transaction_without_signatures_or_output_values();
+
+        transaction.output[0].value = (self.total_input_value() - fee).as_sat();
+
+        transaction.lock_time = self.max_locktime().unwrap_or(0);
+
+        Ok(signature_hash(
+            &transaction,
+            input_index,
+            input,
+            SigHashType::All,
+        ))
+    }
+
     pub fn total_input_value(&self) -> Amount {
         Amount::from_sat(
             self.inputs
@@ -198,11 +205,56 @@ impl PrimedTransaction {
     }
 }
 
+/// Signs the inputs of `transaction`, which are expected to be the
+/// `primed_inputs` in the same order.
+pub(crate) fn sign_inputs<C: secp256k1::Signing>(
+    secp: &Secp256k1<C>,
+    primed_inputs: &[PrimedInput],
+    transaction: &mut Transaction,
+) {
+    for (i, primed_input) in primed_inputs.iter().enumerate() {
+        let input_parameters = &primed_input.input_parameters;
+        for (j, witness) in input_parameters.witness.iter().enumerate() {
+            if let Some((signing_key, sighash_type)) = witness.signature() {
+                let message_to_sign = signature_hash(transaction, i, primed_input, sighash_type);
+                let signature = secp.sign(&message_to_sign, &signing_key.secret_key());
+
+                let mut serialized_signature = signature.serialize_der().to_vec();
+                serialized_signature.push(sighash_type as u8);
+                transaction.input[i].witness[j] = serialized_signature;
+            }
+        }
+    }
+}
+
+fn signature_hash(
+    transaction: &Transaction,
+    input_index: usize,
+    input: &PrimedInput,
+    sighash_type: SigHashType,
+) -> Message {
+    let mut sighash_cache = SigHashCache::new(transaction);
+    let hash_to_sign = sighash_cache.signature_hash(
+        input_index,
+        &input.input_parameters.prev_script,
+        input.value.as_sat(),
+        sighash_type,
+    );
+    // `from` should be used instead of `from_slice` once `ThirtyTwoByteHash` is
+    // implemented for Hashes See https://github.com/rust-bitcoin/rust-secp256k1/issues/106
+    Message::from_slice(&hash_to_sign.into_inner()).expect("Should not fail because it is a hash")
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::bitcoin::witness::p2wpkh::UnlockP2wpkh;
-    use rust_bitcoin::{hashes::sha256d, Address, PrivateKey};
+    use crate::bitcoin::witness::{p2wpkh::UnlockP2wpkh, NoPlaceholder, Witness};
+    use rust_bitcoin::{
+        blockdata::{opcodes::all::*, script::Builder},
+        hashes::sha256d,
+        secp256k1::{PublicKey, SecretKey, Signature},
+        Address, PrivateKey,
+    };
     use std::str::FromStr;
 
     #[test]
@@ -242,4 +294,164 @@ mod test {
         );
         Ok(())
     }
+
+    #[test]
+    fn custom_script_is_signed_with_hash_type_of_each_signature() {
+        let secp = Secp256k1::new();
+        let secret_keys = [
+            SecretKey::from_slice(&[1u8; 32]).unwrap(),
+            SecretKey::from_slice(&[2u8; 32]).unwrap(),
+        ];
+        let public_keys = [
+            PublicKey::from_secret_key(&secp, &secret_keys[0]),
+            PublicKey::from_secret_key(&secp, &secret_keys[1]),
+        ];
+        let multisig = Builder::new()
+            .push_int(2)
+            .push_slice(&public_keys[0].serialize())
+            .push_slice(&public_keys[1].serialize())
+            .push_int(2)
+            .push_opcode(OP_CHECKMULTISIG)
+            .into_script();
+        let sighash_type = SigHashType::SinglePlusAnyoneCanPay;
+
+        let unlock_parameters = UnlockParameters::p2wsh(
+            multisig,
+            vec![
+                Witness::Empty,
+                Witness::Signature(secret_keys[0].into()),
+                Witness::SignatureWithHashType(secret_keys[1].into(), sighash_type),
+                Witness::PrevScript,
+            ],
+        );
+        assert_eq!(
+            unlock_parameters.witness_weight(),
+            1 + 1 + 2 * (1 + 71) + 1 + 71
+        );
+
+        let input = PrimedInput::new(
+            OutPoint {
+                txid: sha256d::Hash::default().into(),
+                vout: 0,
+            },
+            Amount::from_sat(100_000),
+            unlock_parameters,
+        );
+        let transaction = PrimedTransaction {
+            inputs: vec![input.clone()],
+            output_address: Address::from_str("bc1q87v7fjxcs29xvtz8kdu79u2tjfn3ppu0c3e6cl")
+                .unwrap(),
+        }
+        .sign_with_fee(&secp, Amount::from_sat(1_000));
+
+        let witness = &transaction.input[0].witness;
+        assert!(witness[0].is_empty());
+        for (item, secret_key, sighash_type) in [
+            (&witness[1], secret_keys[0], SigHashType::All),
+            (&witness[2], secret_keys[1], sighash_type),
+        ]
+        .iter()
+        {
+            let (hash_type, der_signature) = item.split_last().unwrap();
+            assert_eq!(*hash_type, *sighash_type as u8);
+            assert!(secp
+                .verify(
+                    &signature_hash(&transaction, 0, &input, *sighash_type),
+                    &Signature::from_der(der_signature).unwrap(),
+                    &PublicKey::from_secret_key(&secp, secret_key),
+                )
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn placeholder_stands_for_counterparty_signature_until_filled() {
+        let secp = Secp256k1::new();
+        let secret_keys = [
+            SecretKey::from_slice(&[1u8; 32]).unwrap(),
+            SecretKey::from_slice(&[2u8; 32]).unwrap(),
+        ];
+        let multisig = Builder::new()
+            .push_int(2)
+            .push_slice(&PublicKey::from_secret_key(&secp, &secret_keys[0]).serialize())
+            .push_slice(&PublicKey::from_secret_key(&secp, &secret_keys[1]).serialize())
+            .push_int(2)
+            .push_opcode(OP_CHECKMULTISIG)
+            .into_script();
+
+        let mut unlock_parameters = UnlockParameters::p2wsh(
+            multisig,
+            vec![
+                Witness::Empty,
+                Witness::Signature(secret_keys[0].into()),
+                Witness::Placeholder { len: 73 },
+                Witness::PrevScript,
+            ],
+        );
+        assert_eq!(
+            unlock_parameters.witness_weight(),
+            1 + 1 + (1 + 71) + (1 + 73) + 1 + 71
+        );
+
+        let primed_transaction = |unlock_parameters| PrimedTransaction {
+            inputs: vec![PrimedInput::new(
+                OutPoint {
+                    txid: sha256d::Hash::default().into(),
+                    vout: 0,
+                },
+                Amount::from_sat(100_000),
+                unlock_parameters,
+            )],
+            output_address: Address::from_str("bc1q87v7fjxcs29xvtz8kdu79u2tjfn3ppu0c3e6cl")
+                .unwrap(),
+        };
+        let fee = Amount::from_sat(1_000);
+        let message = primed_transaction(unlock_parameters.clone())
+            .signature_hash(0, fee)
+            .unwrap();
+
+        let mut counterparty_signature = secp
+            .sign(&message, &secret_keys[1])
+            .serialize_der()
+            .to_vec();
+        counterparty_signature.push(SigHashType::All as u8);
+        unlock_parameters
+            .fill_placeholder(counterparty_signature.clone())
+            .unwrap();
+        assert_eq!(
+            unlock_parameters.fill_placeholder(vec![]),
+            Err(NoPlaceholder)
+        );
+
+        let transaction = primed_transaction(unlock_parameters).sign_with_fee(&secp, fee);
+
+        assert_eq!(transaction.input[0].witness[2], counterparty_signature);
+    }
+
+    #[test]
+    fn signature_hash_of_missing_input_is_an_error() {
+        let secp = Secp256k1::signing_only();
+        let private_key =
+            PrivateKey::from_str("L4nZrdzNnawCtaEcYGWuPqagQA3dJxVPgN8ARTXaMLCxiYCy89wm").unwrap();
+        let primed_txn = PrimedTransaction {
+            inputs: vec![PrimedInput::new(
+                OutPoint {
+                    txid: sha256d::Hash::default().into(),
+                    vout: 0,
+                },
+                Amount::from_sat(100_000),
+                private_key.key.p2wpkh_unlock_parameters(&secp),
+            )],
+            output_address: Address::from_str("bc1q87v7fjxcs29xvtz8kdu79u2tjfn3ppu0c3e6cl")
+                .unwrap(),
+        };
+
+        assert!(primed_txn
+            .signature_hash(0, Amount::from_sat(1_000))
+            .is_ok());
+        assert_eq!(
+            primed_txn.signature_hash(1, Amount::from_sat(1_000)),
+            Err(Error::InputIndexOutOfRange)
+        );
+    }
 }
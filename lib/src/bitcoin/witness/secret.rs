@@ -0,0 +1,83 @@
+//! Secrets put in the witness stack of an input, wiped from memory when
+//! dropped and redacted from `Debug` output.
+use rust_bitcoin::secp256k1::SecretKey;
+use std::fmt;
+use zeroize::Zeroize;
+
+/// A key used to sign an input.
+///
+/// `SecretKey` is `Copy` and can't be wiped, hence only the bytes of the key
+/// are kept and the `SecretKey` only exists while signing.
+#[derive(Clone, PartialEq)]
+pub struct SigningKey([u8; 32]);
+
+impl SigningKey {
+    pub(crate) fn secret_key(&self) -> SecretKey {
+        SecretKey::from_slice(&self.0).expect("bytes come from a valid secret key")
+    }
+}
+
+impl From<SecretKey> for SigningKey {
+    fn from(secret_key: SecretKey) -> Self {
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&secret_key[..]);
+
+        SigningKey(bytes)
+    }
+}
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SigningKey([REDACTED])")
+    }
+}
+
+impl Drop for SigningKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// The preimage of the secret hash of a swap.
+#[derive(Clone, PartialEq)]
+pub struct Preimage([u8; 32]);
+
+impl Preimage {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for Preimage {
+    fn from(bytes: [u8; 32]) -> Self {
+        Preimage(bytes)
+    }
+}
+
+impl fmt::Debug for Preimage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Preimage([REDACTED])")
+    }
+}
+
+impl Drop for Preimage {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_are_redacted_from_debug_output() {
+        let signing_key = SigningKey::from(SecretKey::from_slice(&[0xab; 32]).unwrap());
+        let preimage = Preimage::from([0xcd; 32]);
+
+        assert_eq!(
+            format!("{:?} {:?}", signing_key, preimage),
+            "SigningKey([REDACTED]) Preimage([REDACTED])"
+        );
+    }
+}
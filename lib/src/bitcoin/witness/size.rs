@@ -0,0 +1,90 @@
+use rust_bitcoin::{consensus::encode::VarInt, Amount, Script};
+
+/// We can't know the length of a signature before signing so we estimate
+/// it with the most likely length of a DER signature plus the sighash type.
+pub(crate) const PLACEHOLDER_SIGNATURE_LEN: usize = 71;
+
+/// The dust relay fee of Bitcoin Core in sat/kvB.
+const DUST_RELAY_FEE: u64 = 3_000;
+
+/// Computes the weight of a witness whose stack items have the given
+/// lengths. Witness data counts for one weight unit per byte.
+pub(crate) fn witness_weight(item_lengths: &[usize]) -> usize {
+    item_lengths
+        .iter()
+        .fold(varint_len(item_lengths.len()), |acc, len| {
+            acc + varint_len(*len) + len
+        })
+}
+
+/// Computes the size of a transaction spending a single segwit input with a
+/// witness of `witness_weight` to a single output locked by `script_pubkey`.
+///
+/// The size is computed the same way `PrimedTransaction::estimate_size` does.
+pub(crate) fn single_input_transaction_size(
+    witness_weight: usize,
+    script_pubkey: &Script,
+) -> usize {
+    const VERSION: usize = 4;
+    const SEGWIT_MARKER_AND_FLAG: usize = 2;
+    const LOCKTIME: usize = 4;
+    // previous output (36), empty script_sig (1), sequence (4)
+    const INPUT: usize = 41;
+
+    VERSION
+        + SEGWIT_MARKER_AND_FLAG
+        + varint_len(1)
+        + INPUT
+        + varint_len(1)
+        + output_size(script_pubkey)
+        + witness_weight
+        + LOCKTIME
+}
+
+/// Computes the value under which Bitcoin Core considers an output locked by
+/// `script_pubkey` to be dust, in which case the transaction creating it is
+/// not relayed.
+///
+/// See `GetDustThreshold` in Bitcoin Core's `policy.cpp`.
+pub fn dust_limit(script_pubkey: &Script) -> Amount {
+    if script_pubkey.is_provably_unspendable() {
+        return Amount::ZERO;
+    }
+
+    let spending_input_size = if script_pubkey.is_witness_program() {
+        32 + 4 + 1 + (107 / 4) + 4
+    } else {
+        32 + 4 + 1 + 107 + 4
+    };
+    let size = output_size(script_pubkey) + spending_input_size;
+
+    Amount::from_sat(size as u64 * DUST_RELAY_FEE / 1_000)
+}
+
+fn output_size(script_pubkey: &Script) -> usize {
+    8 + varint_len(script_pubkey.len()) + script_pubkey.len()
+}
+
+fn varint_len(n: usize) -> usize {
+    VarInt(n as u64).len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_bitcoin::Address;
+    use std::str::FromStr;
+
+    #[test]
+    fn dust_limits_match_bitcoin_core() {
+        let p2pkh = Address::from_str("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2").unwrap();
+        let p2wpkh = Address::from_str("bc1q87v7fjxcs29xvtz8kdu79u2tjfn3ppu0c3e6cl").unwrap();
+        let p2wsh =
+            Address::from_str("bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3")
+                .unwrap();
+
+        assert_eq!(dust_limit(&p2pkh.script_pubkey()), Amount::from_sat(546));
+        assert_eq!(dust_limit(&p2wpkh.script_pubkey()), Amount::from_sat(294));
+        assert_eq!(dust_limit(&p2wsh.script_pubkey()), Amount::from_sat(330));
+    }
+}
@@ -0,0 +1,63 @@
+use crate::bitcoin::{hbit, witness::UnlockParameters, Identity, IdentityMismatch};
+use rust_bitcoin::{
+    secp256k1::{self, PublicKey, Secp256k1, SecretKey},
+    Script,
+};
+use rust_elements::{Address, AddressParams};
+
+/// An hbit HTLC on an Elements chain.
+#[derive(Debug)]
+pub struct Htlc(hbit::Htlc);
+
+impl Htlc {
+    pub fn new(
+        expiry: u32,
+        refund_identity: Identity,
+        redeem_identity: Identity,
+        secret_hash: [u8; 32],
+    ) -> Self {
+        Htlc(hbit::Htlc::new(
+            expiry,
+            refund_identity,
+            redeem_identity,
+            secret_hash,
+        ))
+    }
+
+    /// The P2WSH address of the HTLC, a confidential blech32 address if a
+    /// `blinding_key` is given.
+    ///
+    /// `PrimedTransaction` only spends outputs with explicit values: funds
+    /// sent to a confidential address have to be unblinded and spent by a
+    /// wallet able to blind the outputs.
+    pub fn compute_address(
+        &self,
+        params: &'static AddressParams,
+        blinding_key: Option<PublicKey>,
+    ) -> Address {
+        Address::from_script(&self.script_pubkey(), blinding_key, params)
+            .expect("P2WSH scripts have an address")
+    }
+
+    /// The P2WSH output script locking funds in this HTLC.
+    pub fn script_pubkey(&self) -> Script {
+        self.0.script_pubkey()
+    }
+
+    pub fn unlock_with_secret<C: secp256k1::Signing>(
+        self,
+        secp: &Secp256k1<C>,
+        secret_key: SecretKey,
+        secret: [u8; 32],
+    ) -> Result<UnlockParameters, IdentityMismatch> {
+        self.0.unlock_with_secret(secp, secret_key, secret)
+    }
+
+    pub fn unlock_after_timeout<C: secp256k1::Signing>(
+        self,
+        secp: &Secp256k1<C>,
+        secret_key: SecretKey,
+    ) -> Result<UnlockParameters, IdentityMismatch> {
+        self.0.unlock_after_timeout(secp, secret_key)
+    }
+}
@@ -0,0 +1,10 @@
+//! Hbit on Elements based chains such as Liquid.
+//!
+//! Elements evaluates scripts like Bitcoin, hence the hbit contract is the
+//! same. Transactions are serialized differently, carry an asset with every
+//! value, pay their fee in an explicit output and are signed with a variant of
+//! BIP143.
+pub mod hbit;
+mod primed_transaction;
+
+pub use self::primed_transaction::{PrimedInput, PrimedTransaction};
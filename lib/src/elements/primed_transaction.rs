@@ -0,0 +1,506 @@
+use crate::bitcoin::witness::{Error, UnlockParameters};
+use rust_bitcoin::{
+    hashes::{sha256d, Hash, HashEngine},
+    secp256k1::{self, Message, Secp256k1},
+    Amount, Script, SigHashType,
+};
+use rust_elements::{
+    confidential::{Asset, Nonce, Value},
+    encode::Encodable,
+    AssetId, OutPoint, Transaction, TxIn, TxInWitness, TxOut,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrimedInput {
+    input_parameters: UnlockParameters,
+    asset: AssetId,
+    value: Amount,
+    /// The value as found in the spent output, which signatures commit to
+    value_commitment: Value,
+    previous_output: OutPoint,
+}
+
+impl PrimedInput {
+    /// Spends an output with an explicit `asset` and `value`.
+    pub fn new(
+        previous_output: OutPoint,
+        asset: AssetId,
+        value: Amount,
+        input_parameters: UnlockParameters,
+    ) -> PrimedInput {
+        PrimedInput::new_confidential(
+            previous_output,
+            asset,
+            value,
+            Value::Explicit(value.as_sat()),
+            input_parameters,
+        )
+    }
+
+    /// Spends an output whose value is blinded, given its unblinded `asset`
+    /// and `value`, and the `value_commitment` found in the output.
+    pub fn new_confidential(
+        previous_output: OutPoint,
+        asset: AssetId,
+        value: Amount,
+        value_commitment: Value,
+        input_parameters: UnlockParameters,
+    ) -> PrimedInput {
+        PrimedInput {
+            input_parameters,
+            asset,
+            value,
+            value_commitment,
+            previous_output,
+        }
+    }
+
+    fn to_txin_without_signature(&self) -> TxIn {
+        TxIn {
+            previous_output: self.previous_output,
+            is_pegin: false,
+            has_issuance: false,
+            script_sig: Script::new(),
+            sequence: self.input_parameters.sequence,
+            asset_issuance: Default::default(),
+            witness: TxInWitness {
+                script_witness: self.input_parameters.placeholder_witness(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// A transaction that's ready for signing
+///
+/// The value of every asset of the inputs is paid to `output_script`, minus
+/// the fee for `fee_asset`, e.g. L-BTC on Liquid, which is paid in an explicit
+/// fee output.
+#[derive(Debug, Clone)]
+pub struct PrimedTransaction {
+    pub inputs: Vec<PrimedInput>,
+    pub output_script: Script,
+    pub fee_asset: AssetId,
+}
+
+impl PrimedTransaction {
+    pub fn sign_with_rate<C: secp256k1::Signing>(
+        self,
+        secp: &Secp256k1<C>,
+        fee_per_vbyte: Amount,
+    ) -> Result<Transaction, Error> {
+        let fee = fee_per_vbyte
+            .checked_mul(self.estimate_vsize() as u64)
+            .ok_or(Error::OverflowingFee)?;
+
+        self.sign_with_fee(secp, fee)
+    }
+
+    pub fn sign_with_fee<C: secp256k1::Signing>(
+        self,
+        secp: &Secp256k1<C>,
+        fee: Amount,
+    ) -> Result<Transaction, Error> {
+        if self.total_input_value(self.fee_asset) < fee {
+            return Err(Error::FeeHigherThanInputValue);
+        }
+
+        let mut transaction = self.transaction_without_signatures(fee);
+
+        for (i, primed_input) in self.inputs.iter().enumerate() {
+            for (j, witness) in primed_input.input_parameters.witness.iter().enumerate() {
+                if let Some((signing_key, sighash_type)) = witness.signature() {
+                    let message_to_sign = signature_hash(
+                        &transaction,
+                        i,
+                        &primed_input.input_parameters.prev_script,
+                        primed_input.value_commitment,
+                        sighash_type,
+                    );
+                    let signature = secp.sign(&message_to_sign, &signing_key.secret_key());
+
+                    let mut serialized_signature = signature.serialize_der().to_vec();
+                    serialized_signature.push(sighash_type as u8);
+                    transaction.input[i].witness.script_witness[j] = serialized_signature;
+                }
+            }
+        }
+
+        Ok(transaction)
+    }
+
+    pub fn estimate_vsize(&self) -> usize {
+        let weight = self
+            .transaction_without_signatures(Amount::ZERO)
+            .get_weight();
+
+        weight.div_ceil(4)
+    }
+
+    pub fn total_input_value(&self, asset: AssetId) -> Amount {
+        self.inputs
+            .iter()
+            .filter(|input| input.asset == asset)
+            .fold(Amount::ZERO, |acc, input| acc + input.value)
+    }
+
+    fn transaction_without_signatures(&self, fee: Amount) -> Transaction {
+        let mut assets = Vec::new();
+        for input in &self.inputs {
+            if !assets.contains(&input.asset) {
+                assets.push(input.asset);
+            }
+        }
+
+        let mut output = assets
+            .into_iter()
+            .filter_map(|asset| {
+                let mut value = self.total_input_value(asset);
+                if asset == self.fee_asset {
+                    value = value.checked_sub(fee).unwrap_or(Amount::ZERO);
+                }
+
+                // Nothing is left once the fee is paid
+                if value == Amount::ZERO {
+                    return None;
+                }
+
+                Some(explicit_txout(asset, value, self.output_script.clone()))
+            })
+            .collect::<Vec<_>>();
+        output.push(explicit_txout(self.fee_asset, fee, Script::new()));
+
+        Transaction {
+            version: 2,
+            lock_time: self
+                .inputs
+                .iter()
+                .map(|input| input.input_parameters.locktime)
+                .max()
+                .unwrap_or(0),
+            input: self
+                .inputs
+                .iter()
+                .map(PrimedInput::to_txin_without_signature)
+                .collect(),
+            output,
+        }
+    }
+}
+
+fn explicit_txout(asset: AssetId, value: Amount, script_pubkey: Script) -> TxOut {
+    TxOut {
+        asset: Asset::Explicit(asset),
+        value: Value::Explicit(value.as_sat()),
+        nonce: Nonce::Null,
+        script_pubkey,
+        witness: Default::default(),
+    }
+}
+
+const SIGHASH_ANYONECANPAY: u8 = 0x80;
+
+/// The signature hash of segwit v0 inputs on Elements.
+///
+/// It differs from BIP143 by committing to the asset issuances of the
+/// transaction and to the spent value as a confidential value, which is the
+/// commitment of blinded outputs.
+fn signature_hash(
+    transaction: &Transaction,
+    input_index: usize,
+    script_code: &Script,
+    value: Value,
+    sighash_type: SigHashType,
+) -> Message {
+    let anyone_can_pay = sighash_type as u8 & SIGHASH_ANYONECANPAY != 0;
+    let base_type = SigHashType::from_u32(u32::from(sighash_type as u8 & !SIGHASH_ANYONECANPAY));
+    let commits_to_all_outputs = base_type == SigHashType::All;
+
+    let zero = sha256d::Hash::default();
+    let hash_prevouts = if anyone_can_pay {
+        zero
+    } else {
+        hash_all(transaction.input.iter().map(|txin| &txin.previous_output))
+    };
+    let hash_sequence = if anyone_can_pay || !commits_to_all_outputs {
+        zero
+    } else {
+        hash_all(transaction.input.iter().map(|txin| &txin.sequence))
+    };
+    // Inputs without issuance are committed to as a single zero byte
+    let hash_issuance = if anyone_can_pay {
+        zero
+    } else {
+        hash_all(transaction.input.iter().map(|_| &0u8))
+    };
+    let hash_outputs = match transaction.output.get(input_index) {
+        _ if commits_to_all_outputs => hash_all(transaction.output.iter()),
+        Some(output) if base_type == SigHashType::Single => hash_all(std::iter::once(output)),
+        _ => zero,
+    };
+
+    let mut engine = sha256d::Hash::engine();
+    encode(&transaction.version, &mut engine);
+    encode(&hash_prevouts, &mut engine);
+    encode(&hash_sequence, &mut engine);
+    encode(&hash_issuance, &mut engine);
+    encode(&transaction.input[input_index].previous_output, &mut engine);
+    encode(script_code, &mut engine);
+    encode(&value, &mut engine);
+    encode(&transaction.input[input_index].sequence, &mut engine);
+    encode(&hash_outputs, &mut engine);
+    encode(&transaction.lock_time, &mut engine);
+    encode(&sighash_type.as_u32(), &mut engine);
+
+    Message::from_slice(&sha256d::Hash::from_engine(engine).into_inner())
+        .expect("Should not fail because it is a hash")
+}
+
+fn hash_all<'a, T: Encodable + 'a>(items: impl Iterator<Item = &'a T>) -> sha256d::Hash {
+    let mut engine = sha256d::Hash::engine();
+    for item in items {
+        encode(item, &mut engine);
+    }
+
+    sha256d::Hash::from_engine(engine)
+}
+
+fn encode<T: Encodable>(item: &T, engine: &mut impl HashEngine) {
+    let mut data = Vec::new();
+    item.consensus_encode(&mut data)
+        .expect("writing to a vector does not fail");
+    engine.input(&data);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bitcoin::{witness::UnlockP2wpkh, Identity},
+        elements::hbit::Htlc,
+    };
+    use rust_bitcoin::{hashes::hex::FromHex, secp256k1::SecretKey, Txid};
+    use rust_elements::{encode::deserialize, AddressParams};
+
+    const NOW: u32 = 1_600_000_000;
+
+    fn secret_key(byte: u8) -> SecretKey {
+        SecretKey::from_slice(&[byte; 32]).unwrap()
+    }
+
+    fn htlc() -> Htlc {
+        let identity = Identity::from_secret_key(&Secp256k1::signing_only(), &secret_key(1));
+
+        Htlc::new(NOW, identity, identity, [0u8; 32])
+    }
+
+    fn asset(byte: u8) -> AssetId {
+        AssetId::from_slice(&[byte; 32]).unwrap()
+    }
+
+    fn outpoint(vout: u32) -> OutPoint {
+        OutPoint {
+            txid: Txid::from_hex(&"ab".repeat(32)).unwrap(),
+            vout,
+        }
+    }
+
+    fn explicit_value(txout: &TxOut) -> u64 {
+        txout.value.explicit().unwrap()
+    }
+
+    #[test]
+    fn addresses_use_elements_encoding() {
+        let secp = Secp256k1::signing_only();
+        let blinding_key = secp256k1::PublicKey::from_secret_key(&secp, &secret_key(2));
+
+        let address = htlc().compute_address(&AddressParams::LIQUID, None);
+        let confidential_address =
+            htlc().compute_address(&AddressParams::LIQUID, Some(blinding_key));
+
+        assert!(address.to_string().starts_with("ex1q"));
+        assert!(confidential_address.to_string().starts_with("lq1q"));
+        assert_eq!(address.script_pubkey(), htlc().script_pubkey());
+        assert_eq!(confidential_address.script_pubkey(), htlc().script_pubkey());
+    }
+
+    #[test]
+    fn redeem_pays_fee_in_explicit_output() {
+        let secp = Secp256k1::new();
+        let input = PrimedInput::new(
+            outpoint(0),
+            asset(1),
+            Amount::from_sat(100_000),
+            htlc()
+                .unlock_with_secret(&secp, secret_key(1), [0u8; 32])
+                .unwrap(),
+        );
+
+        let transaction = PrimedTransaction {
+            inputs: vec![input.clone()],
+            output_script: Script::new_op_return(&[]),
+            fee_asset: asset(1),
+        }
+        .sign_with_fee(&secp, Amount::from_sat(500))
+        .unwrap();
+
+        assert_eq!(transaction.output.len(), 2);
+        assert_eq!(explicit_value(&transaction.output[0]), 99_500);
+        assert!(transaction.output[1].is_fee());
+        assert_eq!(explicit_value(&transaction.output[1]), 500);
+
+        let signature = &transaction.input[0].witness.script_witness[0];
+        let (hash_type, der_signature) = signature.split_last().unwrap();
+        assert_eq!(*hash_type, SigHashType::All as u8);
+        assert!(secp
+            .verify(
+                &signature_hash(
+                    &transaction,
+                    0,
+                    &input.input_parameters.prev_script,
+                    input.value_commitment,
+                    SigHashType::All
+                ),
+                &secp256k1::Signature::from_der(der_signature).unwrap(),
+                &secp256k1::PublicKey::from_secret_key(&secp, &secret_key(1)),
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn signature_hash_matches_elements_vectors() {
+        // Generated by Elements, see the sighash tests of rust-elements
+        const TRANSACTION: &str = "010000000001715df5ccebaf02ff18d6fae7263fa69fed5de59c900f4749556eba41bc7bf2af0000000000000000000201230f4f5d4b7c6fa845806ee4f67713459e1b69e8e60fcee2e4940c7a0d5de1b2010000000124101100001f5175517551755175517551755175517551755175517551755175517551755101230f4f5d4b7c6fa845806ee4f67713459e1b69e8e60fcee2e4940c7a0d5de1b2010000000005f5e100000000000000";
+        const SCRIPT_CODE: &str = "76a914f54a5851e9372b87810a8e60cdd2e7cfd80b6e3188ac";
+        const COMMITMENT: &str =
+            "0850863ad64a87ae8a2fe83c1af1a8403cb53f53e486d8511dad8a04887e5b2352";
+        const EXPLICIT: &str = "010000000005f5e100";
+
+        let vectors = [
+            (
+                COMMITMENT,
+                SigHashType::All,
+                "e201b4019129a03ca0304989731c6dccde232c854d86fce999b7411da1e90048",
+            ),
+            (
+                COMMITMENT,
+                SigHashType::None,
+                "bfc6599816673083334ae82ac3459a2d0fef478d3e580e3ae203a28347502cb4",
+            ),
+            (
+                COMMITMENT,
+                SigHashType::Single,
+                "4bc8546e32d31c5415444138184696e80f49e537a083bfcc89be2ab41d962e76",
+            ),
+            (
+                COMMITMENT,
+                SigHashType::AllPlusAnyoneCanPay,
+                "b70ba5f4a1c2c48cd7f2104b2baa6a5c97987eb560916d39a5d427deb8b1dc2a",
+            ),
+            (
+                COMMITMENT,
+                SigHashType::NonePlusAnyoneCanPay,
+                "6d6a4749c09ffd9a8df4c5de5d939325d896009e18f94bb095c9d7d695a8465e",
+            ),
+            (
+                COMMITMENT,
+                SigHashType::SinglePlusAnyoneCanPay,
+                "7fc34367b42bf0e2bb78d8c20f45a64b81b2d4fbb59cbff8649322f619e88a0f",
+            ),
+            (
+                EXPLICIT,
+                SigHashType::All,
+                "71141639d982f1a1a8901e32fb1a9e15a0ea168b37d33300a3c9619fc3767388",
+            ),
+            (
+                EXPLICIT,
+                SigHashType::None,
+                "00730922d0e1d55b4b5fffafd087b06aeb44c4cedb58d8e182cbb9b87382cddb",
+            ),
+            (
+                EXPLICIT,
+                SigHashType::Single,
+                "100063ea0923ef4432dd51c5756383530f28b31ffe9d50b59a11b94a63c84c78",
+            ),
+            (
+                EXPLICIT,
+                SigHashType::AllPlusAnyoneCanPay,
+                "e1c4ddf5f723759f7d99d4f162155119160b1c6b765fdbdb25aedb2059769b74",
+            ),
+            (
+                EXPLICIT,
+                SigHashType::NonePlusAnyoneCanPay,
+                "b0be275e0c69e89ef5c482fdf330038c3b2994ebce3e3639bb81456d15a95a7a",
+            ),
+            (
+                EXPLICIT,
+                SigHashType::SinglePlusAnyoneCanPay,
+                "27c293da7a0f08e161fa2a77aeefa6743c929905597b5bcb28f2015fe648aa0c",
+            ),
+        ];
+
+        let transaction: Transaction =
+            deserialize(&Vec::<u8>::from_hex(TRANSACTION).unwrap()).unwrap();
+        let script_code = Script::from(Vec::<u8>::from_hex(SCRIPT_CODE).unwrap());
+
+        for (value, sighash_type, expected) in vectors.iter() {
+            let value: Value = deserialize(&Vec::<u8>::from_hex(value).unwrap()).unwrap();
+            let expected = Message::from_slice(&Vec::<u8>::from_hex(expected).unwrap()).unwrap();
+
+            assert_eq!(
+                signature_hash(&transaction, 0, &script_code, value, *sighash_type),
+                expected,
+                "{:?} {:?}",
+                value,
+                sighash_type
+            );
+        }
+    }
+
+    #[test]
+    fn refund_of_issued_asset_pays_fee_from_other_input() {
+        let secp = Secp256k1::signing_only();
+        let refund = htlc().unlock_after_timeout(&secp, secret_key(1)).unwrap();
+        let fee_input = secret_key(2).p2wpkh_unlock_parameters(&secp);
+
+        let transaction = PrimedTransaction {
+            inputs: vec![
+                PrimedInput::new(outpoint(0), asset(2), Amount::from_sat(1_000), refund),
+                PrimedInput::new(outpoint(1), asset(1), Amount::from_sat(10_000), fee_input),
+            ],
+            output_script: Script::new_op_return(&[]),
+            fee_asset: asset(1),
+        }
+        .sign_with_rate(&secp, Amount::from_sat(1))
+        .unwrap();
+
+        assert_eq!(transaction.lock_time, NOW);
+        assert_eq!(transaction.output.len(), 3);
+        assert_eq!(transaction.output[0].asset, Asset::Explicit(asset(2)));
+        assert_eq!(explicit_value(&transaction.output[0]), 1_000);
+        assert_eq!(
+            explicit_value(&transaction.output[1]) + explicit_value(&transaction.output[2]),
+            10_000
+        );
+    }
+
+    #[test]
+    fn fee_asset_must_be_spent() {
+        let secp = Secp256k1::signing_only();
+        let input = PrimedInput::new(
+            outpoint(0),
+            asset(2),
+            Amount::from_sat(1_000),
+            htlc().unlock_after_timeout(&secp, secret_key(1)).unwrap(),
+        );
+
+        let result = PrimedTransaction {
+            inputs: vec![input],
+            output_script: Script::new_op_return(&[]),
+            fee_asset: asset(1),
+        }
+        .sign_with_fee(&secp, Amount::from_sat(500));
+
+        assert_eq!(result.unwrap_err(), Error::FeeHigherThanInputValue);
+    }
+}
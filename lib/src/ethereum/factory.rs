@@ -0,0 +1,103 @@
+//! A factory deploying HTLCs with CREATE2, which makes the address of an
+//! HTLC independent of the nonce of its funder.
+//!
+//! The factory is deployed once per chain. It is called with a salt followed
+//! by the init code of a `heth::Htlc` or `herc20::Htlc`, deploys the HTLC
+//! with the value of the call and returns its address. The factory hashes
+//! the salt with the address of its caller, hence someone watching the
+//! mempool cannot deploy a different HTLC at the address the caller expects.
+use crate::ethereum::{contract_address_create2, keccak256, Address};
+use hex_literal::hex;
+
+// contract template: print_offsets/factory_template
+pub const CONTRACT: [u8; 66] = hex!("61003361000f6000396100336000f333600052600035602052604060002060203603602060003760203603600034f58015602d5760005260206000f35b60006000fd");
+
+pub fn deploy_tx_gas_limit() -> u64 {
+    // 53_000 for the creation, 7_800 to store the code and the call data
+    70_000
+}
+
+/// The gas used by the factory on top of the deployment of the HTLC itself,
+/// e.g. on top of `heth::Htlc::deploy_tx_gas_limit()`.
+pub fn deploy_htlc_tx_gas_overhead() -> u64 {
+    // The call data of the salt, hashing it with the caller, copying and
+    // hashing the init code and the execution of the factory
+    5_000
+}
+
+/// Constructs the call data to deploy `htlc` through the factory, salted
+/// with the secret hash of the HTLC.
+pub fn deploy_htlc_tx_payload(secret_hash: [u8; 32], htlc: impl Into<Vec<u8>>) -> Vec<u8> {
+    let mut data = secret_hash.to_vec();
+    data.extend(htlc.into());
+
+    data
+}
+
+/// The address at which the factory deployed at `factory` deploys `htlc`,
+/// given as init code, when called by `deployer`.
+pub fn htlc_address(
+    factory: Address,
+    deployer: Address,
+    secret_hash: [u8; 32],
+    htlc: &[u8],
+) -> Address {
+    contract_address_create2(factory, salt(deployer, secret_hash), htlc)
+}
+
+/// The CREATE2 salt of the factory, the hash of the caller, padded to a word,
+/// followed by the salt of the call data.
+fn salt(deployer: Address, secret_hash: [u8; 32]) -> [u8; 32] {
+    let mut data = [0u8; 64];
+    data[12..32].copy_from_slice(&deployer.0);
+    data[32..].copy_from_slice(&secret_hash);
+
+    keccak256(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::{heth::Htlc, Expiry, HashFunction};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn deploy_header_copies_and_returns_the_whole_factory() {
+        let header_length = 15u16.to_be_bytes();
+        let factory_length = u16::try_from(CONTRACT.len() - 15).unwrap().to_be_bytes();
+
+        assert_eq!(CONTRACT[1..3], factory_length);
+        assert_eq!(CONTRACT[4..6], header_length);
+        assert_eq!(CONTRACT[10..12], factory_length);
+    }
+
+    #[test]
+    fn htlc_address_depends_on_secret_hash_and_deployer() {
+        let htlc: Vec<u8> = Htlc::new(
+            Expiry::Timestamp(2_000_000_000),
+            Address([1; 20]),
+            Address([2; 20]),
+            [3; 32],
+            HashFunction::Sha256,
+        )
+        .into();
+        let factory = Address([4; 20]);
+        let deployer = Address([1; 20]);
+
+        let address = htlc_address(factory, deployer, [3; 32], &htlc);
+
+        assert_eq!(
+            address,
+            contract_address_create2(factory, salt(deployer, [3; 32]), &htlc)
+        );
+        assert_ne!(address, htlc_address(factory, deployer, [5; 32], &htlc));
+        assert_ne!(
+            address,
+            htlc_address(factory, Address([6; 20]), [3; 32], &htlc)
+        );
+        assert_eq!(
+            deploy_htlc_tx_payload([3; 32], htlc.clone())[32..],
+            htlc[..]
+        );
+    }
+}
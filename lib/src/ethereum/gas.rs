@@ -0,0 +1,847 @@
+//! Measures the gas of the transactions deploying, funding, redeeming and
+//! refunding HTLCs by executing them in an embedded EVM.
+//!
+//! The `*_tx_gas_limit` functions of the HTLCs return constants, which the
+//! tests check against the estimates of every hardfork. A `GasEstimator`
+//! instead looks for the lowest gas limit the transaction succeeds with under
+//! the rules of a given hardfork, and adds a safety margin to it.
+use crate::ethereum::local_chain::{LocalChain, Outcome, Transaction};
+use crate::ethereum::{
+    contract_address, herc20, herc20_full_balance, herc20_relayer_fee, herc20_safe,
+    herc20_transfer_from, heth, heth_relayer_fee, keccak256, layout::TemplateCode, Address, Expiry,
+    HashFunction, HtlcCode, TokenQuantity,
+};
+use evm::{Config, ExitReason};
+use primitive_types::{H160, H256, U256};
+use std::fmt;
+
+/// No transaction is measured with more gas than this.
+const MAX_GAS_LIMIT: u64 = 10_000_000;
+
+const DEFAULT_SAFETY_MARGIN_PERCENT: u64 = 10;
+
+/// The secret redeeming the HTLCs while measuring. Calldata costs more for
+/// non-zero bytes, hence none of its bytes is zero.
+const SECRET: [u8; 32] = [0xff; 32];
+
+/// Deploys the HTLCs and sends the redeem and refund transactions. Not being
+/// one of the parties of the HTLC, the accounts receiving the funds do not
+/// exist beforehand, which is the most expensive case. Neither is the HTLC
+/// deployed where the token contract is, as it could if the refund identity
+/// deployed it.
+const SENDER: H160 = H160([0xee; 20]);
+
+const TRANSFER_EVENT: &[u8] = b"Transfer(address,address,uint256)";
+
+const APPROVAL_EVENT: &[u8] = b"Approval(address,address,uint256)";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hardfork {
+    Istanbul,
+    Berlin,
+    London,
+    Merge,
+    Shanghai,
+}
+
+impl Hardfork {
+    pub(crate) fn config(self) -> Config {
+        match self {
+            Hardfork::Istanbul => Config::istanbul(),
+            Hardfork::Berlin => Config::berlin(),
+            Hardfork::London => Config::london(),
+            Hardfork::Merge => Config::merge(),
+            Hardfork::Shanghai => Config::shanghai(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// The transaction reverts whatever its gas limit.
+    Reverted { return_data: Vec<u8> },
+    /// The transaction fails whatever its gas limit, e.g. for lack of funds.
+    Halted,
+    /// The HTLC does not manage to transfer its tokens.
+    TokenTransferFailed,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Reverted { return_data } => write!(
+                f,
+                "transaction reverted with {}",
+                return_data
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect::<String>()
+            ),
+            Error::Halted => write!(f, "transaction halted"),
+            Error::TokenTransferFailed => write!(f, "token transfer failed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The ERC20 contract of an `herc20::Htlc`, deployed with the tokens of its
+/// parties in the measured transactions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenContract {
+    /// The code deployed at the token contract address, as returned by
+    /// `eth_getCode`.
+    pub runtime_code: Vec<u8>,
+    /// The storage slot of the `address => uint256` balances mapping, laid
+    /// out as Solidity does.
+    pub balances_slot: u64,
+}
+
+impl TokenContract {
+    fn balance_key(&self, owner: H160) -> H256 {
+        let mut preimage = [0u8; 64];
+        preimage[12..32].copy_from_slice(owner.as_bytes());
+        preimage[56..].copy_from_slice(&self.balances_slot.to_be_bytes());
+
+        H256(keccak256(&preimage))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GasEstimator {
+    hardfork: Hardfork,
+    safety_margin_percent: u64,
+}
+
+impl GasEstimator {
+    pub fn new(hardfork: Hardfork) -> Self {
+        GasEstimator {
+            hardfork,
+            safety_margin_percent: DEFAULT_SAFETY_MARGIN_PERCENT,
+        }
+    }
+
+    /// The percentage of the measured gas added to the estimates.
+    pub fn with_safety_margin(self, safety_margin_percent: u64) -> Self {
+        GasEstimator {
+            safety_margin_percent,
+            ..self
+        }
+    }
+
+    pub fn heth_deploy(&self, htlc: &heth::Htlc) -> Result<u64, Error> {
+        self.deploy(htlc, U256::one())
+    }
+
+    pub fn heth_redeem(&self, htlc: &heth::Htlc) -> Result<u64, Error> {
+        let chain = self.deployed(htlc, htlc.hash_function(), U256::one())?;
+
+        self.estimate(&chain, &call_htlc(SECRET.to_vec()), |_| true)
+    }
+
+    pub fn heth_refund(&self, htlc: &heth::Htlc) -> Result<u64, Error> {
+        let mut chain = self.deployed(htlc, htlc.hash_function(), U256::one())?;
+        expire(&mut chain, htlc.expiry());
+
+        self.estimate(&chain, &call_htlc(Vec::new()), |_| true)
+    }
+
+    pub fn heth_relayer_fee_deploy(&self, htlc: &heth_relayer_fee::Htlc) -> Result<u64, Error> {
+        self.deploy(htlc, relayer_fee_value(htlc))
+    }
+
+    /// Measures the redeem paying the relayer fee to the caller and the rest,
+    /// one wei, to the redeem identity.
+    pub fn heth_relayer_fee_redeem(&self, htlc: &heth_relayer_fee::Htlc) -> Result<u64, Error> {
+        let chain = self.deployed(htlc, HashFunction::Sha256, relayer_fee_value(htlc))?;
+
+        self.estimate(&chain, &call_htlc(SECRET.to_vec()), |_| true)
+    }
+
+    pub fn heth_relayer_fee_refund(&self, htlc: &heth_relayer_fee::Htlc) -> Result<u64, Error> {
+        let mut chain = self.deployed(htlc, HashFunction::Sha256, relayer_fee_value(htlc))?;
+        expire(&mut chain, htlc.expiry());
+
+        self.estimate(&chain, &call_htlc(Vec::new()), |_| true)
+    }
+
+    pub fn herc20_deploy(&self, htlc: &herc20::Htlc) -> Result<u64, Error> {
+        self.deploy(htlc, U256::zero())
+    }
+
+    /// Measures the transfer of the tokens of the refund identity to the
+    /// HTLC.
+    pub fn herc20_fund(&self, htlc: &herc20::Htlc, token: &TokenContract) -> Result<u64, Error> {
+        self.fund(
+            htlc,
+            token,
+            htlc.token_contract_address(),
+            htlc.token_quantity(),
+        )
+    }
+
+    pub fn herc20_redeem(&self, htlc: &herc20::Htlc, token: &TokenContract) -> Result<u64, Error> {
+        let chain = self.deployed_with_tokens(
+            htlc,
+            htlc.hash_function(),
+            token,
+            htlc.token_contract_address(),
+            htlc.token_quantity(),
+        )?;
+
+        self.estimate_transfer(
+            &chain,
+            &call_htlc(SECRET.to_vec()),
+            htlc.token_contract_address(),
+        )
+    }
+
+    pub fn herc20_refund(&self, htlc: &herc20::Htlc, token: &TokenContract) -> Result<u64, Error> {
+        let mut chain = self.deployed_with_tokens(
+            htlc,
+            htlc.hash_function(),
+            token,
+            htlc.token_contract_address(),
+            htlc.token_quantity(),
+        )?;
+        expire(&mut chain, htlc.expiry());
+
+        self.estimate_transfer(
+            &chain,
+            &call_htlc(Vec::new()),
+            htlc.token_contract_address(),
+        )
+    }
+
+    /// Measures the approval, by the refund identity, of the tokens the HTLC
+    /// pulls when deployed by its next transaction.
+    pub fn herc20_transfer_from_approve(
+        &self,
+        htlc: &herc20_transfer_from::Htlc,
+        token: &TokenContract,
+    ) -> Result<u64, Error> {
+        let (chain, approve) = self.approving(htlc, token);
+
+        self.estimate(&chain, &approve, |outcome| {
+            emits(outcome, htlc.token_contract_address(), APPROVAL_EVENT)
+        })
+    }
+
+    /// Measures the deployment pulling the approved tokens. The redeem and
+    /// refund are the ones of the `herc20::Htlc` of the runtime code.
+    pub fn herc20_transfer_from_deploy(
+        &self,
+        htlc: &herc20_transfer_from::Htlc,
+        token: &TokenContract,
+    ) -> Result<u64, Error> {
+        let (mut chain, approve) = self.approving(htlc, token);
+        ensure_succeeded(chain.transact(&approve, MAX_GAS_LIMIT), |outcome| {
+            emits(outcome, htlc.token_contract_address(), APPROVAL_EVENT)
+        })?;
+
+        let deploy = Transaction::Create {
+            from: H160(htlc.refund_identity().0),
+            value: U256::zero(),
+            init_code: htlc.code().to_vec(),
+        };
+
+        self.estimate_transfer(&chain, &deploy, htlc.token_contract_address())
+    }
+
+    pub fn herc20_safe_deploy(&self, htlc: &herc20_safe::Htlc) -> Result<u64, Error> {
+        self.deploy(htlc, U256::zero())
+    }
+
+    pub fn herc20_safe_fund(
+        &self,
+        htlc: &herc20_safe::Htlc,
+        token: &TokenContract,
+    ) -> Result<u64, Error> {
+        self.fund(
+            htlc,
+            token,
+            htlc.token_contract_address(),
+            htlc.token_quantity(),
+        )
+    }
+
+    pub fn herc20_safe_redeem(
+        &self,
+        htlc: &herc20_safe::Htlc,
+        token: &TokenContract,
+    ) -> Result<u64, Error> {
+        let chain = self.deployed_with_tokens(
+            htlc,
+            HashFunction::Sha256,
+            token,
+            htlc.token_contract_address(),
+            htlc.token_quantity(),
+        )?;
+
+        self.estimate_transfer(
+            &chain,
+            &call_htlc(SECRET.to_vec()),
+            htlc.token_contract_address(),
+        )
+    }
+
+    pub fn herc20_safe_refund(
+        &self,
+        htlc: &herc20_safe::Htlc,
+        token: &TokenContract,
+    ) -> Result<u64, Error> {
+        let mut chain = self.deployed_with_tokens(
+            htlc,
+            HashFunction::Sha256,
+            token,
+            htlc.token_contract_address(),
+            htlc.token_quantity(),
+        )?;
+        expire(&mut chain, htlc.expiry());
+
+        self.estimate_transfer(
+            &chain,
+            &call_htlc(Vec::new()),
+            htlc.token_contract_address(),
+        )
+    }
+
+    pub fn herc20_full_balance_deploy(
+        &self,
+        htlc: &herc20_full_balance::Htlc,
+    ) -> Result<u64, Error> {
+        self.deploy(htlc, U256::zero())
+    }
+
+    /// Measures the transfer of `balance` tokens of the refund identity to
+    /// the HTLC.
+    pub fn herc20_full_balance_fund(
+        &self,
+        htlc: &herc20_full_balance::Htlc,
+        token: &TokenContract,
+        balance: TokenQuantity,
+    ) -> Result<u64, Error> {
+        self.fund(htlc, token, htlc.token_contract_address(), balance)
+    }
+
+    /// Measures the redeem of the HTLC holding `balance` tokens.
+    pub fn herc20_full_balance_redeem(
+        &self,
+        htlc: &herc20_full_balance::Htlc,
+        token: &TokenContract,
+        balance: TokenQuantity,
+    ) -> Result<u64, Error> {
+        let chain = self.deployed_with_tokens(
+            htlc,
+            HashFunction::Sha256,
+            token,
+            htlc.token_contract_address(),
+            balance,
+        )?;
+
+        self.estimate_transfer(
+            &chain,
+            &call_htlc(SECRET.to_vec()),
+            htlc.token_contract_address(),
+        )
+    }
+
+    /// Measures the refund of the HTLC holding `balance` tokens.
+    pub fn herc20_full_balance_refund(
+        &self,
+        htlc: &herc20_full_balance::Htlc,
+        token: &TokenContract,
+        balance: TokenQuantity,
+    ) -> Result<u64, Error> {
+        let mut chain = self.deployed_with_tokens(
+            htlc,
+            HashFunction::Sha256,
+            token,
+            htlc.token_contract_address(),
+            balance,
+        )?;
+        expire(&mut chain, htlc.expiry());
+
+        self.estimate_transfer(
+            &chain,
+            &call_htlc(Vec::new()),
+            htlc.token_contract_address(),
+        )
+    }
+
+    pub fn herc20_relayer_fee_deploy(&self, htlc: &herc20_relayer_fee::Htlc) -> Result<u64, Error> {
+        self.deploy(htlc, U256::zero())
+    }
+
+    pub fn herc20_relayer_fee_fund(
+        &self,
+        htlc: &herc20_relayer_fee::Htlc,
+        token: &TokenContract,
+    ) -> Result<u64, Error> {
+        self.fund(
+            htlc,
+            token,
+            htlc.token_contract_address(),
+            htlc.token_quantity(),
+        )
+    }
+
+    /// Measures the redeem transferring the net token quantity to the redeem
+    /// identity and the relayer fee to the caller, neither holding tokens
+    /// beforehand.
+    pub fn herc20_relayer_fee_redeem(
+        &self,
+        htlc: &herc20_relayer_fee::Htlc,
+        token: &TokenContract,
+    ) -> Result<u64, Error> {
+        let chain = self.deployed_with_tokens(
+            htlc,
+            HashFunction::Sha256,
+            token,
+            htlc.token_contract_address(),
+            htlc.token_quantity(),
+        )?;
+
+        self.estimate_transfer(
+            &chain,
+            &call_htlc(SECRET.to_vec()),
+            htlc.token_contract_address(),
+        )
+    }
+
+    pub fn herc20_relayer_fee_refund(
+        &self,
+        htlc: &herc20_relayer_fee::Htlc,
+        token: &TokenContract,
+    ) -> Result<u64, Error> {
+        let mut chain = self.deployed_with_tokens(
+            htlc,
+            HashFunction::Sha256,
+            token,
+            htlc.token_contract_address(),
+            htlc.token_quantity(),
+        )?;
+        expire(&mut chain, htlc.expiry());
+
+        self.estimate_transfer(
+            &chain,
+            &call_htlc(Vec::new()),
+            htlc.token_contract_address(),
+        )
+    }
+
+    /// Measures the deployment of the HTLC with `value` wei by its refund
+    /// identity.
+    fn deploy<H: TemplateCode>(&self, htlc: &H, value: U256) -> Result<u64, Error> {
+        let refund_identity = H160(htlc.refund_identity().0);
+        let mut chain = LocalChain::new(self.hardfork);
+        chain.give_eth_to(refund_identity, value);
+
+        self.estimate(
+            &chain,
+            &Transaction::Create {
+                from: refund_identity,
+                value,
+                init_code: htlc.code().to_vec(),
+            },
+            |_| true,
+        )
+    }
+
+    fn fund<H: TemplateCode>(
+        &self,
+        htlc: &H,
+        token: &TokenContract,
+        token_contract_address: Address,
+        token_quantity: TokenQuantity,
+    ) -> Result<u64, Error> {
+        let refund_identity = H160(htlc.refund_identity().0);
+        let mut chain = self.deployed(htlc, HashFunction::Sha256, U256::zero())?;
+        deploy_token(
+            &mut chain,
+            token,
+            token_contract_address,
+            token_quantity,
+            refund_identity,
+        );
+
+        let transfer = Transaction::Call {
+            from: refund_identity,
+            to: H160(token_contract_address.0),
+            data: herc20::Htlc::transfer_erc20_tx_payload(
+                token_quantity,
+                Address(htlc_address().0),
+            ),
+        };
+
+        self.estimate_transfer(&chain, &transfer, token_contract_address)
+    }
+
+    /// A chain on which the refund identity owns the tokens of the HTLC, and
+    /// the transaction approving them for the HTLC it deploys next.
+    fn approving(
+        &self,
+        htlc: &herc20_transfer_from::Htlc,
+        token: &TokenContract,
+    ) -> (LocalChain, Transaction) {
+        let refund_identity = H160(htlc.refund_identity().0);
+        let mut chain = LocalChain::new(self.hardfork);
+        deploy_token(
+            &mut chain,
+            token,
+            htlc.token_contract_address(),
+            htlc.token_quantity(),
+            refund_identity,
+        );
+
+        let htlc_address =
+            contract_address(htlc.refund_identity(), chain.nonce_of(refund_identity) + 1);
+        let approve = Transaction::Call {
+            from: refund_identity,
+            to: H160(htlc.token_contract_address().0),
+            data: herc20_transfer_from::Htlc::approve_erc20_tx_payload(
+                htlc.token_quantity(),
+                htlc_address,
+            ),
+        };
+
+        (chain, approve)
+    }
+
+    /// Deploys the HTLC with `value` wei, locked with the hash of `SECRET`
+    /// instead of its own secret hash.
+    fn deployed<H: TemplateCode>(
+        &self,
+        htlc: &H,
+        hash_function: HashFunction,
+        value: U256,
+    ) -> Result<LocalChain, Error> {
+        let mut init_code = htlc.code().to_vec();
+        init_code[htlc.htlc_layout().secret_hash.clone()]
+            .copy_from_slice(&hash_function.secret_hash(&SECRET));
+
+        with_htlc(self.hardfork, init_code, value)
+    }
+
+    /// Deploys the HTLC, locked with the hash of `SECRET`, holding
+    /// `token_quantity` tokens of `token`.
+    fn deployed_with_tokens<H: TemplateCode>(
+        &self,
+        htlc: &H,
+        hash_function: HashFunction,
+        token: &TokenContract,
+        token_contract_address: Address,
+        token_quantity: TokenQuantity,
+    ) -> Result<LocalChain, Error> {
+        let mut chain = self.deployed(htlc, hash_function, U256::zero())?;
+        deploy_token(
+            &mut chain,
+            token,
+            token_contract_address,
+            token_quantity,
+            htlc_address(),
+        );
+
+        Ok(chain)
+    }
+
+    /// Estimates a transaction which only succeeds if the token contract
+    /// transfers tokens.
+    fn estimate_transfer(
+        &self,
+        chain: &LocalChain,
+        transaction: &Transaction,
+        token_contract_address: Address,
+    ) -> Result<u64, Error> {
+        self.estimate(chain, transaction, |outcome| {
+            emits(outcome, token_contract_address, TRANSFER_EVENT)
+        })
+    }
+
+    fn estimate<F>(
+        &self,
+        chain: &LocalChain,
+        transaction: &Transaction,
+        succeeded: F,
+    ) -> Result<u64, Error>
+    where
+        F: Fn(&Outcome) -> bool,
+    {
+        let succeeds_with = |gas_limit: u64| {
+            let outcome = chain.call(transaction, gas_limit);
+            outcome.exit_reason.is_succeed() && succeeded(&outcome)
+        };
+
+        ensure_succeeded(chain.call(transaction, MAX_GAS_LIMIT), &succeeded)?;
+
+        // Transactions may succeed with less gas but not with a bit more, e.g.
+        // the ERC20 HTLC forwarding a fixed amount of gas less than it has, so
+        // the estimate is measured again with its margin.
+        let mut lower_bound = 0;
+        loop {
+            let (mut low, mut high) = (lower_bound, MAX_GAS_LIMIT);
+            while low < high {
+                let middle = low + (high - low) / 2;
+                if succeeds_with(middle) {
+                    high = middle;
+                } else {
+                    low = middle + 1;
+                }
+            }
+
+            let estimate = high + high * self.safety_margin_percent / 100;
+            if estimate >= MAX_GAS_LIMIT {
+                return Ok(estimate.min(MAX_GAS_LIMIT));
+            }
+            if succeeds_with(estimate) {
+                return Ok(estimate);
+            }
+
+            lower_bound = estimate + 1;
+        }
+    }
+}
+
+/// The address the HTLC is deployed at, the first contract of `SENDER`.
+fn htlc_address() -> H160 {
+    H160(contract_address(Address(SENDER.0), 0).0)
+}
+
+/// A chain on which `SENDER` deployed the HTLC with `value` wei.
+fn with_htlc(hardfork: Hardfork, init_code: Vec<u8>, value: U256) -> Result<LocalChain, Error> {
+    let mut chain = LocalChain::new(hardfork);
+    chain.give_eth_to(SENDER, value);
+
+    let deploy = Transaction::Create {
+        from: SENDER,
+        value,
+        init_code,
+    };
+    ensure_succeeded(chain.transact(&deploy, MAX_GAS_LIMIT), |_| true)?;
+
+    Ok(chain)
+}
+
+/// Deploys `token` at `token_contract_address`, with `owner` owning
+/// `token_quantity` tokens.
+fn deploy_token(
+    chain: &mut LocalChain,
+    token: &TokenContract,
+    token_contract_address: Address,
+    TokenQuantity(token_quantity): TokenQuantity,
+    owner: H160,
+) {
+    let token_contract_address = H160(token_contract_address.0);
+
+    chain.set_code(token_contract_address, token.runtime_code.clone());
+    chain.set_storage(
+        token_contract_address,
+        token.balance_key(owner),
+        H256(token_quantity),
+    );
+}
+
+/// The relayer fee and a wei for the redeem identity.
+fn relayer_fee_value(htlc: &heth_relayer_fee::Htlc) -> U256 {
+    U256::from(htlc.relayer_fee()) + U256::one()
+}
+
+fn expire(chain: &mut LocalChain, expiry: Expiry) {
+    match expiry {
+        Expiry::Timestamp(timestamp) => {
+            chain.set_block_timestamp(U256::from(timestamp) + U256::one())
+        }
+        Expiry::BlockHeight(block_height) => {
+            chain.set_block_number(U256::from(block_height) + U256::one())
+        }
+    }
+}
+
+fn call_htlc(data: Vec<u8>) -> Transaction {
+    Transaction::Call {
+        from: SENDER,
+        to: htlc_address(),
+        data,
+    }
+}
+
+fn ensure_succeeded<F>(outcome: Outcome, succeeded: F) -> Result<(), Error>
+where
+    F: Fn(&Outcome) -> bool,
+{
+    match outcome.exit_reason {
+        ExitReason::Succeed(_) if succeeded(&outcome) => Ok(()),
+        ExitReason::Succeed(_) => Err(Error::TokenTransferFailed),
+        ExitReason::Revert(_) => Err(Error::Reverted {
+            return_data: outcome.return_data,
+        }),
+        ExitReason::Error(_) | ExitReason::Fatal(_) => Err(Error::Halted),
+    }
+}
+
+/// Whether `token_contract_address` emitted the ERC20 `event`.
+fn emits(outcome: &Outcome, token_contract_address: Address, event: &[u8]) -> bool {
+    let topic = H256(keccak256(event));
+
+    outcome.logs.iter().any(|log| {
+        log.address == H160(token_contract_address.0) && log.topics.first() == Some(&topic)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::HashFunction;
+
+    fn heth_htlc() -> heth::Htlc {
+        heth::Htlc::new(
+            Expiry::Timestamp(2_000_000_000),
+            Address([1u8; 20]),
+            Address([2u8; 20]),
+            [3u8; 32],
+            HashFunction::Sha256,
+        )
+    }
+
+    fn herc20_htlc() -> herc20::Htlc {
+        herc20::Htlc::new(
+            Expiry::Timestamp(2_000_000_000),
+            Address([1u8; 20]),
+            Address([2u8; 20]),
+            [3u8; 32],
+            HashFunction::Sha256,
+            Address([4u8; 20]),
+            TokenQuantity([5u8; 32]),
+        )
+    }
+
+    const HARDFORKS: [Hardfork; 5] = [
+        Hardfork::Istanbul,
+        Hardfork::Berlin,
+        Hardfork::London,
+        Hardfork::Merge,
+        Hardfork::Shanghai,
+    ];
+
+    #[test]
+    fn heth_estimates_are_below_the_hard_coded_gas_limits() {
+        let htlc = heth_htlc();
+        let relayer_fee_htlc = heth_relayer_fee::Htlc::new(
+            Expiry::Timestamp(2_000_000_000),
+            Address([1u8; 20]),
+            Address([2u8; 20]),
+            [3u8; 32],
+            u128::MAX - 1,
+        );
+
+        for hardfork in &HARDFORKS {
+            let estimator = GasEstimator::new(*hardfork);
+
+            assert!(estimator.heth_deploy(&htlc).unwrap() <= heth::Htlc::deploy_tx_gas_limit());
+            assert!(estimator.heth_redeem(&htlc).unwrap() <= heth::Htlc::redeem_tx_gas_limit());
+            assert!(estimator.heth_refund(&htlc).unwrap() <= heth::Htlc::refund_tx_gas_limit());
+            assert!(
+                estimator
+                    .heth_relayer_fee_deploy(&relayer_fee_htlc)
+                    .unwrap()
+                    <= heth_relayer_fee::Htlc::deploy_tx_gas_limit()
+            );
+            assert!(
+                estimator
+                    .heth_relayer_fee_redeem(&relayer_fee_htlc)
+                    .unwrap()
+                    <= heth_relayer_fee::Htlc::redeem_tx_gas_limit()
+            );
+            assert!(
+                estimator
+                    .heth_relayer_fee_refund(&relayer_fee_htlc)
+                    .unwrap()
+                    <= heth_relayer_fee::Htlc::refund_tx_gas_limit()
+            );
+        }
+    }
+
+    #[test]
+    fn refund_of_block_height_expiry_is_estimated() {
+        let htlc = heth::Htlc::new(
+            Expiry::BlockHeight(1_000_000),
+            Address([1u8; 20]),
+            Address([2u8; 20]),
+            [3u8; 32],
+            HashFunction::Sha256,
+        );
+
+        assert!(GasEstimator::new(Hardfork::London)
+            .heth_refund(&htlc)
+            .is_ok());
+    }
+
+    #[test]
+    fn keccak256_hashlock_is_cheaper_to_redeem() {
+        let estimator = GasEstimator::new(Hardfork::London);
+        let keccak256_htlc = heth::Htlc::new(
+            Expiry::Timestamp(2_000_000_000),
+            Address([1u8; 20]),
+            Address([2u8; 20]),
+            HashFunction::Keccak256.secret_hash(&SECRET),
+            HashFunction::Keccak256,
+        );
+
+        assert!(
+            estimator.heth_redeem(&keccak256_htlc).unwrap()
+                < estimator.heth_redeem(&heth_htlc()).unwrap()
+        );
+    }
+
+    #[test]
+    fn safety_margin_is_added_to_the_measured_gas() {
+        let htlc = heth_htlc();
+        let measured = GasEstimator::new(Hardfork::London)
+            .with_safety_margin(0)
+            .heth_redeem(&htlc)
+            .unwrap();
+
+        assert_eq!(
+            GasEstimator::new(Hardfork::London)
+                .with_safety_margin(50)
+                .heth_redeem(&htlc)
+                .unwrap(),
+            measured + measured / 2
+        );
+    }
+
+    #[test]
+    fn herc20_paths_fail_without_token_contract_code() {
+        let estimator = GasEstimator::new(Hardfork::Shanghai);
+        let token = TokenContract {
+            runtime_code: Vec::new(),
+            balances_slot: 0,
+        };
+
+        assert!(estimator.herc20_deploy(&herc20_htlc()).is_ok());
+        assert_eq!(
+            estimator.herc20_redeem(&herc20_htlc(), &token),
+            Err(Error::TokenTransferFailed)
+        );
+        assert_eq!(
+            estimator.herc20_fund(&herc20_htlc(), &token),
+            Err(Error::TokenTransferFailed)
+        );
+    }
+
+    #[test]
+    fn refund_before_expiry_is_reported_as_reverted() {
+        let mut chain = GasEstimator::new(Hardfork::Istanbul)
+            .deployed(&heth_htlc(), HashFunction::Sha256, U256::one())
+            .unwrap();
+        chain.set_block_timestamp(U256::one());
+
+        let error = ensure_succeeded(chain.call(&call_htlc(Vec::new()), MAX_GAS_LIMIT), |_| true)
+            .unwrap_err();
+
+        match error {
+            Error::Reverted { .. } => {}
+            _ => panic!("expected a revert, got {:?}", error),
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! An herc20 HTLC transferring its whole token balance on redeem and refund.
+//!
+//! The contract queries `balanceOf` for its own address instead of
+//! transferring a fixed quantity, hence tokens sent in excess are not lost
+//! and fee-on-transfer tokens delivering less than sent can still be redeemed
+//! or refunded. The agreed quantity is not part of the contract: check the
+//! funding with `Htlc::is_funded`. Like `herc20_safe::Htlc`, redeem and
+//! refund revert with `TRANSFER_FAILED` when the token transfer fails.
+use crate::ethereum::{
+    herc20,
+    layout::{HtlcLayout, Layout, Template, TemplateCode},
+    read_address, Address, DeployedHtlcCode, Expiry, TokenQuantity,
+};
+use crate::FitIntoPlaceholderSlice;
+use hex_literal::hex;
+use std::ops::Range;
+
+pub const CONTRACT_TEMPLATE: [u8;494] = hex!("6101df61000f6000396101df6000f3361561007957602036141561004f57602060006000376020602160206000600060026048f17f100000000000000000000000000000000000000000000000000000000000000160215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b426320000002106100f1577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a1733000000000000000000000000000000000000003602052610134565b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1734000000000000000000000000000000000000004602052610134565b7360000000000000000000000000000000000000066370a0823160605230608052602060406024607c845afa156101b55760203d106101b55763a9059cbb600052602060606044601c600085620186a05a03f1156101b5573d156101a75760203d106101b557606051156101b5576101af565b803b156101b5575b50602051ff5b7f7472616e736665724661696c656400000000000000000000000000000000000060005260206000fd");
+
+pub const BLOCK_HEIGHT_CONTRACT_TEMPLATE: [u8;494] = hex!("6101df61000f6000396101df6000f3361561007957602036141561004f57602060006000376020602160206000600060026048f17f100000000000000000000000000000000000000000000000000000000000000160215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b436320000002106100f1577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a1733000000000000000000000000000000000000003602052610134565b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1734000000000000000000000000000000000000004602052610134565b7360000000000000000000000000000000000000066370a0823160605230608052602060406024607c845afa156101b55760203d106101b55763a9059cbb600052602060606044601c600085620186a05a03f1156101b5573d156101a75760203d106101b557606051156101b5576101af565b803b156101b5575b50602051ff5b7f7472616e736665724661696c656400000000000000000000000000000000000060005260206000fd");
+
+/// Where the parameters of an HTLC are in the code of a contract template.
+#[derive(Debug)]
+struct FullBalanceLayout {
+    htlc: HtlcLayout,
+    token_contract_address: Range<usize>,
+}
+
+impl Layout for FullBalanceLayout {
+    fn htlc(&self) -> &HtlcLayout {
+        &self.htlc
+    }
+
+    fn other_placeholders(&self) -> Vec<Range<usize>> {
+        vec![self.token_contract_address.clone()]
+    }
+}
+
+const LAYOUT: FullBalanceLayout = FullBalanceLayout {
+    htlc: HtlcLayout {
+        secret_hash: 53..85,
+        expiry_opcode: 137,
+        expiry: 139..143,
+        redeem_identity: 229..249,
+        refund_identity: 296..316,
+    },
+    token_contract_address: 325..345,
+};
+
+const TEMPLATES: [Template; 2] = [
+    (&CONTRACT_TEMPLATE, &LAYOUT),
+    (&BLOCK_HEIGHT_CONTRACT_TEMPLATE, &LAYOUT),
+];
+
+#[derive(Debug, Clone)]
+pub struct Htlc(Vec<u8>);
+
+impl From<Htlc> for Vec<u8> {
+    fn from(htlc: Htlc) -> Self {
+        htlc.0
+    }
+}
+
+impl TemplateCode for Htlc {
+    const TEMPLATES: &'static [Template] = &TEMPLATES;
+
+    fn from_code(code: Vec<u8>) -> Self {
+        Htlc(code)
+    }
+
+    fn code(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn htlc_layout(&self) -> &'static HtlcLayout {
+        &LAYOUT.htlc
+    }
+}
+
+impl DeployedHtlcCode for Htlc {}
+
+impl Htlc {
+    pub fn new(
+        expiry: Expiry,
+        refund_identity: Address,
+        redeem_identity: Address,
+        secret_hash: [u8; 32],
+        token_contract_address: Address,
+    ) -> Self {
+        let template = match expiry {
+            Expiry::Timestamp(_) => &CONTRACT_TEMPLATE[..],
+            Expiry::BlockHeight(_) => &BLOCK_HEIGHT_CONTRACT_TEMPLATE[..],
+        };
+
+        let mut contract = template.to_vec();
+        LAYOUT.htlc.fit_into_placeholders(
+            &mut contract,
+            expiry,
+            refund_identity,
+            redeem_identity,
+            secret_hash,
+        );
+        token_contract_address
+            .fit_into_placeholder_slice(&mut contract[LAYOUT.token_contract_address]);
+
+        Htlc(contract)
+    }
+
+    pub fn token_contract_address(&self) -> Address {
+        read_address(&self.0[LAYOUT.token_contract_address])
+    }
+
+    /// Whether the token balance of the HTLC, e.g. queried with the payload
+    /// of `balance_of_tx_payload`, covers the agreed `token_quantity`.
+    /// Note: both quantities must be BigEndian
+    pub fn is_funded(balance: TokenQuantity, token_quantity: TokenQuantity) -> bool {
+        balance.0 >= token_quantity.0
+    }
+
+    pub fn deploy_tx_gas_limit() -> u64 {
+        // 170_764 to 170_799 estimated by the GasEstimator
+        180_000
+    }
+
+    pub fn fund_tx_gas_limit() -> u64 {
+        herc20::Htlc::fund_tx_gas_limit()
+    }
+
+    pub fn redeem_tx_gas_limit() -> u64 {
+        // 68_208 to 172_268 estimated by the GasEstimator, the transfer forwarding all but
+        // 100_000 gas fails with some limits in between
+        180_000
+    }
+
+    pub fn refund_tx_gas_limit() -> u64 {
+        // 66_442 to 69_742 estimated by the GasEstimator
+        100_000
+    }
+
+    /// Constructs the payload to transfer `Erc20` tokens to a `to_address`
+    /// Note: `token_quantity` must be BigEndian
+    pub fn transfer_erc20_tx_payload(
+        token_quantity: TokenQuantity,
+        to_address: Address,
+    ) -> Vec<u8> {
+        herc20::Htlc::transfer_erc20_tx_payload(token_quantity, to_address)
+    }
+
+    /// Constructs the payload of the `eth_call` querying the `Erc20` token
+    /// balance of `owner`, e.g. the HTLC
+    pub fn balance_of_tx_payload(owner: Address) -> Vec<u8> {
+        let balance_of_fn_abi = hex!("70A08231");
+
+        let mut data = [0u8; 4 + 32];
+        data[..4].copy_from_slice(&balance_of_fn_abi);
+        data[16..36].copy_from_slice(&owner.0);
+
+        data.to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::{layout::tests::SECRET_HASH, HtlcCode, UnknownContract};
+    use crate::ethereum::{Expiry, HashFunction};
+    use hex::ToHex;
+
+    fn token_quantity(amount: u8) -> TokenQuantity {
+        let mut token_quantity = [0u8; 32];
+        token_quantity[31] = amount;
+        TokenQuantity(token_quantity)
+    }
+
+    #[test]
+    fn token_contract_address_is_extracted_from_deployed_code() {
+        let htlc = Htlc::new(
+            Expiry::Timestamp(1_552_263_040),
+            Address([1u8; 20]),
+            Address([2u8; 20]),
+            SECRET_HASH,
+            Address([3u8; 20]),
+        );
+
+        let deployed = Htlc::from_runtime_code(htlc.runtime_code()).unwrap();
+
+        assert_eq!(deployed.token_contract_address(), Address([3u8; 20]));
+    }
+
+    #[test]
+    fn herc20_code_is_not_recognized() {
+        let herc20: Vec<u8> = herc20::Htlc::new(
+            Expiry::Timestamp(3_000_000),
+            Address([0u8; 20]),
+            Address([0u8; 20]),
+            SECRET_HASH,
+            HashFunction::Sha256,
+            Address([0u8; 20]),
+            TokenQuantity([0u8; 32]),
+        )
+        .into();
+
+        assert_eq!(Htlc::from_init_code(&herc20).unwrap_err(), UnknownContract);
+    }
+
+    #[test]
+    fn balance_meeting_the_quantity_funds_the_htlc() {
+        let mut large_balance = token_quantity(0);
+        large_balance.0[0] = 1;
+
+        assert!(Htlc::is_funded(token_quantity(10), token_quantity(10)));
+        assert!(Htlc::is_funded(token_quantity(11), token_quantity(10)));
+        assert!(Htlc::is_funded(large_balance, token_quantity(255)));
+        assert!(!Htlc::is_funded(token_quantity(9), token_quantity(10)));
+    }
+
+    #[test]
+    fn balance_of_payload_encodes_the_owner() {
+        let payload = Htlc::balance_of_tx_payload(Address([0xab; 20]));
+
+        assert_eq!(
+            payload.encode_hex::<String>(),
+            "70a08231000000000000000000000000abababababababababababababababababababab"
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! An herc20 HTLC paying a fixed relayer fee in tokens to the caller of a
+//! successful redeem and the rest of the token quantity to the redeem
+//! identity.
+//!
+//! Anyone knowing the secret can redeem an HTLC, hence a relayer can pay the
+//! gas of the redeem transaction for a redeemer without ether and be paid
+//! back in tokens. Refunding transfers the whole token quantity and pays no
+//! fee.
+//!
+//! The transfer to the redeem or refund identity is checked like in
+//! `herc20_safe::Htlc`: if it fails, e.g. because the caller left it too
+//! little gas, the redeem reverts with `TRANSFER_FAILED` before paying the
+//! fee. Only the relayer loses out if the transfer of the fee fails.
+use crate::ethereum::{
+    herc20::{self, Herc20Layout},
+    layout::{read_bytes32, HtlcLayout, Layout, Template, TemplateCode},
+    Address, DeployedHtlcCode, Expiry, TokenQuantity,
+};
+use crate::FitIntoPlaceholderSlice;
+use hex_literal::hex;
+use std::{fmt, ops::Range};
+
+pub const CONTRACT_TEMPLATE: [u8;608] = hex!("61025161000f6000396102516000f3361561007957602036141561004f57602060006000376020602160206000600060026048f17f100000000000000000000000000000000000000000000000000000000000000160215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b42632000000210610117577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a17330000000000000000000000000000000000000036020527f80000000000000000000000000000000000000000000000000000000000000086040526001610180565b7f726566756e64656400000000000000000000000000000000000000000000000060006000a17340000000000000000000000000000000000000046020527f50000000000000000000000000000000000000000000000000000000000000056040526000610180565b63a9059cbb600052736000000000000000000000000000000000000006602060606044601c600085620186a05a03f115610227573d156101cf5760203d106102275760605115610227576101d7565b803b15610227575b90156102215763a9059cbb606052336080527f700000000000000000000000000000000000000000000000000000000000000760a052602060c06044607c600085620186a05a03f1505b50602051ff5b7f7472616e736665724661696c656400000000000000000000000000000000000060005260206000fd");
+
+pub const BLOCK_HEIGHT_CONTRACT_TEMPLATE: [u8;608] = hex!("61025161000f6000396102516000f3361561007957602036141561004f57602060006000376020602160206000600060026048f17f100000000000000000000000000000000000000000000000000000000000000160215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b43632000000210610117577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a17330000000000000000000000000000000000000036020527f80000000000000000000000000000000000000000000000000000000000000086040526001610180565b7f726566756e64656400000000000000000000000000000000000000000000000060006000a17340000000000000000000000000000000000000046020527f50000000000000000000000000000000000000000000000000000000000000056040526000610180565b63a9059cbb600052736000000000000000000000000000000000000006602060606044601c600085620186a05a03f115610227573d156101cf5760203d106102275760605115610227576101d7565b803b15610227575b90156102215763a9059cbb606052336080527f700000000000000000000000000000000000000000000000000000000000000760a052602060c06044607c600085620186a05a03f1505b50602051ff5b7f7472616e736665724661696c656400000000000000000000000000000000000060005260206000fd");
+
+/// Where the parameters of an HTLC are in the code of a contract template.
+#[derive(Debug)]
+struct RelayerFeeLayout {
+    herc20: Herc20Layout,
+    net_token_quantity: Range<usize>,
+    relayer_fee: Range<usize>,
+}
+
+impl Layout for RelayerFeeLayout {
+    fn htlc(&self) -> &HtlcLayout {
+        &self.herc20.htlc
+    }
+
+    fn other_placeholders(&self) -> Vec<Range<usize>> {
+        let mut placeholders = self.herc20.other_placeholders();
+        placeholders.extend(vec![
+            self.net_token_quantity.clone(),
+            self.relayer_fee.clone(),
+        ]);
+
+        placeholders
+    }
+}
+
+const LAYOUT: RelayerFeeLayout = RelayerFeeLayout {
+    herc20: Herc20Layout {
+        htlc: HtlcLayout {
+            secret_hash: 53..85,
+            expiry_opcode: 137,
+            expiry: 139..143,
+            redeem_identity: 229..249,
+            refund_identity: 334..354,
+        },
+        token_quantity: 358..390,
+        token_contract_address: 409..429,
+    },
+    net_token_quantity: 253..285,
+    relayer_fee: 506..538,
+};
+
+const TEMPLATES: [Template; 2] = [
+    (&CONTRACT_TEMPLATE, &LAYOUT),
+    (&BLOCK_HEIGHT_CONTRACT_TEMPLATE, &LAYOUT),
+];
+
+/// The relayer fee of an HTLC is larger than its token quantity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FeeExceedsQuantity;
+
+impl fmt::Display for FeeExceedsQuantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "relayer fee exceeds the token quantity")
+    }
+}
+
+impl std::error::Error for FeeExceedsQuantity {}
+
+#[derive(Debug, Clone)]
+pub struct Htlc(Vec<u8>);
+
+impl From<Htlc> for Vec<u8> {
+    fn from(htlc: Htlc) -> Self {
+        htlc.0
+    }
+}
+
+impl TemplateCode for Htlc {
+    const TEMPLATES: &'static [Template] = &TEMPLATES;
+
+    fn from_code(code: Vec<u8>) -> Self {
+        Htlc(code)
+    }
+
+    fn code(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn htlc_layout(&self) -> &'static HtlcLayout {
+        &LAYOUT.herc20.htlc
+    }
+
+    /// The relayer fee and the net token quantity must add up to the token
+    /// quantity, otherwise the redeem would not spend what the refund does.
+    fn has_consistent_parameters(&self) -> bool {
+        checked_sub(self.token_quantity(), self.relayer_fee())
+            .is_some_and(|net_token_quantity| net_token_quantity.0 == self.net_token_quantity().0)
+    }
+}
+
+impl DeployedHtlcCode for Htlc {}
+
+impl Htlc {
+    /// Constructs an HTLC locking `token_quantity` of which `relayer_fee` is
+    /// paid to the caller of the redeem.
+    /// Note: `token_quantity` and `relayer_fee` must be BigEndian
+    pub fn new(
+        expiry: Expiry,
+        refund_identity: Address,
+        redeem_identity: Address,
+        secret_hash: [u8; 32],
+        token_contract_address: Address,
+        token_quantity: TokenQuantity,
+        relayer_fee: TokenQuantity,
+    ) -> Result<Self, FeeExceedsQuantity> {
+        let net_token_quantity =
+            checked_sub(token_quantity, relayer_fee).ok_or(FeeExceedsQuantity)?;
+
+        let template = match expiry {
+            Expiry::Timestamp(_) => &CONTRACT_TEMPLATE[..],
+            Expiry::BlockHeight(_) => &BLOCK_HEIGHT_CONTRACT_TEMPLATE[..],
+        };
+
+        let mut contract = template.to_vec();
+        LAYOUT.herc20.htlc.fit_into_placeholders(
+            &mut contract,
+            expiry,
+            refund_identity,
+            redeem_identity,
+            secret_hash,
+        );
+        LAYOUT.herc20.fit_token_into_placeholders(
+            &mut contract,
+            token_contract_address,
+            token_quantity,
+        );
+        net_token_quantity.fit_into_placeholder_slice(&mut contract[LAYOUT.net_token_quantity]);
+        relayer_fee.fit_into_placeholder_slice(&mut contract[LAYOUT.relayer_fee]);
+
+        Ok(Htlc(contract))
+    }
+
+    pub fn token_contract_address(&self) -> Address {
+        LAYOUT.herc20.token_contract_address(&self.0)
+    }
+
+    /// The quantity the HTLC must be funded with, transferred back whole on
+    /// refund.
+    pub fn token_quantity(&self) -> TokenQuantity {
+        LAYOUT.herc20.token_quantity(&self.0)
+    }
+
+    /// The quantity paid to the caller of the redeem.
+    pub fn relayer_fee(&self) -> TokenQuantity {
+        TokenQuantity(read_bytes32(&self.0[LAYOUT.relayer_fee]))
+    }
+
+    /// The quantity received by the redeem identity.
+    pub fn net_token_quantity(&self) -> TokenQuantity {
+        TokenQuantity(read_bytes32(&self.0[LAYOUT.net_token_quantity]))
+    }
+
+    pub fn deploy_tx_gas_limit() -> u64 {
+        // 196_662 to 196_704 estimated by the GasEstimator
+        200_000
+    }
+
+    pub fn fund_tx_gas_limit() -> u64 {
+        herc20::Htlc::fund_tx_gas_limit()
+    }
+
+    pub fn redeem_tx_gas_limit() -> u64 {
+        // 95_830 to 98_360 estimated by the GasEstimator
+        100_000
+    }
+
+    pub fn refund_tx_gas_limit() -> u64 {
+        // 64_157 to 68_887 estimated by the GasEstimator
+        100_000
+    }
+
+    /// Constructs the payload to transfer `Erc20` tokens to a `to_address`
+    /// Note: `token_quantity` must be BigEndian
+    pub fn transfer_erc20_tx_payload(
+        token_quantity: TokenQuantity,
+        to_address: Address,
+    ) -> Vec<u8> {
+        herc20::Htlc::transfer_erc20_tx_payload(token_quantity, to_address)
+    }
+}
+
+/// Subtracts two BigEndian quantities, `None` on underflow.
+fn checked_sub(minuend: TokenQuantity, subtrahend: TokenQuantity) -> Option<TokenQuantity> {
+    let mut difference = [0u8; 32];
+    let mut borrow = false;
+
+    for i in (0..32).rev() {
+        let (byte, borrow_subtrahend) = minuend.0[i].overflowing_sub(subtrahend.0[i]);
+        let (byte, borrow_previous) = byte.overflowing_sub(u8::from(borrow));
+        difference[i] = byte;
+        borrow = borrow_subtrahend || borrow_previous;
+    }
+
+    if borrow {
+        None
+    } else {
+        Some(TokenQuantity(difference))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::{layout::tests::SECRET_HASH, HtlcCode, UnknownContract};
+    use crate::ethereum::{Expiry, HashFunction};
+    use byteorder::{BigEndian, ByteOrder};
+
+    fn token_quantity(amount: u16) -> TokenQuantity {
+        let mut token_quantity = [0u8; 32];
+        BigEndian::write_u16(&mut token_quantity[30..], amount);
+        TokenQuantity(token_quantity)
+    }
+
+    fn htlc(quantity: u16, relayer_fee: u16) -> Result<Htlc, FeeExceedsQuantity> {
+        Htlc::new(
+            Expiry::Timestamp(1_552_263_040),
+            Address([1u8; 20]),
+            Address([2u8; 20]),
+            SECRET_HASH,
+            Address([3u8; 20]),
+            token_quantity(quantity),
+            token_quantity(relayer_fee),
+        )
+    }
+
+    #[test]
+    fn token_parameters_are_extracted_from_deployed_code() {
+        let htlc = htlc(1_000, 30).unwrap();
+
+        let deployed = Htlc::from_runtime_code(htlc.runtime_code()).unwrap();
+
+        assert_eq!(deployed.token_contract_address(), Address([3u8; 20]));
+        assert_eq!(deployed.token_quantity().0, token_quantity(1_000).0);
+        assert_eq!(deployed.relayer_fee().0, token_quantity(30).0);
+        assert_eq!(deployed.net_token_quantity().0, token_quantity(970).0);
+    }
+
+    #[test]
+    fn net_token_quantity_borrows_across_bytes() {
+        let htlc = htlc(0x0100, 0x01).unwrap();
+
+        assert_eq!(htlc.net_token_quantity().0, token_quantity(0xff).0);
+    }
+
+    #[test]
+    fn fee_exceeding_the_quantity_is_rejected() {
+        assert_eq!(htlc(30, 30).unwrap().net_token_quantity().0, [0u8; 32]);
+        assert_eq!(htlc(30, 31).unwrap_err(), FeeExceedsQuantity);
+    }
+
+    #[test]
+    fn code_not_adding_up_the_quantities_is_not_recognized() {
+        let mut code: Vec<u8> = htlc(1_000, 30).unwrap().into();
+        code[LAYOUT.net_token_quantity.end - 1] += 1;
+
+        assert_eq!(Htlc::from_init_code(&code).unwrap_err(), UnknownContract);
+    }
+
+    #[test]
+    fn herc20_code_is_not_recognized() {
+        let herc20: Vec<u8> = herc20::Htlc::new(
+            Expiry::Timestamp(3_000_000),
+            Address([0u8; 20]),
+            Address([0u8; 20]),
+            SECRET_HASH,
+            HashFunction::Sha256,
+            Address([0u8; 20]),
+            TokenQuantity([0u8; 32]),
+        )
+        .into();
+
+        assert_eq!(Htlc::from_init_code(&herc20).unwrap_err(), UnknownContract);
+    }
+}
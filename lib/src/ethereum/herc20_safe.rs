@@ -0,0 +1,179 @@
+//! An herc20 HTLC whose redeem and refund revert unless the token transfer
+//! succeeded, in the way of OpenZeppelin's `SafeERC20`.
+//!
+//! Tokens may return `true`, or nothing like USDT, from `transfer`. A token
+//! returning `false` or reverting makes the redeem or refund revert with
+//! `TRANSFER_FAILED` instead of destroying the HTLC with its tokens stuck.
+use crate::ethereum::{
+    herc20::{self, Herc20Layout},
+    layout::{HtlcLayout, Template, TemplateCode},
+    Address, DeployedHtlcCode, Expiry, TokenQuantity,
+};
+use hex_literal::hex;
+
+pub const CONTRACT_TEMPLATE: [u8;494] = hex!("6101df61000f6000396101df6000f3361561007957602036141561004f57602060006000376020602160206000600060026048f17f100000000000000000000000000000000000000000000000000000000000000160215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b426320000002106100f1577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a1733000000000000000000000000000000000000003602052610134565b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1734000000000000000000000000000000000000004602052610134565b63a9059cbb6000527f5000000000000000000000000000000000000000000000000000000000000005604052736000000000000000000000000000000000000006602060606044601c600085620186a05a03f1156101b5573d156101a75760203d106101b557606051156101b5576101af565b803b156101b5575b50602051ff5b7f7472616e736665724661696c656400000000000000000000000000000000000060005260206000fd");
+
+pub const BLOCK_HEIGHT_CONTRACT_TEMPLATE: [u8;494] = hex!("6101df61000f6000396101df6000f3361561007957602036141561004f57602060006000376020602160206000600060026048f17f100000000000000000000000000000000000000000000000000000000000000160215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b436320000002106100f1577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a1733000000000000000000000000000000000000003602052610134565b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1734000000000000000000000000000000000000004602052610134565b63a9059cbb6000527f5000000000000000000000000000000000000000000000000000000000000005604052736000000000000000000000000000000000000006602060606044601c600085620186a05a03f1156101b5573d156101a75760203d106101b557606051156101b5576101af565b803b156101b5575b50602051ff5b7f7472616e736665724661696c656400000000000000000000000000000000000060005260206000fd");
+
+const LAYOUT: Herc20Layout = Herc20Layout {
+    htlc: HtlcLayout {
+        secret_hash: 53..85,
+        expiry_opcode: 137,
+        expiry: 139..143,
+        redeem_identity: 229..249,
+        refund_identity: 296..316,
+    },
+    token_quantity: 333..365,
+    token_contract_address: 369..389,
+};
+
+const TEMPLATES: [Template; 2] = [
+    (&CONTRACT_TEMPLATE, &LAYOUT),
+    (&BLOCK_HEIGHT_CONTRACT_TEMPLATE, &LAYOUT),
+];
+
+#[derive(Debug, Clone)]
+pub struct Htlc(Vec<u8>);
+
+impl From<Htlc> for Vec<u8> {
+    fn from(htlc: Htlc) -> Self {
+        htlc.0
+    }
+}
+
+impl TemplateCode for Htlc {
+    const TEMPLATES: &'static [Template] = &TEMPLATES;
+
+    fn from_code(code: Vec<u8>) -> Self {
+        Htlc(code)
+    }
+
+    fn code(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn htlc_layout(&self) -> &'static HtlcLayout {
+        &LAYOUT.htlc
+    }
+}
+
+impl DeployedHtlcCode for Htlc {}
+
+impl Htlc {
+    pub fn new(
+        expiry: Expiry,
+        refund_identity: Address,
+        redeem_identity: Address,
+        secret_hash: [u8; 32],
+        token_contract_address: Address,
+        token_quantity: TokenQuantity,
+    ) -> Self {
+        let template = match expiry {
+            Expiry::Timestamp(_) => &CONTRACT_TEMPLATE[..],
+            Expiry::BlockHeight(_) => &BLOCK_HEIGHT_CONTRACT_TEMPLATE[..],
+        };
+
+        let mut contract = template.to_vec();
+        LAYOUT.htlc.fit_into_placeholders(
+            &mut contract,
+            expiry,
+            refund_identity,
+            redeem_identity,
+            secret_hash,
+        );
+        LAYOUT.fit_token_into_placeholders(&mut contract, token_contract_address, token_quantity);
+
+        Htlc(contract)
+    }
+
+    pub fn token_contract_address(&self) -> Address {
+        LAYOUT.token_contract_address(&self.0)
+    }
+
+    pub fn token_quantity(&self) -> TokenQuantity {
+        LAYOUT.token_quantity(&self.0)
+    }
+
+    pub fn deploy_tx_gas_limit() -> u64 {
+        // 170_368 to 170_403 estimated by the GasEstimator
+        180_000
+    }
+
+    pub fn fund_tx_gas_limit() -> u64 {
+        // 57_209 to 57_429 estimated by the GasEstimator
+        100_000
+    }
+
+    pub fn redeem_tx_gas_limit() -> u64 {
+        // 65_898 to 69_968 estimated by the GasEstimator
+        100_000
+    }
+
+    pub fn refund_tx_gas_limit() -> u64 {
+        // 64_132 to 68_862 estimated by the GasEstimator
+        100_000
+    }
+
+    /// Constructs the payload to transfer `Erc20` tokens to a `to_address`
+    /// Note: `token_quantity` must be BigEndian
+    pub fn transfer_erc20_tx_payload(
+        token_quantity: TokenQuantity,
+        to_address: Address,
+    ) -> Vec<u8> {
+        herc20::Htlc::transfer_erc20_tx_payload(token_quantity, to_address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::{layout::tests::SECRET_HASH, HtlcCode, UnknownContract};
+    use crate::ethereum::{Expiry, HashFunction};
+
+    #[test]
+    fn token_parameters_are_extracted_from_deployed_code() {
+        let htlc = Htlc::new(
+            Expiry::Timestamp(1_552_263_040),
+            Address([1u8; 20]),
+            Address([2u8; 20]),
+            SECRET_HASH,
+            Address([3u8; 20]),
+            TokenQuantity([4u8; 32]),
+        );
+
+        let deployed = Htlc::from_runtime_code(htlc.runtime_code()).unwrap();
+
+        assert_eq!(deployed.token_contract_address(), Address([3u8; 20]));
+        assert_eq!(deployed.token_quantity().0, [4u8; 32]);
+    }
+
+    #[test]
+    fn heth_code_is_not_recognized() {
+        let heth: Vec<u8> = crate::ethereum::heth::Htlc::new(
+            Expiry::Timestamp(3_000_000),
+            Address([0u8; 20]),
+            Address([0u8; 20]),
+            SECRET_HASH,
+            HashFunction::Sha256,
+        )
+        .into();
+
+        assert_eq!(Htlc::from_init_code(&heth).unwrap_err(), UnknownContract);
+    }
+
+    #[test]
+    fn herc20_code_is_not_recognized() {
+        let herc20: Vec<u8> = herc20::Htlc::new(
+            Expiry::Timestamp(3_000_000),
+            Address([0u8; 20]),
+            Address([0u8; 20]),
+            SECRET_HASH,
+            HashFunction::Sha256,
+            Address([0u8; 20]),
+            TokenQuantity([0u8; 32]),
+        )
+        .into();
+
+        assert_eq!(Htlc::from_init_code(&herc20).unwrap_err(), UnknownContract);
+    }
+}
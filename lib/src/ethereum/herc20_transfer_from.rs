@@ -0,0 +1,269 @@
+//! An herc20 HTLC funded by its own deployment.
+//!
+//! The init code pulls the token quantity from the deployer with
+//! `transferFrom`, which the deployer approved beforehand with the payload of
+//! `Htlc::approve_erc20_tx_payload`, and fails the deployment if the transfer
+//! reverts or returns anything but `true`, or if nothing was returned by an
+//! address without code. The HTLC hence never exists unfunded. Once deployed, its code is the
+//! one of an `herc20::Htlc`.
+use crate::ethereum::{
+    herc20::{self, Herc20Layout},
+    layout::{HtlcLayout, Layout, Template, TemplateCode},
+    Address, Expiry, TokenQuantity,
+};
+use crate::FitIntoPlaceholderSlice;
+use hex_literal::hex;
+use std::ops::Range;
+
+pub const CONTRACT_TEMPLATE: [u8;536] = hex!("6323b872dd60005233602052306040527f8000000000000000000000000000000000000000000000000000000000000008606052739000000000000000000000000000000000000009602060806064601c6000855af1156086573d1560705760203d10608657608051156086576076565b3b156086575b61018c61008c60003961018c6000f35b60006000fd361561007957602036141561004f57602060006000376020602160206000600060026048f17f100000000000000000000000000000000000000000000000000000000000000160215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b426320000002106100f1577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a1733000000000000000000000000000000000000003602052610134565b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1734000000000000000000000000000000000000004602052610134565b63a9059cbb6000527f5000000000000000000000000000000000000000000000000000000000000005604052602060606044601c6000736000000000000000000000000000000000000006620186a05a03f150602051ff");
+
+pub const BLOCK_HEIGHT_CONTRACT_TEMPLATE: [u8;536] = hex!("6323b872dd60005233602052306040527f8000000000000000000000000000000000000000000000000000000000000008606052739000000000000000000000000000000000000009602060806064601c6000855af1156086573d1560705760203d10608657608051156086576076565b3b156086575b61018c61008c60003961018c6000f35b60006000fd361561007957602036141561004f57602060006000376020602160206000600060026048f17f100000000000000000000000000000000000000000000000000000000000000160215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b436320000002106100f1577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a1733000000000000000000000000000000000000003602052610134565b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1734000000000000000000000000000000000000004602052610134565b63a9059cbb6000527f5000000000000000000000000000000000000000000000000000000000000005604052602060606044601c6000736000000000000000000000000000000000000006620186a05a03f150602051ff");
+
+/// The length of the deploy header, which pulls the tokens before returning
+/// the runtime code following it.
+const DEPLOY_HEADER_LEN: usize = 140;
+
+/// Where the parameters of an HTLC are in the code of a contract template,
+/// the tokens pulled by the deploy header followed by the parameters of the
+/// `herc20::Htlc` it deploys.
+#[derive(Debug)]
+struct TransferFromLayout {
+    transfer_from_token_quantity: Range<usize>,
+    transfer_from_token_contract_address: Range<usize>,
+    herc20: Herc20Layout,
+}
+
+impl Layout for TransferFromLayout {
+    fn htlc(&self) -> &HtlcLayout {
+        &self.herc20.htlc
+    }
+
+    fn other_placeholders(&self) -> Vec<Range<usize>> {
+        let mut placeholders = vec![
+            self.transfer_from_token_quantity.clone(),
+            self.transfer_from_token_contract_address.clone(),
+        ];
+        placeholders.extend(self.herc20.other_placeholders());
+
+        placeholders
+    }
+}
+
+const LAYOUT: TransferFromLayout = TransferFromLayout {
+    transfer_from_token_quantity: 17..49,
+    transfer_from_token_contract_address: 53..73,
+    herc20: Herc20Layout {
+        htlc: HtlcLayout {
+            secret_hash: 178..210,
+            expiry_opcode: 262,
+            expiry: 264..268,
+            redeem_identity: 354..374,
+            refund_identity: 421..441,
+        },
+        token_quantity: 458..490,
+        token_contract_address: 504..524,
+    },
+};
+
+const TEMPLATES: [Template; 2] = [
+    (&CONTRACT_TEMPLATE, &LAYOUT),
+    (&BLOCK_HEIGHT_CONTRACT_TEMPLATE, &LAYOUT),
+];
+
+#[derive(Debug, Clone)]
+pub struct Htlc(Vec<u8>);
+
+impl From<Htlc> for Vec<u8> {
+    fn from(htlc: Htlc) -> Self {
+        htlc.0
+    }
+}
+
+impl TemplateCode for Htlc {
+    const TEMPLATES: &'static [Template] = &TEMPLATES;
+    const DEPLOY_HEADER_LEN: usize = DEPLOY_HEADER_LEN;
+
+    fn from_code(code: Vec<u8>) -> Self {
+        Htlc(code)
+    }
+
+    fn code(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn htlc_layout(&self) -> &'static HtlcLayout {
+        &LAYOUT.herc20.htlc
+    }
+
+    /// The tokens pulled must be the ones the HTLC transfers.
+    fn has_consistent_parameters(&self) -> bool {
+        self.0[LAYOUT.transfer_from_token_quantity] == self.0[LAYOUT.herc20.token_quantity]
+            && self.0[LAYOUT.transfer_from_token_contract_address]
+                == self.0[LAYOUT.herc20.token_contract_address]
+    }
+}
+
+impl Htlc {
+    pub fn new(
+        expiry: Expiry,
+        refund_identity: Address,
+        redeem_identity: Address,
+        secret_hash: [u8; 32],
+        token_contract_address: Address,
+        token_quantity: TokenQuantity,
+    ) -> Self {
+        let template = match expiry {
+            Expiry::Timestamp(_) => &CONTRACT_TEMPLATE[..],
+            Expiry::BlockHeight(_) => &BLOCK_HEIGHT_CONTRACT_TEMPLATE[..],
+        };
+
+        let mut contract = template.to_vec();
+        token_quantity
+            .fit_into_placeholder_slice(&mut contract[LAYOUT.transfer_from_token_quantity]);
+        token_contract_address
+            .fit_into_placeholder_slice(&mut contract[LAYOUT.transfer_from_token_contract_address]);
+        LAYOUT.herc20.htlc.fit_into_placeholders(
+            &mut contract,
+            expiry,
+            refund_identity,
+            redeem_identity,
+            secret_hash,
+        );
+        LAYOUT.herc20.fit_token_into_placeholders(
+            &mut contract,
+            token_contract_address,
+            token_quantity,
+        );
+
+        Htlc(contract)
+    }
+
+    pub fn token_contract_address(&self) -> Address {
+        LAYOUT.herc20.token_contract_address(&self.0)
+    }
+
+    pub fn token_quantity(&self) -> TokenQuantity {
+        LAYOUT.herc20.token_quantity(&self.0)
+    }
+
+    pub fn approve_tx_gas_limit() -> u64 {
+        // 48_403 to 50_713 estimated by the GasEstimator
+        100_000
+    }
+
+    pub fn deploy_tx_gas_limit() -> u64 {
+        // 195_094 to 195_352 estimated by the GasEstimator
+        200_000
+    }
+
+    pub fn redeem_tx_gas_limit() -> u64 {
+        herc20::Htlc::redeem_tx_gas_limit()
+    }
+
+    pub fn refund_tx_gas_limit() -> u64 {
+        herc20::Htlc::refund_tx_gas_limit()
+    }
+
+    /// Constructs the payload allowing `spender`, the address the HTLC will
+    /// be deployed at, to transfer `Erc20` tokens of the sender.
+    /// Note: `token_quantity` must be BigEndian
+    pub fn approve_erc20_tx_payload(token_quantity: TokenQuantity, spender: Address) -> Vec<u8> {
+        let approve_fn_abi = hex!("095EA7B3");
+
+        let mut data = [0u8; 4 + 32 + 32];
+        data[..4].copy_from_slice(&approve_fn_abi);
+        data[16..36].copy_from_slice(&spender.0);
+        data[36..68].copy_from_slice(&token_quantity.0);
+
+        data.to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::{layout::tests::SECRET_HASH, HtlcCode, UnknownContract};
+    use crate::ethereum::{Expiry, HashFunction};
+
+    fn htlc() -> Htlc {
+        Htlc::new(
+            Expiry::Timestamp(1_552_263_040),
+            Address([1u8; 20]),
+            Address([2u8; 20]),
+            SECRET_HASH,
+            Address([3u8; 20]),
+            TokenQuantity([4u8; 32]),
+        )
+    }
+
+    #[test]
+    fn token_parameters_are_extracted_from_init_code() {
+        let htlc = Htlc::from_init_code(&Vec::from(htlc())).unwrap();
+
+        assert_eq!(htlc.token_contract_address(), Address([3u8; 20]));
+        assert_eq!(htlc.token_quantity().0, [4u8; 32]);
+    }
+
+    #[test]
+    fn deployed_code_is_herc20_htlc() {
+        let htlc = htlc();
+        let herc20 = herc20::Htlc::new(
+            Expiry::Timestamp(1_552_263_040),
+            Address([1u8; 20]),
+            Address([2u8; 20]),
+            SECRET_HASH,
+            HashFunction::Sha256,
+            Address([3u8; 20]),
+            TokenQuantity([4u8; 32]),
+        );
+
+        assert_eq!(htlc.runtime_code(), herc20.runtime_code());
+    }
+
+    #[test]
+    fn pulls_the_tokens_transferred_by_the_htlc() {
+        let init_code: Vec<u8> = htlc().into();
+
+        assert_eq!(
+            init_code[LAYOUT.transfer_from_token_quantity],
+            init_code[LAYOUT.herc20.token_quantity]
+        );
+        assert_eq!(
+            init_code[LAYOUT.transfer_from_token_contract_address],
+            init_code[LAYOUT.herc20.token_contract_address]
+        );
+    }
+
+    #[test]
+    fn init_code_pulling_other_tokens_is_not_recognized() {
+        let mut init_code: Vec<u8> = htlc().into();
+        init_code[LAYOUT.transfer_from_token_quantity.end - 1] = 0;
+
+        assert_eq!(
+            Htlc::from_init_code(&init_code).unwrap_err(),
+            UnknownContract
+        );
+
+        let herc20: Vec<u8> = herc20::Htlc::new(
+            Expiry::Timestamp(1_552_263_040),
+            Address([1u8; 20]),
+            Address([2u8; 20]),
+            SECRET_HASH,
+            HashFunction::Sha256,
+            Address([3u8; 20]),
+            TokenQuantity([4u8; 32]),
+        )
+        .into();
+        assert_eq!(Htlc::from_init_code(&herc20).unwrap_err(), UnknownContract);
+    }
+
+    #[test]
+    fn approve_payload_allows_spender_to_transfer_quantity() {
+        let payload = Htlc::approve_erc20_tx_payload(TokenQuantity([4u8; 32]), Address([5u8; 20]));
+
+        assert_eq!(payload[..4], hex!("095ea7b3"));
+        assert_eq!(payload[4..16], [0u8; 12]);
+        assert_eq!(payload[16..36], [5u8; 20]);
+        assert_eq!(payload[36..], [4u8; 32]);
+    }
+}
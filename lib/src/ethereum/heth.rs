@@ -1,10 +1,58 @@
-use crate::ethereum::Address;
-use crate::{EthereumTimestamp, FitIntoPlaceholderSlice, SecretHash};
+use crate::ethereum::{
+    layout::{HtlcLayout, Layout, Template, TemplateCode},
+    Address, DeployedHtlcCode, Expiry, HashFunction,
+};
 use hex_literal::hex;
+use std::ops::Range;
 
 // contract template RFC: https://github.com/comit-network/RFCs/blob/master/RFC-007-SWAP-Basic-Ether.md#contract
 pub const CONTRACT_TEMPLATE: [u8;311] = hex!("61012861000f6000396101286000f3361561007957602036141561004f57602060006000376020602160206000600060026048f17f100000000000000000000000000000000000000000000000000000000000000160215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b426320000002106100eb577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a1733000000000000000000000000000000000000003ff5b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1734000000000000000000000000000000000000004ff");
 
+/// The contract template comparing the expiry with the block number instead of
+/// the block timestamp.
+pub const BLOCK_HEIGHT_CONTRACT_TEMPLATE: [u8;311] = hex!("61012861000f6000396101286000f3361561007957602036141561004f57602060006000376020602160206000600060026048f17f100000000000000000000000000000000000000000000000000000000000000160215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b436320000002106100eb577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a1733000000000000000000000000000000000000003ff5b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1734000000000000000000000000000000000000004ff");
+
+/// The contract template hashing the secret with the `KECCAK256` opcode instead
+/// of the SHA-256 precompile.
+pub const KECCAK256_CONTRACT_TEMPLATE: [u8;297] = hex!("61011a61000f60003961011a6000f3361561006b57602036141561004157602060006000377f10000000000000000000000000000000000000000000000000000000000000016020600020146100a0575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b426320000002106100dd577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a1733000000000000000000000000000000000000003ff5b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1734000000000000000000000000000000000000004ff");
+
+/// The contract template hashing the secret with the `KECCAK256` opcode and
+/// comparing the expiry with the block number.
+pub const KECCAK256_BLOCK_HEIGHT_CONTRACT_TEMPLATE: [u8;297] = hex!("61011a61000f60003961011a6000f3361561006b57602036141561004157602060006000377f10000000000000000000000000000000000000000000000000000000000000016020600020146100a0575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b436320000002106100dd577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a1733000000000000000000000000000000000000003ff5b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1734000000000000000000000000000000000000004ff");
+
+const SHA256_LAYOUT: HtlcLayout = HtlcLayout {
+    secret_hash: 53..85,
+    expiry_opcode: 137,
+    expiry: 139..143,
+    redeem_identity: 229..249,
+    refund_identity: 290..310,
+};
+
+const KECCAK256_LAYOUT: HtlcLayout = HtlcLayout {
+    secret_hash: 38..70,
+    expiry_opcode: 123,
+    expiry: 125..129,
+    redeem_identity: 215..235,
+    refund_identity: 276..296,
+};
+
+impl Layout for HtlcLayout {
+    fn htlc(&self) -> &HtlcLayout {
+        self
+    }
+
+    fn other_placeholders(&self) -> Vec<Range<usize>> {
+        Vec::new()
+    }
+}
+
+const TEMPLATES: [Template; 4] = [
+    (&CONTRACT_TEMPLATE, &SHA256_LAYOUT),
+    (&BLOCK_HEIGHT_CONTRACT_TEMPLATE, &SHA256_LAYOUT),
+    (&KECCAK256_CONTRACT_TEMPLATE, &KECCAK256_LAYOUT),
+    (&KECCAK256_BLOCK_HEIGHT_CONTRACT_TEMPLATE, &KECCAK256_LAYOUT),
+];
+
 #[derive(Debug)]
 pub struct Htlc(Vec<u8>);
 
@@ -14,22 +62,76 @@ impl From<Htlc> for Vec<u8> {
     }
 }
 
+impl TemplateCode for Htlc {
+    const TEMPLATES: &'static [Template] = &TEMPLATES;
+
+    fn from_code(code: Vec<u8>) -> Self {
+        Htlc(code)
+    }
+
+    fn code(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn htlc_layout(&self) -> &'static HtlcLayout {
+        self.layout()
+    }
+}
+
+impl DeployedHtlcCode for Htlc {}
+
 impl Htlc {
     pub fn new(
-        expiry: u32,
+        expiry: Expiry,
         refund_identity: Address,
         redeem_identity: Address,
         secret_hash: [u8; 32],
+        hash_function: HashFunction,
     ) -> Self {
-        let mut contract = CONTRACT_TEMPLATE.to_vec();
-        SecretHash(secret_hash).fit_into_placeholder_slice(&mut contract[53..85]);
-        EthereumTimestamp(expiry).fit_into_placeholder_slice(&mut contract[139..143]);
-        redeem_identity.fit_into_placeholder_slice(&mut contract[229..249]);
-        refund_identity.fit_into_placeholder_slice(&mut contract[290..310]);
+        let (template, layout) = match (hash_function, expiry) {
+            (HashFunction::Sha256, Expiry::Timestamp(_)) => {
+                (&CONTRACT_TEMPLATE[..], &SHA256_LAYOUT)
+            }
+            (HashFunction::Sha256, Expiry::BlockHeight(_)) => {
+                (&BLOCK_HEIGHT_CONTRACT_TEMPLATE[..], &SHA256_LAYOUT)
+            }
+            (HashFunction::Keccak256, Expiry::Timestamp(_)) => {
+                (&KECCAK256_CONTRACT_TEMPLATE[..], &KECCAK256_LAYOUT)
+            }
+            (HashFunction::Keccak256, Expiry::BlockHeight(_)) => (
+                &KECCAK256_BLOCK_HEIGHT_CONTRACT_TEMPLATE[..],
+                &KECCAK256_LAYOUT,
+            ),
+        };
+
+        let mut contract = template.to_vec();
+        layout.fit_into_placeholders(
+            &mut contract,
+            expiry,
+            refund_identity,
+            redeem_identity,
+            secret_hash,
+        );
 
         Htlc(contract)
     }
 
+    /// The hash function of the hashlock, which tells the contract template.
+    pub fn hash_function(&self) -> HashFunction {
+        if self.0.len() == KECCAK256_CONTRACT_TEMPLATE.len() {
+            HashFunction::Keccak256
+        } else {
+            HashFunction::Sha256
+        }
+    }
+
+    fn layout(&self) -> &'static HtlcLayout {
+        match self.hash_function() {
+            HashFunction::Sha256 => &SHA256_LAYOUT,
+            HashFunction::Keccak256 => &KECCAK256_LAYOUT,
+        }
+    }
+
     pub fn deploy_tx_gas_limit() -> u64 {
         // 126_386 to 126_450 consumed in local test
         130_000
@@ -51,77 +153,52 @@ impl Htlc {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use hex::{FromHex, ToHex};
-    use regex::bytes::Regex;
-    use spectral::assert_that;
-
-    const SECRET_HASH: [u8; 32] = [
-        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9,
-        0, 1,
-    ];
-
-    const SECRET_HASH_REGEX: &str = "\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x00\x01";
+    use crate::ethereum::{layout::tests::SECRET_HASH, HtlcCode, UnknownContract};
 
     #[test]
-    fn compiled_contract_is_same_length_as_template() {
-        let htlc = Htlc::new(
-            3_000_000,
+    fn code_differing_outside_placeholders_is_rejected() {
+        let htlc: Vec<u8> = Htlc::new(
+            Expiry::Timestamp(3_000_000),
             Address([0u8; 20]),
             Address([0u8; 20]),
             SECRET_HASH,
-        );
+            HashFunction::Sha256,
+        )
+        .into();
 
+        assert!(Htlc::from_init_code(&htlc).is_ok());
         assert_eq!(
-            htlc.0.len(),
-            CONTRACT_TEMPLATE.len(),
-            "HTLC is the same length as template"
+            Htlc::from_init_code(&htlc[..htlc.len() - 1]).unwrap_err(),
+            UnknownContract
         );
-    }
+        assert_eq!(Htlc::from_runtime_code(&htlc).unwrap_err(), UnknownContract);
 
-    #[test]
-    fn given_input_data_when_compiled_should_contain_given_data() {
-        let htlc = Htlc::new(
-            2_000_000_000,
-            Address([0u8; 20]),
-            Address([0u8; 20]),
-            SECRET_HASH,
+        // The opcode comparing the timestamp with the expiry
+        let mut tampered = htlc;
+        tampered[143] ^= 0xff;
+        assert_eq!(
+            Htlc::from_init_code(&tampered).unwrap_err(),
+            UnknownContract
         );
-
-        let compiled_code = htlc.0;
-
-        // Allowed because `str::contains` (clippy's suggestion) does not apply to bytes
-        // array
-        #[allow(clippy::trivial_regex)]
-        let _re_match = Regex::new(SECRET_HASH_REGEX)
-            .expect("Could not create regex")
-            .find(&compiled_code)
-            .expect("Could not find secret hash in hex code");
     }
 
     #[test]
-    fn test_replaced_placeholders_for_rfc_example() {
-        let redeem_identity =
-            <[u8; 20]>::from_hex("53fd2cac865d3aa1ad6fbdebaa00802c94239fba").unwrap();
-        let refund_identity =
-            <[u8; 20]>::from_hex("0f59e9e105be01d5e2206792a267406f255c5ea5").unwrap();
-        let secret_hash = <[u8; 32]>::from_hex(
-            "ac5a18da6431ed256965b873ef49dc15a70a0a66e2d28d0c226b5db040123727",
-        )
-        .unwrap();
-        let expiry = 1_552_263_040;
-
+    fn keccak256_parameters_are_extracted_from_deployed_code() {
         let htlc = Htlc::new(
-            expiry,
-            Address(refund_identity),
-            Address(redeem_identity),
-            secret_hash,
+            Expiry::BlockHeight(12_000_000),
+            Address([1u8; 20]),
+            Address([2u8; 20]),
+            SECRET_HASH,
+            HashFunction::Keccak256,
         );
 
-        let compiled_code = htlc.0;
-        let contract_string = compiled_code.encode_hex::<String>();
-
-        let expected_contract_code = "61012861000f6000396101286000f3361561007957602036141561004f57602060006000376020602160206000600060026048f17fac5a18da6431ed256965b873ef49dc15a70a0a66e2d28d0c226b5db04012372760215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b42635c85a780106100eb577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a17353fd2cac865d3aa1ad6fbdebaa00802c94239fbaff5b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1730f59e9e105be01d5e2206792a267406f255c5ea5ff";
+        let deployed = Htlc::from_runtime_code(htlc.runtime_code()).unwrap();
 
-        assert_that!(contract_string.as_str()).is_equal_to(expected_contract_code)
+        assert_eq!(deployed.hash_function(), HashFunction::Keccak256);
+        assert_eq!(deployed.expiry(), Expiry::BlockHeight(12_000_000));
+        assert_eq!(deployed.refund_identity(), Address([1u8; 20]));
+        assert_eq!(deployed.redeem_identity(), Address([2u8; 20]));
+        assert_eq!(deployed.secret_hash(), SECRET_HASH);
+        assert_eq!(deployed.code_hash(), htlc.code_hash());
     }
 }
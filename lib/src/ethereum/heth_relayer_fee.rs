@@ -0,0 +1,186 @@
+//! An heth HTLC paying a fixed relayer fee to the caller of a successful
+//! redeem and the rest of its ether to the redeem identity.
+//!
+//! Anyone knowing the secret can redeem an HTLC, hence a relayer can pay the
+//! gas of the redeem transaction for a redeemer without ether and be paid
+//! back from the HTLC. The fee is paid with the gas stipend of a transfer and
+//! a failed payment, e.g. to a contract rejecting ether, does not block the
+//! redeem: the redeem identity then receives the whole value. Refunding pays
+//! no fee.
+use crate::ethereum::{
+    layout::{HtlcLayout, Layout, Template, TemplateCode},
+    Address, DeployedHtlcCode, Expiry,
+};
+use byteorder::{BigEndian, ByteOrder};
+use hex_literal::hex;
+use std::ops::Range;
+
+pub const CONTRACT_TEMPLATE: [u8;341] = hex!("61014661000f6000396101466000f3361561007957602036141561004f57602060006000376020602160206000600060026048f17f100000000000000000000000000000000000000000000000000000000000000160215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b42632000000210610109577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a160006000600060006f70000000000000000000000000000007336000f150733000000000000000000000000000000000000003ff5b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1734000000000000000000000000000000000000004ff");
+
+pub const BLOCK_HEIGHT_CONTRACT_TEMPLATE: [u8;341] = hex!("61014661000f6000396101466000f3361561007957602036141561004f57602060006000376020602160206000600060026048f17f100000000000000000000000000000000000000000000000000000000000000160215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b43632000000210610109577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a160006000600060006f70000000000000000000000000000007336000f150733000000000000000000000000000000000000003ff5b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1734000000000000000000000000000000000000004ff");
+
+/// Where the parameters of an HTLC are in the code of a contract template.
+#[derive(Debug)]
+struct RelayerFeeLayout {
+    htlc: HtlcLayout,
+    relayer_fee: Range<usize>,
+}
+
+impl Layout for RelayerFeeLayout {
+    fn htlc(&self) -> &HtlcLayout {
+        &self.htlc
+    }
+
+    fn other_placeholders(&self) -> Vec<Range<usize>> {
+        vec![self.relayer_fee.clone()]
+    }
+}
+
+const LAYOUT: RelayerFeeLayout = RelayerFeeLayout {
+    htlc: HtlcLayout {
+        secret_hash: 53..85,
+        expiry_opcode: 137,
+        expiry: 139..143,
+        redeem_identity: 259..279,
+        refund_identity: 320..340,
+    },
+    relayer_fee: 237..253,
+};
+
+const TEMPLATES: [Template; 2] = [
+    (&CONTRACT_TEMPLATE, &LAYOUT),
+    (&BLOCK_HEIGHT_CONTRACT_TEMPLATE, &LAYOUT),
+];
+
+#[derive(Debug, Clone)]
+pub struct Htlc(Vec<u8>);
+
+impl From<Htlc> for Vec<u8> {
+    fn from(htlc: Htlc) -> Self {
+        htlc.0
+    }
+}
+
+impl TemplateCode for Htlc {
+    const TEMPLATES: &'static [Template] = &TEMPLATES;
+
+    fn from_code(code: Vec<u8>) -> Self {
+        Htlc(code)
+    }
+
+    fn code(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn htlc_layout(&self) -> &'static HtlcLayout {
+        &LAYOUT.htlc
+    }
+}
+
+impl DeployedHtlcCode for Htlc {}
+
+impl Htlc {
+    /// Constructs an HTLC paying `relayer_fee`, in wei, to the caller of the
+    /// redeem.
+    pub fn new(
+        expiry: Expiry,
+        refund_identity: Address,
+        redeem_identity: Address,
+        secret_hash: [u8; 32],
+        relayer_fee: u128,
+    ) -> Self {
+        let template = match expiry {
+            Expiry::Timestamp(_) => &CONTRACT_TEMPLATE[..],
+            Expiry::BlockHeight(_) => &BLOCK_HEIGHT_CONTRACT_TEMPLATE[..],
+        };
+
+        let mut contract = template.to_vec();
+        LAYOUT.htlc.fit_into_placeholders(
+            &mut contract,
+            expiry,
+            refund_identity,
+            redeem_identity,
+            secret_hash,
+        );
+        BigEndian::write_u128(&mut contract[LAYOUT.relayer_fee], relayer_fee);
+
+        Htlc(contract)
+    }
+
+    /// The fee, in wei, paid to the caller of the redeem.
+    pub fn relayer_fee(&self) -> u128 {
+        BigEndian::read_u128(&self.0[LAYOUT.relayer_fee])
+    }
+
+    /// The wei received by the redeem identity when the HTLC holding `value`
+    /// wei is redeemed, or `None` if `value` does not cover the relayer fee.
+    pub fn net_redeem_amount(&self, value: u128) -> Option<u128> {
+        value.checked_sub(self.relayer_fee())
+    }
+
+    pub fn deploy_tx_gas_limit() -> u64 {
+        // 134_622 to 134_646 estimated by the GasEstimator
+        140_000
+    }
+
+    pub fn redeem_tx_gas_limit() -> u64 {
+        // 68_464 estimated by the GasEstimator
+        100_000
+    }
+
+    pub fn refund_tx_gas_limit() -> u64 {
+        // 59_843 estimated by the GasEstimator
+        100_000
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::{heth, Expiry, HashFunction};
+    use crate::ethereum::{layout::tests::SECRET_HASH, HtlcCode, UnknownContract};
+
+    #[test]
+    fn relayer_fee_is_extracted_from_deployed_code() {
+        let htlc = Htlc::new(
+            Expiry::Timestamp(1_552_263_040),
+            Address([1u8; 20]),
+            Address([2u8; 20]),
+            SECRET_HASH,
+            u128::MAX,
+        );
+
+        let deployed = Htlc::from_runtime_code(htlc.runtime_code()).unwrap();
+
+        assert_eq!(deployed.relayer_fee(), u128::MAX);
+    }
+
+    #[test]
+    fn net_redeem_amount_deducts_the_relayer_fee() {
+        let htlc = Htlc::new(
+            Expiry::Timestamp(3_000_000),
+            Address([0u8; 20]),
+            Address([0u8; 20]),
+            SECRET_HASH,
+            400,
+        );
+
+        assert_eq!(htlc.net_redeem_amount(1_000), Some(600));
+        assert_eq!(htlc.net_redeem_amount(400), Some(0));
+        assert_eq!(htlc.net_redeem_amount(399), None);
+    }
+
+    #[test]
+    fn heth_code_is_not_recognized() {
+        let heth: Vec<u8> = heth::Htlc::new(
+            Expiry::Timestamp(3_000_000),
+            Address([0u8; 20]),
+            Address([0u8; 20]),
+            SECRET_HASH,
+            HashFunction::Sha256,
+        )
+        .into();
+
+        assert_eq!(Htlc::from_init_code(&heth).unwrap_err(), UnknownContract);
+    }
+}
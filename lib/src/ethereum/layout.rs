@@ -0,0 +1,465 @@
+//! Where the parameters of the HTLCs are in the code of their contract
+//! templates, to build HTLCs and recognize their code.
+use crate::ethereum::{
+    keccak256, read_address, Address, Expiry, UnknownContract, DEPLOY_HEADER_LEN,
+};
+use crate::{FitIntoPlaceholderSlice, SecretHash};
+use std::ops::Range;
+
+/// A contract template with the layout of its parameters.
+pub type Template = (&'static [u8], &'static dyn Layout);
+
+/// Where the parameters every HTLC has are in the code of a contract template.
+#[derive(Debug)]
+pub struct HtlcLayout {
+    pub(crate) secret_hash: Range<usize>,
+    /// The opcode pushing the block value the expiry is compared with.
+    pub(crate) expiry_opcode: usize,
+    pub(crate) expiry: Range<usize>,
+    pub(crate) redeem_identity: Range<usize>,
+    pub(crate) refund_identity: Range<usize>,
+}
+
+impl HtlcLayout {
+    pub(crate) fn fit_into_placeholders(
+        &self,
+        code: &mut [u8],
+        expiry: Expiry,
+        refund_identity: Address,
+        redeem_identity: Address,
+        secret_hash: [u8; 32],
+    ) {
+        SecretHash(secret_hash).fit_into_placeholder_slice(&mut code[self.secret_hash.clone()]);
+        expiry.fit_into_placeholder_slice(&mut code[self.expiry.clone()]);
+        redeem_identity.fit_into_placeholder_slice(&mut code[self.redeem_identity.clone()]);
+        refund_identity.fit_into_placeholder_slice(&mut code[self.refund_identity.clone()]);
+    }
+
+    pub(crate) fn expiry(&self, code: &[u8]) -> Expiry {
+        Expiry::from_placeholder(code[self.expiry_opcode], &code[self.expiry.clone()])
+    }
+
+    pub(crate) fn refund_identity(&self, code: &[u8]) -> Address {
+        read_address(&code[self.refund_identity.clone()])
+    }
+
+    pub(crate) fn redeem_identity(&self, code: &[u8]) -> Address {
+        read_address(&code[self.redeem_identity.clone()])
+    }
+
+    pub(crate) fn secret_hash(&self, code: &[u8]) -> [u8; 32] {
+        read_bytes32(&code[self.secret_hash.clone()])
+    }
+}
+
+/// Where the parameters of an HTLC are in the code of a contract template.
+pub trait Layout {
+    fn htlc(&self) -> &HtlcLayout;
+
+    /// The placeholders of the parameters not every HTLC has, e.g. the token
+    /// quantity of an herc20 HTLC.
+    fn other_placeholders(&self) -> Vec<Range<usize>>;
+
+    fn placeholders(&self) -> Vec<Range<usize>> {
+        let htlc = self.htlc();
+        let mut placeholders = vec![
+            htlc.secret_hash.clone(),
+            htlc.expiry.clone(),
+            htlc.redeem_identity.clone(),
+            htlc.refund_identity.clone(),
+        ];
+        placeholders.extend(self.other_placeholders());
+
+        placeholders
+    }
+}
+
+/// The template `init_code` is built from, with any value in the
+/// placeholders of its layout.
+pub(crate) fn find_template(
+    templates: &[Template],
+    init_code: &[u8],
+) -> Result<Template, UnknownContract> {
+    templates
+        .iter()
+        .find(|(template, layout)| matches_template(init_code, template, &layout.placeholders()))
+        .copied()
+        .ok_or(UnknownContract)
+}
+
+/// The init code of an HTLC deployed with `runtime_code`, built from one of
+/// `templates`.
+pub(crate) fn init_code_of(
+    templates: &[Template],
+    runtime_code: &[u8],
+) -> Result<Vec<u8>, UnknownContract> {
+    // The deploy header encodes the length of the runtime code
+    templates
+        .iter()
+        .map(|(template, _)| {
+            let mut init_code = template[..DEPLOY_HEADER_LEN].to_vec();
+            init_code.extend_from_slice(runtime_code);
+            init_code
+        })
+        .find(|init_code| find_template(templates, init_code).is_ok())
+        .ok_or(UnknownContract)
+}
+
+/// The code of an HTLC type built from its contract templates.
+pub trait TemplateCode: Sized {
+    /// The contract templates of the HTLC type, each one comparing the expiry
+    /// with the block timestamp followed by the one comparing it with the
+    /// block number.
+    const TEMPLATES: &'static [Template];
+
+    /// The length of the deploy header preceding the runtime code.
+    const DEPLOY_HEADER_LEN: usize = DEPLOY_HEADER_LEN;
+
+    fn from_code(code: Vec<u8>) -> Self;
+
+    fn code(&self) -> &[u8];
+
+    /// Where the parameters every HTLC has are in the code.
+    fn htlc_layout(&self) -> &'static HtlcLayout;
+
+    /// Whether parameters the templates repeat hold consistent values, e.g.
+    /// the tokens pulled and the ones transferred by the HTLC.
+    fn has_consistent_parameters(&self) -> bool {
+        true
+    }
+}
+
+/// The code of an HTLC, from which the parameters every HTLC has are read.
+pub trait HtlcCode: TemplateCode {
+    /// Recognizes the init code of an HTLC, e.g. the data of the transaction
+    /// deploying it.
+    fn from_init_code(init_code: &[u8]) -> Result<Self, UnknownContract> {
+        find_template(Self::TEMPLATES, init_code)?;
+        let htlc = Self::from_code(init_code.to_vec());
+
+        if htlc.has_consistent_parameters() {
+            Ok(htlc)
+        } else {
+            Err(UnknownContract)
+        }
+    }
+
+    /// The code stored at the address of the HTLC once deployed.
+    fn runtime_code(&self) -> &[u8] {
+        &self.code()[Self::DEPLOY_HEADER_LEN..]
+    }
+
+    /// The keccak hash of the runtime code, as returned by `EXTCODEHASH`.
+    fn code_hash(&self) -> [u8; 32] {
+        keccak256(self.runtime_code())
+    }
+
+    fn expiry(&self) -> Expiry {
+        self.htlc_layout().expiry(self.code())
+    }
+
+    fn refund_identity(&self) -> Address {
+        self.htlc_layout().refund_identity(self.code())
+    }
+
+    fn redeem_identity(&self) -> Address {
+        self.htlc_layout().redeem_identity(self.code())
+    }
+
+    fn secret_hash(&self) -> [u8; 32] {
+        self.htlc_layout().secret_hash(self.code())
+    }
+}
+
+impl<H: TemplateCode> HtlcCode for H {}
+
+/// The code of an HTLC whose runtime code is the one of its templates, which
+/// it can hence be recognized from once deployed.
+pub trait DeployedHtlcCode: HtlcCode {
+    /// Recognizes the runtime code of a deployed HTLC, as returned by
+    /// `eth_getCode`.
+    fn from_runtime_code(runtime_code: &[u8]) -> Result<Self, UnknownContract> {
+        Self::from_init_code(&init_code_of(Self::TEMPLATES, runtime_code)?)
+    }
+}
+
+fn matches_template(init_code: &[u8], template: &[u8], placeholders: &[Range<usize>]) -> bool {
+    let is_placeholder = |index: usize| placeholders.iter().any(|range| range.contains(&index));
+
+    init_code.len() == template.len()
+        && init_code
+            .iter()
+            .zip(template)
+            .enumerate()
+            .all(|(index, (byte, expected))| byte == expected || is_placeholder(index))
+}
+
+pub(crate) fn read_bytes32(bytes: &[u8]) -> [u8; 32] {
+    let mut array = [0u8; 32];
+    array.copy_from_slice(bytes);
+    array
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::ethereum::{
+        herc20, herc20_full_balance, herc20_relayer_fee, herc20_safe, herc20_transfer_from, heth,
+        heth_relayer_fee, HashFunction, TokenQuantity,
+    };
+    use hex::ToHex;
+    use hex_literal::hex;
+    use spectral::assert_that;
+
+    pub(crate) const SECRET_HASH: [u8; 32] = [
+        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9,
+        0, 1,
+    ];
+
+    const TOKEN_CONTRACT_ADDRESS: Address =
+        Address(hex!("b97048628db6b661d4c2aa833e95dbe1a905b280"));
+
+    const TOKEN_QUANTITY: TokenQuantity = TokenQuantity(hex!(
+        "0000000000000000000000000000000000000000000000000de0b6b3a7640000"
+    ));
+
+    const RELAYER_FEE: TokenQuantity = TokenQuantity(hex!(
+        "00000000000000000000000000000000000000000000000000038d7ea4c68000"
+    ));
+
+    /// Builds an HTLC from the parameters every HTLC has, the other ones
+    /// being the ones of the RFC example.
+    type New<H> = fn(Expiry, Address, Address, [u8; 32]) -> H;
+
+    fn new_herc20(
+        expiry: Expiry,
+        refund: Address,
+        redeem: Address,
+        secret_hash: [u8; 32],
+    ) -> herc20::Htlc {
+        herc20::Htlc::new(
+            expiry,
+            refund,
+            redeem,
+            secret_hash,
+            HashFunction::Sha256,
+            TOKEN_CONTRACT_ADDRESS,
+            TOKEN_QUANTITY,
+        )
+    }
+
+    fn new_heth(
+        expiry: Expiry,
+        refund: Address,
+        redeem: Address,
+        secret_hash: [u8; 32],
+    ) -> heth::Htlc {
+        heth::Htlc::new(expiry, refund, redeem, secret_hash, HashFunction::Sha256)
+    }
+
+    fn new_herc20_safe(
+        expiry: Expiry,
+        refund: Address,
+        redeem: Address,
+        secret_hash: [u8; 32],
+    ) -> herc20_safe::Htlc {
+        herc20_safe::Htlc::new(
+            expiry,
+            refund,
+            redeem,
+            secret_hash,
+            TOKEN_CONTRACT_ADDRESS,
+            TOKEN_QUANTITY,
+        )
+    }
+
+    fn new_herc20_full_balance(
+        expiry: Expiry,
+        refund: Address,
+        redeem: Address,
+        secret_hash: [u8; 32],
+    ) -> herc20_full_balance::Htlc {
+        herc20_full_balance::Htlc::new(expiry, refund, redeem, secret_hash, TOKEN_CONTRACT_ADDRESS)
+    }
+
+    fn new_herc20_relayer_fee(
+        expiry: Expiry,
+        refund: Address,
+        redeem: Address,
+        secret_hash: [u8; 32],
+    ) -> herc20_relayer_fee::Htlc {
+        herc20_relayer_fee::Htlc::new(
+            expiry,
+            refund,
+            redeem,
+            secret_hash,
+            TOKEN_CONTRACT_ADDRESS,
+            TOKEN_QUANTITY,
+            RELAYER_FEE,
+        )
+        .unwrap()
+    }
+
+    fn new_heth_relayer_fee(
+        expiry: Expiry,
+        refund: Address,
+        redeem: Address,
+        secret_hash: [u8; 32],
+    ) -> heth_relayer_fee::Htlc {
+        heth_relayer_fee::Htlc::new(expiry, refund, redeem, secret_hash, 1_000_000_000_000_000)
+    }
+
+    fn new_herc20_transfer_from(
+        expiry: Expiry,
+        refund: Address,
+        redeem: Address,
+        secret_hash: [u8; 32],
+    ) -> herc20_transfer_from::Htlc {
+        herc20_transfer_from::Htlc::new(
+            expiry,
+            refund,
+            redeem,
+            secret_hash,
+            TOKEN_CONTRACT_ADDRESS,
+            TOKEN_QUANTITY,
+        )
+    }
+
+    fn from_init_code<H: HtlcCode>(htlc: &H) -> Result<H, UnknownContract> {
+        H::from_init_code(htlc.code())
+    }
+
+    fn from_runtime_code<H: DeployedHtlcCode>(htlc: &H) -> Result<H, UnknownContract> {
+        H::from_runtime_code(htlc.runtime_code())
+    }
+
+    /// The indexes at which two templates of the same length differ.
+    fn differences(template: &[u8], other: &[u8]) -> Vec<usize> {
+        template
+            .iter()
+            .zip(other)
+            .enumerate()
+            .filter(|(_, (byte, other))| byte != other)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    fn assert_compiled_contracts_are_same_length_as_templates<H: HtlcCode>(new: New<H>) {
+        let expiries = [Expiry::Timestamp(3_000_000), Expiry::BlockHeight(3_000_000)];
+
+        for (expiry, (template, _)) in expiries.iter().zip(H::TEMPLATES) {
+            let htlc = new(*expiry, Address([0u8; 20]), Address([0u8; 20]), SECRET_HASH);
+
+            assert_eq!(
+                htlc.code().len(),
+                template.len(),
+                "HTLC is the same length as template"
+            );
+        }
+    }
+
+    fn assert_parameters_are_extracted<H: HtlcCode>(
+        new: New<H>,
+        recognize: fn(&H) -> Result<H, UnknownContract>,
+    ) {
+        for expiry in &[
+            Expiry::Timestamp(1_552_263_040),
+            Expiry::BlockHeight(12_000_000),
+        ] {
+            let htlc = new(*expiry, Address([1u8; 20]), Address([2u8; 20]), SECRET_HASH);
+
+            let recognized = recognize(&htlc).unwrap();
+
+            assert_eq!(recognized.expiry(), *expiry);
+            assert_eq!(recognized.refund_identity(), Address([1u8; 20]));
+            assert_eq!(recognized.redeem_identity(), Address([2u8; 20]));
+            assert_eq!(recognized.secret_hash(), SECRET_HASH);
+            assert_eq!(recognized.code_hash(), htlc.code_hash());
+            assert_eq!(recognized.code(), htlc.code());
+        }
+    }
+
+    fn assert_block_height_templates_only_differ_in_the_compared_block_value<H: HtlcCode>() {
+        for templates in H::TEMPLATES.chunks(2) {
+            let ((template, layout), (block_height_template, _)) = (templates[0], templates[1]);
+
+            assert_eq!(
+                differences(template, block_height_template),
+                vec![layout.htlc().expiry_opcode]
+            );
+        }
+    }
+
+    fn rfc_example<H: HtlcCode>(new: New<H>) -> String {
+        let htlc = new(
+            Expiry::Timestamp(1_552_263_040),
+            Address(hex!("0f59e9e105be01d5e2206792a267406f255c5ea5")),
+            Address(hex!("53fd2cac865d3aa1ad6fbdebaa00802c94239fba")),
+            hex!("ac5a18da6431ed256965b873ef49dc15a70a0a66e2d28d0c226b5db040123727"),
+        );
+
+        htlc.code().encode_hex()
+    }
+
+    #[test]
+    fn compiled_contracts_are_same_length_as_templates() {
+        assert_compiled_contracts_are_same_length_as_templates(new_herc20);
+        assert_compiled_contracts_are_same_length_as_templates(new_heth);
+        assert_compiled_contracts_are_same_length_as_templates(new_herc20_safe);
+        assert_compiled_contracts_are_same_length_as_templates(new_herc20_full_balance);
+        assert_compiled_contracts_are_same_length_as_templates(new_herc20_relayer_fee);
+        assert_compiled_contracts_are_same_length_as_templates(new_heth_relayer_fee);
+        assert_compiled_contracts_are_same_length_as_templates(new_herc20_transfer_from);
+    }
+
+    #[test]
+    fn parameters_are_extracted_from_init_code() {
+        assert_parameters_are_extracted(new_herc20, from_init_code);
+        assert_parameters_are_extracted(new_heth, from_init_code);
+        assert_parameters_are_extracted(new_herc20_safe, from_init_code);
+        assert_parameters_are_extracted(new_herc20_full_balance, from_init_code);
+        assert_parameters_are_extracted(new_herc20_relayer_fee, from_init_code);
+        assert_parameters_are_extracted(new_heth_relayer_fee, from_init_code);
+        assert_parameters_are_extracted(new_herc20_transfer_from, from_init_code);
+    }
+
+    #[test]
+    fn parameters_are_extracted_from_deployed_code() {
+        assert_parameters_are_extracted(new_herc20, from_runtime_code);
+        assert_parameters_are_extracted(new_heth, from_runtime_code);
+        assert_parameters_are_extracted(new_herc20_safe, from_runtime_code);
+        assert_parameters_are_extracted(new_herc20_full_balance, from_runtime_code);
+        assert_parameters_are_extracted(new_herc20_relayer_fee, from_runtime_code);
+        assert_parameters_are_extracted(new_heth_relayer_fee, from_runtime_code);
+    }
+
+    #[test]
+    fn block_height_templates_only_differ_in_the_compared_block_value() {
+        assert_block_height_templates_only_differ_in_the_compared_block_value::<herc20::Htlc>();
+        assert_block_height_templates_only_differ_in_the_compared_block_value::<heth::Htlc>();
+        assert_block_height_templates_only_differ_in_the_compared_block_value::<herc20_safe::Htlc>(
+        );
+        assert_block_height_templates_only_differ_in_the_compared_block_value::<
+            herc20_full_balance::Htlc,
+        >();
+        assert_block_height_templates_only_differ_in_the_compared_block_value::<
+            herc20_relayer_fee::Htlc,
+        >();
+        assert_block_height_templates_only_differ_in_the_compared_block_value::<
+            heth_relayer_fee::Htlc,
+        >();
+        assert_block_height_templates_only_differ_in_the_compared_block_value::<
+            herc20_transfer_from::Htlc,
+        >();
+    }
+
+    #[test]
+    fn test_replaced_placeholders_for_rfc_example() {
+        assert_that!(rfc_example(new_herc20).as_str()).is_equal_to("61018c61000f60003961018c6000f3361561007957602036141561004f57602060006000376020602160206000600060026048f17fac5a18da6431ed256965b873ef49dc15a70a0a66e2d28d0c226b5db04012372760215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b42635c85a780106100f1577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a17353fd2cac865d3aa1ad6fbdebaa00802c94239fba602052610134565b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1730f59e9e105be01d5e2206792a267406f255c5ea5602052610134565b63a9059cbb6000527f0000000000000000000000000000000000000000000000000de0b6b3a7640000604052602060606044601c600073b97048628db6b661d4c2aa833e95dbe1a905b280620186a05a03f150602051ff");
+        assert_that!(rfc_example(new_heth).as_str()).is_equal_to("61012861000f6000396101286000f3361561007957602036141561004f57602060006000376020602160206000600060026048f17fac5a18da6431ed256965b873ef49dc15a70a0a66e2d28d0c226b5db04012372760215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b42635c85a780106100eb577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a17353fd2cac865d3aa1ad6fbdebaa00802c94239fbaff5b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1730f59e9e105be01d5e2206792a267406f255c5ea5ff");
+        assert_that!(rfc_example(new_herc20_safe).as_str()).is_equal_to("6101df61000f6000396101df6000f3361561007957602036141561004f57602060006000376020602160206000600060026048f17fac5a18da6431ed256965b873ef49dc15a70a0a66e2d28d0c226b5db04012372760215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b42635c85a780106100f1577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a17353fd2cac865d3aa1ad6fbdebaa00802c94239fba602052610134565b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1730f59e9e105be01d5e2206792a267406f255c5ea5602052610134565b63a9059cbb6000527f0000000000000000000000000000000000000000000000000de0b6b3a764000060405273b97048628db6b661d4c2aa833e95dbe1a905b280602060606044601c600085620186a05a03f1156101b5573d156101a75760203d106101b557606051156101b5576101af565b803b156101b5575b50602051ff5b7f7472616e736665724661696c656400000000000000000000000000000000000060005260206000fd");
+        assert_that!(rfc_example(new_herc20_full_balance).as_str()).is_equal_to("6101df61000f6000396101df6000f3361561007957602036141561004f57602060006000376020602160206000600060026048f17fac5a18da6431ed256965b873ef49dc15a70a0a66e2d28d0c226b5db04012372760215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b42635c85a780106100f1577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a17353fd2cac865d3aa1ad6fbdebaa00802c94239fba602052610134565b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1730f59e9e105be01d5e2206792a267406f255c5ea5602052610134565b73b97048628db6b661d4c2aa833e95dbe1a905b2806370a0823160605230608052602060406024607c845afa156101b55760203d106101b55763a9059cbb600052602060606044601c600085620186a05a03f1156101b5573d156101a75760203d106101b557606051156101b5576101af565b803b156101b5575b50602051ff5b7f7472616e736665724661696c656400000000000000000000000000000000000060005260206000fd");
+        assert_that!(rfc_example(new_herc20_relayer_fee).as_str()).is_equal_to("61025161000f6000396102516000f3361561007957602036141561004f57602060006000376020602160206000600060026048f17fac5a18da6431ed256965b873ef49dc15a70a0a66e2d28d0c226b5db04012372760215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b42635c85a78010610117577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a17353fd2cac865d3aa1ad6fbdebaa00802c94239fba6020527f0000000000000000000000000000000000000000000000000ddd2935029d80006040526001610180565b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1730f59e9e105be01d5e2206792a267406f255c5ea56020527f0000000000000000000000000000000000000000000000000de0b6b3a76400006040526000610180565b63a9059cbb60005273b97048628db6b661d4c2aa833e95dbe1a905b280602060606044601c600085620186a05a03f115610227573d156101cf5760203d106102275760605115610227576101d7565b803b15610227575b90156102215763a9059cbb606052336080527f00000000000000000000000000000000000000000000000000038d7ea4c6800060a052602060c06044607c600085620186a05a03f1505b50602051ff5b7f7472616e736665724661696c656400000000000000000000000000000000000060005260206000fd");
+        assert_that!(rfc_example(new_heth_relayer_fee).as_str()).is_equal_to("61014661000f6000396101466000f3361561007957602036141561004f57602060006000376020602160206000600060026048f17fac5a18da6431ed256965b873ef49dc15a70a0a66e2d28d0c226b5db04012372760215114166100ae575b7f696e76616c69645365637265740000000000000000000000000000000000000060005260206000fd5b42635c85a78010610109577f746f6f4561726c7900000000000000000000000000000000000000000000000060005260206000fd5b7f72656465656d656400000000000000000000000000000000000000000000000060206000a160006000600060006f000000000000000000038d7ea4c68000336000f1507353fd2cac865d3aa1ad6fbdebaa00802c94239fbaff5b7f726566756e64656400000000000000000000000000000000000000000000000060006000a1730f59e9e105be01d5e2206792a267406f255c5ea5ff");
+    }
+}
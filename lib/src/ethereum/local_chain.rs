@@ -0,0 +1,228 @@
+//! An Ethereum chain executed in-process by the `evm` crate, on which the
+//! `GasEstimator` measures transactions and which runs HTLCs without a node.
+//!
+//! Transactions are executed against an in-memory state, in a block whose
+//! number and timestamp are set by hand. Gas is metered under the rules of a
+//! given hardfork but not paid for, hence balances only change by the value
+//! transferred.
+use crate::ethereum::gas::Hardfork;
+use evm::{
+    backend::{ApplyBackend, Log, MemoryAccount, MemoryBackend, MemoryVicinity},
+    executor::stack::{
+        MemoryStackState, PrecompileFailure, PrecompileFn, PrecompileOutput, StackExecutor,
+        StackSubstateMetadata,
+    },
+    Config, Context, ExitError, ExitReason, ExitSucceed,
+};
+use primitive_types::{H160, H256, U256};
+use rust_bitcoin::hashes::{sha256, Hash};
+use std::collections::BTreeMap;
+
+/// The gas limit of the blocks, as returned by the `GASLIMIT` opcode.
+const BLOCK_GAS_LIMIT: u64 = 30_000_000;
+
+type State = BTreeMap<H160, MemoryAccount>;
+
+#[derive(Clone, Debug)]
+pub enum Transaction {
+    Create {
+        from: H160,
+        value: U256,
+        init_code: Vec<u8>,
+    },
+    Call {
+        from: H160,
+        to: H160,
+        data: Vec<u8>,
+    },
+}
+
+/// The outcome of a transaction, as reported by its receipt.
+#[derive(Clone, Debug)]
+pub struct Outcome {
+    pub exit_reason: ExitReason,
+    pub return_data: Vec<u8>,
+    pub logs: Vec<Log>,
+    pub gas_used: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct LocalChain {
+    config: Config,
+    vicinity: MemoryVicinity,
+    state: State,
+    precompiles: BTreeMap<H160, PrecompileFn>,
+}
+
+impl LocalChain {
+    pub fn new(hardfork: Hardfork) -> Self {
+        let mut precompiles = BTreeMap::new();
+        precompiles.insert(H160::from_low_u64_be(2), sha256 as PrecompileFn);
+
+        LocalChain {
+            config: hardfork.config(),
+            vicinity: MemoryVicinity {
+                gas_price: U256::zero(),
+                origin: H160::zero(),
+                chain_id: U256::one(),
+                block_hashes: Vec::new(),
+                block_number: U256::one(),
+                block_coinbase: H160::zero(),
+                block_timestamp: U256::zero(),
+                block_difficulty: U256::zero(),
+                block_gas_limit: U256::from(BLOCK_GAS_LIMIT),
+                block_base_fee_per_gas: U256::zero(),
+                block_randomness: None,
+            },
+            state: State::new(),
+            precompiles,
+        }
+    }
+
+    pub fn block_number(&self) -> U256 {
+        self.vicinity.block_number
+    }
+
+    /// Executes the next transactions in the block with the given number.
+    pub fn set_block_number(&mut self, block_number: U256) {
+        self.vicinity.block_number = block_number;
+    }
+
+    /// Executes the next transactions in a block with the given timestamp,
+    /// e.g. to let an HTLC expire without waiting for it.
+    pub fn set_block_timestamp(&mut self, timestamp: U256) {
+        self.vicinity.block_timestamp = timestamp;
+    }
+
+    pub fn give_eth_to(&mut self, to: H160, wei_amount: U256) {
+        self.state.entry(to).or_default().balance += wei_amount;
+    }
+
+    pub fn eth_balance_of(&self, address: H160) -> U256 {
+        self.state
+            .get(&address)
+            .map(|account| account.balance)
+            .unwrap_or_default()
+    }
+
+    pub fn nonce_of(&self, address: H160) -> u64 {
+        self.state
+            .get(&address)
+            .map(|account| account.nonce.low_u64())
+            .unwrap_or_default()
+    }
+
+    pub fn code_of(&self, address: H160) -> Vec<u8> {
+        self.state
+            .get(&address)
+            .map(|account| account.code.clone())
+            .unwrap_or_default()
+    }
+
+    /// Puts `code` at `address` without deploying it, e.g. the runtime code
+    /// of a contract deployed on another chain.
+    pub fn set_code(&mut self, address: H160, code: Vec<u8>) {
+        self.state.entry(address).or_default().code = code;
+    }
+
+    pub fn set_storage(&mut self, address: H160, key: H256, value: H256) {
+        self.state
+            .entry(address)
+            .or_default()
+            .storage
+            .insert(key, value);
+    }
+
+    /// Executes the transaction and keeps the state it results in, even if
+    /// it fails.
+    pub fn transact(&mut self, transaction: &Transaction, gas_limit: u64) -> Outcome {
+        let (outcome, state) = self.execute(transaction, gas_limit);
+        self.state = state;
+
+        outcome
+    }
+
+    /// Executes the transaction and drops the state it results in, like
+    /// `eth_call`.
+    pub fn call(&self, transaction: &Transaction, gas_limit: u64) -> Outcome {
+        self.execute(transaction, gas_limit).0
+    }
+
+    fn execute(&self, transaction: &Transaction, gas_limit: u64) -> (Outcome, State) {
+        let mut backend = MemoryBackend::new(&self.vicinity, self.state.clone());
+
+        let (exit_reason, return_data, gas_used, applies, logs) = {
+            let metadata = StackSubstateMetadata::new(gas_limit, &self.config);
+            let stack_state = MemoryStackState::new(metadata, &mut backend);
+            let mut executor =
+                StackExecutor::new_with_precompiles(stack_state, &self.config, &self.precompiles);
+
+            let (exit_reason, return_data) = match transaction {
+                Transaction::Create {
+                    from,
+                    value,
+                    init_code,
+                } => executor.transact_create(
+                    *from,
+                    *value,
+                    init_code.clone(),
+                    gas_limit,
+                    Vec::new(),
+                ),
+                Transaction::Call { from, to, data } => executor.transact_call(
+                    *from,
+                    *to,
+                    U256::zero(),
+                    data.clone(),
+                    gas_limit,
+                    Vec::new(),
+                ),
+            };
+            let gas_used = executor.used_gas();
+            let (applies, logs) = executor.into_state().deconstruct();
+
+            (
+                exit_reason,
+                return_data,
+                gas_used,
+                applies.into_iter().collect::<Vec<_>>(),
+                logs.into_iter().collect::<Vec<_>>(),
+            )
+        };
+        backend.apply(applies, logs.clone(), true);
+
+        let outcome = Outcome {
+            exit_reason,
+            return_data,
+            logs,
+            gas_used,
+        };
+
+        (outcome, backend.state().clone())
+    }
+}
+
+/// The SHA-256 precompile at address `0x02`, which checks the secret of the
+/// HTLCs.
+fn sha256(
+    input: &[u8],
+    target_gas: Option<u64>,
+    _: &Context,
+    _: bool,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    let words = input.len().div_ceil(32) as u64;
+    let cost = 60 + 12 * words;
+    if target_gas.is_some_and(|target_gas| cost > target_gas) {
+        return Err(PrecompileFailure::Error {
+            exit_status: ExitError::OutOfGas,
+        });
+    }
+
+    Ok((
+        PrecompileOutput {
+            exit_status: ExitSucceed::Returned,
+            output: sha256::Hash::hash(input).into_inner().to_vec(),
+        },
+        cost,
+    ))
+}
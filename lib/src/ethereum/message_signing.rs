@@ -0,0 +1,163 @@
+//! Proofs that a party controls the key behind an `Address`, as messages
+//! signed with `personal_sign` (EIP-191 version `0x45`).
+use crate::ethereum::{address_from_hash, keccak256, Address};
+use rust_bitcoin::secp256k1::{
+    self,
+    recovery::{RecoverableSignature, RecoveryId},
+    Message, Secp256k1, SecretKey,
+};
+use std::{convert::TryFrom, fmt};
+use tiny_keccak::{Hasher, Keccak};
+
+/// `personal_sign` signatures encode the recovery id as `27 + recovery id`.
+const RECOVERY_ID_OFFSET: u8 = 27;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Error {
+    InvalidSignature,
+    AddressMismatch { expected: Address, actual: Address },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidSignature => {
+                write!(f, "signature is not a valid personal_sign signature")
+            }
+            Error::AddressMismatch { expected, actual } => write!(
+                f,
+                "message is signed by 0x{} instead of 0x{}",
+                hex(&actual.0),
+                hex(&expected.0)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Address {
+    pub fn from_public_key(public_key: &secp256k1::PublicKey) -> Address {
+        // The uncompressed key without its 0x04 prefix
+        address_from_hash(keccak256(&public_key.serialize_uncompressed()[1..]))
+    }
+}
+
+/// Signs `message` like `personal_sign` does, producing `r || s || v`.
+pub fn personal_sign<C: secp256k1::Signing>(
+    secp: &Secp256k1<C>,
+    secret_key: &SecretKey,
+    message: &[u8],
+) -> [u8; 65] {
+    let signature = secp.sign_recoverable(&message_hash(message), secret_key);
+    let (recovery_id, compact) = signature.serialize_compact();
+
+    let mut serialized = [0u8; 65];
+    serialized[..64].copy_from_slice(&compact);
+    serialized[64] = RECOVERY_ID_OFFSET
+        + u8::try_from(recovery_id.to_i32()).expect("recovery ids are between 0 and 3");
+    serialized
+}
+
+/// Verifies that `signature` signs `message` with the key behind `address`.
+///
+/// Both `27 + recovery id` and the bare recovery id are accepted for `v`.
+pub fn verify_personal_sign<C: secp256k1::Verification>(
+    secp: &Secp256k1<C>,
+    address: &Address,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), Error> {
+    if signature.len() != 65 {
+        return Err(Error::InvalidSignature);
+    }
+
+    let v = signature[64];
+    let recovery_id = v.checked_sub(RECOVERY_ID_OFFSET).unwrap_or(v);
+    let recovery_id =
+        RecoveryId::from_i32(i32::from(recovery_id)).map_err(|_| Error::InvalidSignature)?;
+    let signature = RecoverableSignature::from_compact(&signature[..64], recovery_id)
+        .map_err(|_| Error::InvalidSignature)?;
+
+    let public_key = secp
+        .recover(&message_hash(message), &signature)
+        .map_err(|_| Error::InvalidSignature)?;
+
+    let actual = Address::from_public_key(&public_key);
+    if actual != *address {
+        return Err(Error::AddressMismatch {
+            expected: *address,
+            actual,
+        });
+    }
+
+    Ok(())
+}
+
+fn message_hash(message: &[u8]) -> Message {
+    let mut keccak = Keccak::v256();
+    keccak.update(b"\x19Ethereum Signed Message:\n");
+    keccak.update(message.len().to_string().as_bytes());
+    keccak.update(message);
+    let mut hash = [0u8; 32];
+    keccak.finalize(&mut hash);
+
+    Message::from_slice(&hash).expect("Should not fail because it is a hash")
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    fn secret_key() -> SecretKey {
+        SecretKey::from_slice(&hex!(
+            "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn personal_sign_matches_web3_signature() {
+        let secp = Secp256k1::signing_only();
+
+        let signature = personal_sign(&secp, &secret_key(), b"Some data");
+
+        assert_eq!(
+            signature[..],
+            hex!(
+                "b91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd
+                 6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a029
+                 1c"
+            )[..]
+        );
+    }
+
+    #[test]
+    fn signature_verifies_for_address_of_key() {
+        let secp = Secp256k1::new();
+        let address = Address(hex!("2c7536E3605D9C16a7a3D7b1898e529396a65c23"));
+        let signature = personal_sign(&secp, &secret_key(), b"swap 42");
+
+        assert_eq!(
+            Address::from_public_key(&secp256k1::PublicKey::from_secret_key(&secp, &secret_key())),
+            address
+        );
+        assert_eq!(
+            verify_personal_sign(&secp, &address, b"swap 42", &signature),
+            Ok(())
+        );
+        match verify_personal_sign(&secp, &address, b"swap 43", &signature) {
+            Err(Error::AddressMismatch { .. }) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert_eq!(
+            verify_personal_sign(&secp, &address, b"swap 42", &signature[..64]),
+            Err(Error::InvalidSignature)
+        );
+    }
+}
@@ -1,5 +1,25 @@
+pub mod factory;
+#[cfg(feature = "gas-estimation")]
+pub mod gas;
 pub mod herc20;
+pub mod herc20_full_balance;
+pub mod herc20_relayer_fee;
+pub mod herc20_safe;
+pub mod herc20_transfer_from;
 pub mod heth;
+pub mod heth_relayer_fee;
+mod layout;
+#[cfg(feature = "gas-estimation")]
+pub mod local_chain;
+pub mod message_signing;
+mod rlp;
+pub mod transaction;
+
+use byteorder::{BigEndian, ByteOrder};
+pub use layout::{DeployedHtlcCode, HtlcCode};
+use rust_bitcoin::hashes::{sha256, Hash};
+use std::fmt;
+use tiny_keccak::{Hasher, Keccak};
 
 /// The log message emitted when the HTLC is redeemed.
 ///
@@ -23,11 +43,85 @@ pub const TOO_EARLY: &str = "746f6f4561726c7900000000000000000000000000000000000
 /// These are the hex-encoded ASCII-codepoints of the word "invalidSecret", padded to a length of 32 bytes.
 pub const INVALID_SECRET: &str = "696e76616c696453656372657400000000000000000000000000000000000000";
 
+/// The returned message when the token contract did not transfer the tokens out of an
+/// `herc20_safe`, `herc20_full_balance` or `herc20_relayer_fee` HTLC.
+///
+/// These are the hex-encoded ASCII-codepoints of the word "transferFailed", padded to a length of 32 bytes.
+pub const TRANSFER_FAILED: &str =
+    "7472616e736665724661696c6564000000000000000000000000000000000000";
+
 /// The log message emitted by an ERC-20 token contract when a transfer is done, as per the ERC-20 EIP.
 ///
 /// This is the result of keccak('Transfer(address,address,uint256)')/
 pub const ERC20_TRANSFER: &str = "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
 
+/// The length of the deploy header of the HTLC templates, which copies the
+/// runtime code following it into memory and returns it.
+pub(crate) const DEPLOY_HEADER_LEN: usize = 15;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UnknownContract;
+
+impl fmt::Display for UnknownContract {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "code is not an HTLC of the contract template")
+    }
+}
+
+impl std::error::Error for UnknownContract {}
+
+/// The expiry of an HTLC, after which it can be refunded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Expiry {
+    /// Compared with the timestamp of the block, in seconds since the epoch.
+    Timestamp(u32),
+    /// Compared with the number of the block, for chains whose block
+    /// timestamps are coarse or set by a sequencer.
+    BlockHeight(u32),
+}
+
+impl Expiry {
+    /// Reads the expiry pushed after the `opcode` of the block value it is
+    /// compared with.
+    pub(crate) fn from_placeholder(opcode: u8, value: &[u8]) -> Self {
+        let value = BigEndian::read_u32(value);
+        match opcode {
+            NUMBER => Expiry::BlockHeight(value),
+            _ => Expiry::Timestamp(value),
+        }
+    }
+
+    pub(crate) fn value(self) -> u32 {
+        match self {
+            Expiry::Timestamp(value) | Expiry::BlockHeight(value) => value,
+        }
+    }
+}
+
+/// The opcode pushing the block number, compared with block height expiries.
+const NUMBER: u8 = 0x43;
+
+/// The hash function locking an HTLC, with which the secret is hashed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashFunction {
+    /// The SHA-256 precompile, to swap with chains hashing with SHA-256 like
+    /// Bitcoin.
+    Sha256,
+    /// The `KECCAK256` opcode, cheaper when both legs of the swap are on EVM
+    /// chains and independent of precompiles.
+    Keccak256,
+}
+
+impl HashFunction {
+    /// The secret hash of `secret` to lock an HTLC with this hash function.
+    pub fn secret_hash(self, secret: &[u8]) -> [u8; 32] {
+        match self {
+            HashFunction::Sha256 => sha256::Hash::hash(secret).into_inner(),
+            HashFunction::Keccak256 => keccak256(secret),
+        }
+    }
+}
+
 /// Represent a ERC20 token quantity
 /// The inner byte array is Big Endian
 #[derive(Clone, Copy, Debug)]
@@ -35,5 +129,103 @@ pub struct TokenQuantity(pub [u8; 32]);
 
 /// Represent an Ethereum Address
 /// The inner byte array is Big Endian
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Address(pub [u8; 20]);
+
+pub(crate) fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut keccak = Keccak::v256();
+    keccak.update(data);
+    let mut hash = [0u8; 32];
+    keccak.finalize(&mut hash);
+    hash
+}
+
+/// The address of the contract deployed by the transaction of `deployer`
+/// with `nonce`, e.g. an HTLC, known before the transaction is mined.
+pub fn contract_address(deployer: Address, nonce: u64) -> Address {
+    let hash = keccak256(&rlp::list(&[
+        rlp::bytes(&deployer.0),
+        rlp::uint(nonce.into()),
+    ]));
+
+    address_from_hash(hash)
+}
+
+/// The address of the contract deployed by `deployer` with the CREATE2
+/// opcode as per EIP-1014.
+pub fn contract_address_create2(deployer: Address, salt: [u8; 32], init_code: &[u8]) -> Address {
+    let mut preimage = Vec::with_capacity(1 + 20 + 32 + 32);
+    preimage.push(0xff);
+    preimage.extend_from_slice(&deployer.0);
+    preimage.extend_from_slice(&salt);
+    preimage.extend_from_slice(&keccak256(init_code));
+
+    address_from_hash(keccak256(&preimage))
+}
+
+/// Addresses are the last 20 bytes of a hash.
+pub(crate) fn address_from_hash(hash: [u8; 32]) -> Address {
+    read_address(&hash[12..])
+}
+
+pub(crate) fn read_address(bytes: &[u8]) -> Address {
+    let mut address = [0u8; 20];
+    address.copy_from_slice(bytes);
+    Address(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn contract_addresses_match_the_ones_of_deployments() {
+        let deployer = Address(hex!("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0"));
+
+        assert_eq!(
+            contract_address(deployer, 0),
+            Address(hex!("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d"))
+        );
+        assert_eq!(
+            contract_address(deployer, 1),
+            Address(hex!("343c43a37d37dff08ae8c4a11544c718abb4fcf8"))
+        );
+        assert_eq!(
+            contract_address(deployer, 2),
+            Address(hex!("f778b86fa74e846c4f0a1fbd1335fe81c00a0c91"))
+        );
+    }
+
+    #[test]
+    fn secret_hashes_match_the_hash_functions() {
+        assert_eq!(
+            HashFunction::Sha256.secret_hash(b""),
+            hex!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+        assert_eq!(
+            HashFunction::Keccak256.secret_hash(b""),
+            hex!("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+        );
+    }
+
+    #[test]
+    fn create2_addresses_match_eip1014_examples() {
+        assert_eq!(
+            contract_address_create2(Address([0; 20]), [0; 32], &[0x00]),
+            Address(hex!("4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38"))
+        );
+        assert_eq!(
+            contract_address_create2(
+                Address(hex!("deadbeef00000000000000000000000000000000")),
+                [0; 32],
+                &[0x00]
+            ),
+            Address(hex!("B928f69Bb1D91Cd65274e3c79d8986362984fDA3"))
+        );
+        assert_eq!(
+            contract_address_create2(Address([0; 20]), [0; 32], &[]),
+            Address(hex!("E33C0C7F7df4809055C3ebA6c09CFe4BaF1BD9e0"))
+        );
+    }
+}
@@ -0,0 +1,58 @@
+//! The Recursive Length Prefix encoding of transactions and of the inputs of
+//! contract addresses.
+//!
+//! See https://eth.wiki/fundamentals/rlp
+use std::convert::TryFrom;
+
+pub fn uint(value: u128) -> Vec<u8> {
+    big_endian_uint(&value.to_be_bytes())
+}
+
+/// Integers are encoded without leading zeros.
+pub fn big_endian_uint(value: &[u8]) -> Vec<u8> {
+    bytes(strip_leading_zeros(value))
+}
+
+pub fn bytes(bytes: &[u8]) -> Vec<u8> {
+    match bytes {
+        [byte] if *byte < 0x80 => vec![*byte],
+        _ => {
+            let mut encoded = prefix(0x80, bytes.len());
+            encoded.extend_from_slice(bytes);
+            encoded
+        }
+    }
+}
+
+pub fn list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload = items.concat();
+
+    let mut encoded = prefix(0xc0, payload.len());
+    encoded.extend(payload);
+    encoded
+}
+
+/// Payloads longer than 55 bytes are prefixed with the length of their
+/// length.
+fn prefix(offset: u8, len: usize) -> Vec<u8> {
+    if let Ok(short_len @ 0..=55) = u8::try_from(len) {
+        return vec![offset + short_len];
+    }
+
+    let len = len.to_be_bytes();
+    let len = strip_leading_zeros(&len);
+    let len_len = u8::try_from(len.len()).expect("a usize is at most 16 bytes long");
+
+    let mut prefix = vec![offset + 55 + len_len];
+    prefix.extend_from_slice(len);
+    prefix
+}
+
+fn strip_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(bytes.len());
+
+    &bytes[start..]
+}
@@ -0,0 +1,388 @@
+//! Transactions deploying, redeeming and refunding HTLCs, signed offline and
+//! encoded as expected by `eth_sendRawTransaction`.
+use crate::ethereum::{keccak256, rlp, Address};
+use rust_bitcoin::secp256k1::{self, Message, Secp256k1, SecretKey};
+use std::convert::TryFrom;
+
+/// The type of EIP-1559 transactions as per EIP-2718.
+const EIP1559_TRANSACTION_TYPE: u8 = 0x02;
+
+/// How the fee of a transaction is paid, which also determines how the
+/// transaction is encoded and signed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fee {
+    /// A legacy transaction with replay protection as per EIP-155.
+    Legacy { gas_price: u128 },
+    /// An EIP-1559 typed transaction, with an empty access list.
+    Eip1559 {
+        max_priority_fee_per_gas: u128,
+        max_fee_per_gas: u128,
+    },
+}
+
+/// All amounts are in wei.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsignedTransaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub fee: Fee,
+    pub gas_limit: u64,
+    /// `None` to deploy a contract with `data` as init code.
+    pub to: Option<Address>,
+    pub value: u128,
+    pub data: Vec<u8>,
+}
+
+impl UnsignedTransaction {
+    /// Deploys `htlc`, e.g. a `heth::Htlc` with the ether it locks as `value`
+    /// or a `herc20::Htlc` with no value.
+    pub fn deploy(
+        chain_id: u64,
+        nonce: u64,
+        fee: Fee,
+        htlc: impl Into<Vec<u8>>,
+        value: u128,
+        gas_limit: u64,
+    ) -> Self {
+        UnsignedTransaction {
+            chain_id,
+            nonce,
+            fee,
+            gas_limit,
+            to: None,
+            value,
+            data: htlc.into(),
+        }
+    }
+
+    /// Redeems the HTLC deployed at `htlc_address` by revealing `secret`.
+    pub fn redeem(
+        chain_id: u64,
+        nonce: u64,
+        fee: Fee,
+        htlc_address: Address,
+        secret: [u8; 32],
+        gas_limit: u64,
+    ) -> Self {
+        UnsignedTransaction {
+            chain_id,
+            nonce,
+            fee,
+            gas_limit,
+            to: Some(htlc_address),
+            value: 0,
+            data: secret.to_vec(),
+        }
+    }
+
+    /// Refunds the HTLC deployed at `htlc_address`, which only succeeds after
+    /// its expiry.
+    pub fn refund(
+        chain_id: u64,
+        nonce: u64,
+        fee: Fee,
+        htlc_address: Address,
+        gas_limit: u64,
+    ) -> Self {
+        UnsignedTransaction {
+            chain_id,
+            nonce,
+            fee,
+            gas_limit,
+            to: Some(htlc_address),
+            value: 0,
+            data: Vec::new(),
+        }
+    }
+
+    /// The hash signed by `sign`.
+    pub fn signature_hash(&self) -> [u8; 32] {
+        match self.fee {
+            Fee::Legacy { .. } => {
+                let mut fields = self.legacy_fields();
+                fields.extend(vec![
+                    rlp::uint(self.chain_id.into()),
+                    rlp::uint(0),
+                    rlp::uint(0),
+                ]);
+
+                keccak256(&rlp::list(&fields))
+            }
+            Fee::Eip1559 { .. } => {
+                let mut payload = vec![EIP1559_TRANSACTION_TYPE];
+                payload.extend(rlp::list(&self.eip1559_fields()));
+
+                keccak256(&payload)
+            }
+        }
+    }
+
+    /// Signs the transaction, returning the raw transaction to be sent with
+    /// `eth_sendRawTransaction`.
+    pub fn sign<C: secp256k1::Signing>(
+        &self,
+        secp: &Secp256k1<C>,
+        secret_key: &SecretKey,
+    ) -> Vec<u8> {
+        let message = Message::from_slice(&self.signature_hash())
+            .expect("Should not fail because it is a hash");
+        let (recovery_id, signature) = secp
+            .sign_recoverable(&message, secret_key)
+            .serialize_compact();
+        let recovery_id =
+            u64::try_from(recovery_id.to_i32()).expect("recovery ids are between 0 and 3");
+
+        let r = rlp::big_endian_uint(&signature[..32]);
+        let s = rlp::big_endian_uint(&signature[32..]);
+
+        match self.fee {
+            Fee::Legacy { .. } => {
+                // https://github.com/ethereum/EIPs/blob/master/EIPS/eip-155.md#specification
+                let v = recovery_id + 35 + self.chain_id * 2;
+
+                let mut fields = self.legacy_fields();
+                fields.extend(vec![rlp::uint(v.into()), r, s]);
+
+                rlp::list(&fields)
+            }
+            Fee::Eip1559 { .. } => {
+                let mut fields = self.eip1559_fields();
+                fields.extend(vec![rlp::uint(recovery_id.into()), r, s]);
+
+                let mut transaction = vec![EIP1559_TRANSACTION_TYPE];
+                transaction.extend(rlp::list(&fields));
+                transaction
+            }
+        }
+    }
+
+    fn legacy_fields(&self) -> Vec<Vec<u8>> {
+        let gas_price = match self.fee {
+            Fee::Legacy { gas_price } => gas_price,
+            Fee::Eip1559 { .. } => unreachable!("only called for legacy transactions"),
+        };
+
+        vec![
+            rlp::uint(self.nonce.into()),
+            rlp::uint(gas_price),
+            rlp::uint(self.gas_limit.into()),
+            self.rlp_to(),
+            rlp::uint(self.value),
+            rlp::bytes(&self.data),
+        ]
+    }
+
+    fn eip1559_fields(&self) -> Vec<Vec<u8>> {
+        let (max_priority_fee_per_gas, max_fee_per_gas) = match self.fee {
+            Fee::Eip1559 {
+                max_priority_fee_per_gas,
+                max_fee_per_gas,
+            } => (max_priority_fee_per_gas, max_fee_per_gas),
+            Fee::Legacy { .. } => unreachable!("only called for EIP-1559 transactions"),
+        };
+
+        vec![
+            rlp::uint(self.chain_id.into()),
+            rlp::uint(self.nonce.into()),
+            rlp::uint(max_priority_fee_per_gas),
+            rlp::uint(max_fee_per_gas),
+            rlp::uint(self.gas_limit.into()),
+            self.rlp_to(),
+            rlp::uint(self.value),
+            rlp::bytes(&self.data),
+            // The access list
+            rlp::list(&[]),
+        ]
+    }
+
+    fn rlp_to(&self) -> Vec<u8> {
+        match self.to {
+            Some(address) => rlp::bytes(&address.0),
+            None => rlp::bytes(&[]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::{heth::Htlc, Expiry, HashFunction};
+    use hex_literal::hex;
+    use rust_bitcoin::secp256k1::recovery::{RecoverableSignature, RecoveryId};
+
+    const CHAIN_ID: u64 = 1;
+
+    fn secret_key() -> SecretKey {
+        SecretKey::from_slice(&[0x46; 32]).unwrap()
+    }
+
+    fn eip1559() -> Fee {
+        Fee::Eip1559 {
+            max_priority_fee_per_gas: 2_000_000_000,
+            max_fee_per_gas: 100_000_000_000,
+        }
+    }
+
+    /// Decodes the items of the RLP list of a transaction.
+    fn decode_list(encoded: &[u8]) -> Vec<Vec<u8>> {
+        let (header, mut payload) = match encoded[0] {
+            prefix @ 0xc0..=0xf7 => (1, usize::from(prefix - 0xc0)),
+            prefix => (1 + usize::from(prefix - 0xf7), 0),
+        };
+        if header > 1 {
+            payload = encoded[1..header]
+                .iter()
+                .fold(0, |len, byte| len << 8 | usize::from(*byte));
+        }
+
+        let mut rest = &encoded[header..header + payload];
+        let mut items = Vec::new();
+        while !rest.is_empty() {
+            let (start, len) = match rest[0] {
+                0x00..=0x7f => (0, 1),
+                prefix @ 0x80..=0xb7 => (1, usize::from(prefix - 0x80)),
+                // Only the empty access list is nested in the list
+                prefix @ 0xc0..=0xf7 => (1, usize::from(prefix - 0xc0)),
+                prefix => {
+                    let len_len = usize::from(prefix - 0xb7);
+                    let len = rest[1..=len_len]
+                        .iter()
+                        .fold(0, |len, byte| len << 8 | usize::from(*byte));
+                    (1 + len_len, len)
+                }
+            };
+            items.push(rest[start..start + len].to_vec());
+            rest = &rest[start + len..];
+        }
+
+        items
+    }
+
+    fn signer(transaction: &UnsignedTransaction, raw: &[u8]) -> Address {
+        let items = match transaction.fee {
+            Fee::Legacy { .. } => decode_list(raw),
+            Fee::Eip1559 { .. } => decode_list(&raw[1..]),
+        };
+        let (v, r, s) = (
+            &items[items.len() - 3],
+            &items[items.len() - 2],
+            &items[items.len() - 1],
+        );
+        let v = v.iter().fold(0, |v, byte| v << 8 | u64::from(*byte));
+        let recovery_id = match transaction.fee {
+            Fee::Legacy { .. } => v - 35 - CHAIN_ID * 2,
+            Fee::Eip1559 { .. } => v,
+        };
+
+        let mut compact = [0u8; 64];
+        compact[32 - r.len()..32].copy_from_slice(r);
+        compact[64 - s.len()..].copy_from_slice(s);
+        let signature = RecoverableSignature::from_compact(
+            &compact,
+            RecoveryId::from_i32(i32::try_from(recovery_id).unwrap()).unwrap(),
+        )
+        .unwrap();
+        let message = Message::from_slice(&transaction.signature_hash()).unwrap();
+
+        Address::from_public_key(
+            &Secp256k1::verification_only()
+                .recover(&message, &signature)
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn legacy_transaction_matches_eip155_example() {
+        let transaction = UnsignedTransaction {
+            chain_id: CHAIN_ID,
+            nonce: 9,
+            fee: Fee::Legacy {
+                gas_price: 20_000_000_000,
+            },
+            gas_limit: 21_000,
+            to: Some(Address([0x35; 20])),
+            value: 1_000_000_000_000_000_000,
+            data: Vec::new(),
+        };
+
+        assert_eq!(
+            transaction.signature_hash(),
+            hex!("daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53")
+        );
+        assert_eq!(
+            transaction.sign(&Secp256k1::signing_only(), &secret_key()),
+            hex!(
+                "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a7
+                 6400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a0
+                 67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+            )
+            .to_vec()
+        );
+    }
+
+    #[test]
+    fn deploy_transaction_carries_htlc_as_init_code() {
+        let htlc = Htlc::new(
+            Expiry::Timestamp(2_000_000_000),
+            Address([1; 20]),
+            Address([2; 20]),
+            [3; 32],
+            HashFunction::Sha256,
+        );
+        let init_code: Vec<u8> = Htlc::new(
+            Expiry::Timestamp(2_000_000_000),
+            Address([1; 20]),
+            Address([2; 20]),
+            [3; 32],
+            HashFunction::Sha256,
+        )
+        .into();
+
+        let transaction = UnsignedTransaction::deploy(
+            CHAIN_ID,
+            0,
+            eip1559(),
+            htlc,
+            1_000,
+            Htlc::deploy_tx_gas_limit(),
+        );
+        let raw = transaction.sign(&Secp256k1::signing_only(), &secret_key());
+
+        assert_eq!(raw[0], EIP1559_TRANSACTION_TYPE);
+        // The init code is longer than 255 bytes
+        let init_code_position = raw
+            .windows(init_code.len())
+            .position(|window| window == &init_code[..])
+            .unwrap();
+        assert_eq!(
+            raw[init_code_position - 3..init_code_position],
+            [0xb9, 0x01, 0x37]
+        );
+        // The empty `to` and the value precede the init code
+        assert_eq!(
+            raw[init_code_position - 7..init_code_position - 3],
+            [0x80, 0x82, 0x03, 0xe8]
+        );
+    }
+
+    #[test]
+    fn signed_transactions_recover_to_address_of_key() {
+        let address = Address::from_public_key(&secp256k1::PublicKey::from_secret_key(
+            &Secp256k1::signing_only(),
+            &secret_key(),
+        ));
+        let htlc_address = Address([7; 20]);
+
+        for fee in [Fee::Legacy { gas_price: 1 }, eip1559()].iter() {
+            let redeem =
+                UnsignedTransaction::redeem(CHAIN_ID, 1, *fee, htlc_address, [4; 32], 100_000);
+            let refund = UnsignedTransaction::refund(CHAIN_ID, 2, *fee, htlc_address, 100_000);
+
+            for transaction in [redeem, refund].iter() {
+                let raw = transaction.sign(&Secp256k1::signing_only(), &secret_key());
+
+                assert_eq!(signer(transaction, &raw), address);
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::ethereum::{Address, TokenQuantity};
+use crate::ethereum::{Address, Expiry, TokenQuantity};
 use byteorder::{BigEndian, ByteOrder, LittleEndian};
 use rust_bitcoin::hashes::hash160;
 
@@ -24,6 +24,12 @@ impl FitIntoPlaceholderSlice for EthereumTimestamp {
     }
 }
 
+impl FitIntoPlaceholderSlice for Expiry {
+    fn fit_into_placeholder_slice(self, buf: &mut [u8]) {
+        BigEndian::write_u32(buf, self.value());
+    }
+}
+
 impl FitIntoPlaceholderSlice for BitcoinTimestamp {
     fn fit_into_placeholder_slice(self, buf: &mut [u8]) {
         LittleEndian::write_u32(buf, self.0);
@@ -14,6 +14,7 @@
 #![forbid(unsafe_code)]
 
 pub mod bitcoin;
+pub mod elements;
 pub mod ethereum;
 mod fit_into_placeholder_slice;
 
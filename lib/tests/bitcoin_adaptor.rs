@@ -0,0 +1,109 @@
+pub mod bitcoin_helper;
+
+use bitcoin_helper::new_tc_bitcoincore_client;
+use blockchain_contracts::bitcoin::{
+    adaptor::{EncryptedSignature, TwoOfTwo},
+    witness::{PrimedInput, PrimedTransaction},
+};
+use rust_bitcoin::{
+    consensus::encode::serialize_hex,
+    network::constants::Network,
+    secp256k1::{self, PublicKey, Secp256k1},
+    Address, Amount, PrivateKey,
+};
+use std::str::FromStr;
+use testcontainers::{clients::Cli, images::coblox_bitcoincore::BitcoinCore, Docker};
+
+#[test]
+fn redeem_two_of_two_with_decrypted_signature_reveals_secret() {
+    let _ = pretty_env_logger::try_init();
+    let secp: Secp256k1<secp256k1::All> = Secp256k1::new();
+    let docker = Cli::default();
+    let container = docker.run(BitcoinCore::default());
+    let client = new_tc_bitcoincore_client(&container);
+    client.mine_bitcoins();
+
+    let alice_secret_key =
+        PrivateKey::from_str("cSrWvMrWE3biZinxPZc1hSwMMEdYgYsFpB6iEoh8KraLqYZUUCtt")
+            .unwrap()
+            .key;
+    let bob_secret_key =
+        PrivateKey::from_str("cNZUJxVXghSri4dUaNW8ES3KiFyDoWVffLYDz7KMcHmKhLdFyZPx")
+            .unwrap()
+            .key;
+    let alice_public_key = PublicKey::from_secret_key(&secp, &alice_secret_key);
+    let bob_public_key = PublicKey::from_secret_key(&secp, &bob_secret_key);
+
+    let decryption_key =
+        PrivateKey::from_str("L4nZrdzNnawCtaEcYGWuPqagQA3dJxVPgN8ARTXaMLCxiYCy89wm")
+            .unwrap()
+            .key;
+    let encryption_key = PublicKey::from_secret_key(&secp, &decryption_key);
+
+    let two_of_two = TwoOfTwo::new(alice_public_key, bob_public_key);
+    let two_of_two_address = two_of_two.compute_address(Network::Regtest);
+    let input_amount = Amount::from_sat(100_000_001);
+
+    let txid = client
+        .send_to_address(&two_of_two_address, input_amount)
+        .unwrap();
+    client.generate(1).unwrap();
+    let vout = client.find_vout_for_address(&txid, &two_of_two_address);
+
+    let alice_addr: Address = client.get_new_address().unwrap();
+    let fee = Amount::from_sat(1000);
+
+    let message = PrimedTransaction {
+        inputs: vec![PrimedInput::new(
+            vout,
+            input_amount,
+            two_of_two.sighash_parameters(),
+        )],
+        output_address: alice_addr.clone(),
+    }
+    .signature_hash(0, fee)
+    .unwrap();
+
+    let encrypted_signature =
+        EncryptedSignature::encrypt(&secp, &bob_secret_key, &message, &encryption_key);
+    encrypted_signature
+        .verify(&secp, &bob_public_key, &message, &encryption_key)
+        .unwrap();
+
+    let redeem_tx = PrimedTransaction {
+        inputs: vec![PrimedInput::new(
+            vout,
+            input_amount,
+            two_of_two
+                .unlock_with_signature(
+                    &secp,
+                    alice_secret_key,
+                    encrypted_signature.decrypt(&decryption_key),
+                )
+                .unwrap(),
+        )],
+        output_address: alice_addr.clone(),
+    }
+    .sign_with_fee(&secp, fee);
+
+    let rpc_redeem_txid = client
+        .send_raw_transaction(serialize_hex(&redeem_tx))
+        .unwrap();
+    client.generate(1).unwrap();
+
+    assert!(
+        client
+            .find_utxo_at_tx_for_address(&rpc_redeem_txid, &alice_addr)
+            .is_some(),
+        "utxo should exist after redeeming 2-of-2"
+    );
+
+    let published_signature = two_of_two
+        .extract_signature(&redeem_tx, 0, &bob_public_key)
+        .unwrap();
+    let recovered_key = encrypted_signature
+        .recover(&secp, &published_signature, &encryption_key)
+        .unwrap();
+
+    assert_eq!(recovered_key, decryption_key);
+}
@@ -0,0 +1,74 @@
+pub mod bitcoin_helper;
+
+use bitcoin_helper::new_tc_bitcoincore_client;
+use blockchain_contracts::bitcoin::{
+    atomicswap,
+    chain::{ChainTransaction, BITCOIN_REGTEST},
+    witness::PrimedInput,
+    Identity,
+};
+use rust_bitcoin::{
+    consensus::encode::serialize_hex,
+    hashes::{sha256, Hash},
+    secp256k1::{Secp256k1, SecretKey},
+    Address, Amount,
+};
+use std::str::FromStr;
+use testcontainers::{clients::Cli, images::coblox_bitcoincore::BitcoinCore, Docker};
+
+#[test]
+fn refund_atomicswap_contract_with_block_height_locktime() {
+    let _ = pretty_env_logger::try_init();
+    let docker = Cli::default();
+
+    let container = docker.run(BitcoinCore::default());
+    let client = new_tc_bitcoincore_client(&container);
+    client.generate(101).unwrap();
+
+    let secp = Secp256k1::new();
+    let recipient_secret_key = SecretKey::from_slice(&[2u8; 32]).unwrap();
+    let refund_secret_key = SecretKey::from_slice(&[3u8; 32]).unwrap();
+
+    // The funding transaction is mined at the locktime height
+    let locktime = client.get_blockchain_info().unwrap().blocks + 1;
+    let contract = atomicswap::contract(
+        sha256::Hash::hash(&[1u8; 32]).into_inner(),
+        Identity::from_secret_key(&secp, &recipient_secret_key),
+        Identity::from_secret_key(&secp, &refund_secret_key),
+        locktime,
+    );
+
+    let contract_address =
+        Address::from_str(&atomicswap::contract_address(&contract, &BITCOIN_REGTEST)).unwrap();
+    let amount = Amount::from_sat(100_000_000);
+    let txid = client.send_to_address(&contract_address, amount).unwrap();
+    client.generate(1).unwrap();
+    let vout = client.find_vout_for_address(&txid, &contract_address);
+
+    let refund_address = client.get_new_address().unwrap();
+    let refund_tx = ChainTransaction {
+        chain: BITCOIN_REGTEST.without_segwit(),
+        inputs: vec![PrimedInput::new(
+            vout,
+            amount,
+            contract
+                .unlock_after_timeout(&secp, refund_secret_key)
+                .unwrap(),
+        )],
+        output_script: refund_address.script_pubkey(),
+    }
+    .sign_with_rate(&secp, Amount::from_sat(2))
+    .unwrap();
+
+    let refund_txid = client
+        .send_raw_transaction(serialize_hex(&refund_tx))
+        .unwrap();
+    client.generate(1).unwrap();
+
+    assert!(
+        client
+            .find_utxo_at_tx_for_address(&refund_txid, &refund_address)
+            .is_some(),
+        "utxo should exist after refunding contract"
+    );
+}
@@ -0,0 +1,79 @@
+pub mod bitcoin_helper;
+
+use bitcoin_helper::new_tc_bitcoincore_client;
+use blockchain_contracts::bitcoin::{
+    chain::{ChainParams, ChainTransaction, SpendRules, BITCOIN_REGTEST},
+    hbit::Htlc,
+    witness::PrimedInput,
+    Identity,
+};
+use rust_bitcoin::{
+    consensus::encode::serialize_hex,
+    hashes::{sha256, Hash},
+    secp256k1::{Secp256k1, SecretKey},
+    Address, Amount,
+};
+use std::str::FromStr;
+use testcontainers::{clients::Cli, images::coblox_bitcoincore::BitcoinCore, Docker};
+
+/// Bitcoin Core still relays P2SH spends signed with the original signature
+/// hash algorithm, which are the spends of chains without segwit.
+#[test]
+fn redeem_p2sh_htlc_signed_with_legacy_rules() {
+    let _ = pretty_env_logger::try_init();
+    let docker = Cli::default();
+
+    let container = docker.run(BitcoinCore::default());
+    let client = new_tc_bitcoincore_client(&container);
+    client.generate(101).unwrap();
+
+    let secp = Secp256k1::new();
+    let chain = ChainParams {
+        rules: SpendRules::Legacy,
+        ..BITCOIN_REGTEST
+    };
+
+    let secret = [1u8; 32];
+    let redeem_secret_key = SecretKey::from_slice(&[2u8; 32]).unwrap();
+    let refund_secret_key = SecretKey::from_slice(&[3u8; 32]).unwrap();
+    let htlc = Htlc::new(
+        2_000_000_000,
+        Identity::from_secret_key(&secp, &refund_secret_key),
+        Identity::from_secret_key(&secp, &redeem_secret_key),
+        sha256::Hash::hash(&secret).into_inner(),
+    );
+
+    let htlc_address = Address::from_str(&htlc.compute_address_on(&chain)).unwrap();
+    assert_eq!(htlc_address.script_pubkey(), htlc.script_pubkey_on(&chain));
+
+    let amount = Amount::from_sat(100_000_000);
+    let txid = client.send_to_address(&htlc_address, amount).unwrap();
+    client.generate(1).unwrap();
+    let vout = client.find_vout_for_address(&txid, &htlc_address);
+
+    let redeem_address = client.get_new_address().unwrap();
+    let redeem_tx = ChainTransaction {
+        chain,
+        inputs: vec![PrimedInput::new(
+            vout,
+            amount,
+            htlc.unlock_with_secret(&secp, redeem_secret_key, secret)
+                .unwrap(),
+        )],
+        output_script: redeem_address.script_pubkey(),
+    }
+    .sign_with_rate(&secp, Amount::from_sat(2))
+    .unwrap();
+
+    let redeem_txid = client
+        .send_raw_transaction(serialize_hex(&redeem_tx))
+        .unwrap();
+    client.generate(1).unwrap();
+
+    assert!(
+        client
+            .find_utxo_at_tx_for_address(&redeem_txid, &redeem_address)
+            .is_some(),
+        "utxo should exist after redeeming htlc"
+    );
+}
@@ -0,0 +1,79 @@
+pub mod bitcoin_helper;
+
+use bitcoin_helper::new_tc_bitcoincore_client;
+use blockchain_contracts::bitcoin::witness::{
+    PrimedInput, PrimedTransaction, UnlockParameters, Witness,
+};
+use rust_bitcoin::{
+    blockdata::{opcodes::all::*, script::Builder},
+    consensus::encode::serialize_hex,
+    network::constants::Network,
+    secp256k1::{PublicKey, Secp256k1, SecretKey},
+    Address, Amount, SigHashType,
+};
+use testcontainers::{clients::Cli, images::coblox_bitcoincore::BitcoinCore, Docker};
+
+/// Spends a 2-of-3 multisig, a script this crate knows nothing about, with a
+/// `SIGHASH_ALL` signature and a `SIGHASH_SINGLE | SIGHASH_ANYONECANPAY` one.
+#[test]
+fn spend_custom_multisig_script() {
+    let _ = pretty_env_logger::try_init();
+    let docker = Cli::default();
+
+    let container = docker.run(BitcoinCore::default());
+    let client = new_tc_bitcoincore_client(&container);
+    client.generate(101).unwrap();
+
+    let secp = Secp256k1::new();
+    let secret_keys = [1u8, 2, 3]
+        .iter()
+        .map(|byte| SecretKey::from_slice(&[*byte; 32]).unwrap())
+        .collect::<Vec<_>>();
+    let multisig = secret_keys
+        .iter()
+        .fold(Builder::new().push_int(2), |builder, secret_key| {
+            builder.push_slice(&PublicKey::from_secret_key(&secp, secret_key).serialize())
+        })
+        .push_int(3)
+        .push_opcode(OP_CHECKMULTISIG)
+        .into_script();
+
+    let script_address = Address::p2wsh(&multisig, Network::Regtest);
+    let amount = Amount::from_sat(100_000_000);
+    let txid = client.send_to_address(&script_address, amount).unwrap();
+    client.generate(1).unwrap();
+    let vout = client.find_vout_for_address(&txid, &script_address);
+
+    let unlock_parameters = UnlockParameters::p2wsh(
+        multisig,
+        vec![
+            Witness::Empty,
+            Witness::Signature(secret_keys[0].into()),
+            Witness::SignatureWithHashType(
+                secret_keys[2].into(),
+                SigHashType::SinglePlusAnyoneCanPay,
+            ),
+            Witness::PrevScript,
+        ],
+    );
+
+    let output_address = client.get_new_address().unwrap();
+    let transaction = PrimedTransaction {
+        inputs: vec![PrimedInput::new(vout, amount, unlock_parameters)],
+        output_address: output_address.clone(),
+    }
+    .sign_with_rate(&secp, Amount::from_sat(2))
+    .unwrap();
+
+    let spend_txid = client
+        .send_raw_transaction(serialize_hex(&transaction))
+        .unwrap();
+    client.generate(1).unwrap();
+
+    assert!(
+        client
+            .find_utxo_at_tx_for_address(&spend_txid, &output_address)
+            .is_some(),
+        "utxo should exist after spending the multisig output"
+    );
+}
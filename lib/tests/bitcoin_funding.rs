@@ -0,0 +1,78 @@
+pub mod bitcoin_helper;
+
+use bitcoin_helper::new_tc_bitcoincore_client;
+use blockchain_contracts::bitcoin::{
+    funding::FundingTransaction,
+    hbit::Htlc,
+    witness::{PrimedInput, UnlockP2wpkh},
+    Identity,
+};
+use rust_bitcoin::{
+    consensus::encode::serialize_hex,
+    network::constants::Network,
+    secp256k1::{self, PublicKey, Secp256k1},
+    Address, Amount, PrivateKey,
+};
+use std::str::FromStr;
+use testcontainers::{clients::Cli, images::coblox_bitcoincore::BitcoinCore, Docker};
+
+#[test]
+fn fund_two_htlcs_in_one_transaction() {
+    let _ = pretty_env_logger::try_init();
+    let secp: Secp256k1<secp256k1::All> = Secp256k1::new();
+    let docker = Cli::default();
+    let container = docker.run(BitcoinCore::default());
+    let client = new_tc_bitcoincore_client(&container);
+    client.mine_bitcoins();
+
+    let secret_key = PrivateKey::from_str("L4nZrdzNnawCtaEcYGWuPqagQA3dJxVPgN8ARTXaMLCxiYCy89wm")
+        .unwrap()
+        .key;
+    let public_key = PublicKey::from_secret_key(&secp, &secret_key);
+    let identity = Identity::from(public_key);
+
+    let (_, outpoint_1) = client.create_p2wpkh_vout_at(public_key, Amount::from_sat(60_000_000));
+    let (_, outpoint_2) = client.create_p2wpkh_vout_at(public_key, Amount::from_sat(70_000_000));
+
+    let htlc_1 = Htlc::new(2_000_000_000, identity, identity, [1u8; 32]);
+    let htlc_2 = Htlc::new(2_000_000_000, identity, identity, [2u8; 32]);
+    let htlc_1_address = htlc_1.compute_address(Network::Regtest);
+    let htlc_2_address = htlc_2.compute_address(Network::Regtest);
+    let change_address: Address = client.get_new_address().unwrap();
+
+    let funding_tx = FundingTransaction {
+        utxos: vec![
+            PrimedInput::new(
+                outpoint_1,
+                Amount::from_sat(60_000_000),
+                secret_key.p2wpkh_unlock_parameters(&secp),
+            ),
+            PrimedInput::new(
+                outpoint_2,
+                Amount::from_sat(70_000_000),
+                secret_key.p2wpkh_unlock_parameters(&secp),
+            ),
+        ],
+        htlcs: vec![
+            (htlc_1, Amount::from_sat(50_000_000)),
+            (htlc_2, Amount::from_sat(50_000_000)),
+        ],
+        change_address: change_address.clone(),
+    }
+    .sign_with_rate(&secp, Amount::from_sat(10))
+    .unwrap();
+
+    let txid = client
+        .send_raw_transaction(serialize_hex(&funding_tx))
+        .unwrap();
+    client.generate(1).unwrap();
+
+    let mined_tx = client.get_raw_transaction(&funding_tx.txid()).unwrap();
+    for address in &[htlc_1_address, htlc_2_address] {
+        let outpoint = client.find_vout_for_address(&funding_tx.txid(), address);
+        assert_eq!(mined_tx.output[outpoint.vout as usize].value, 50_000_000);
+    }
+    assert!(client
+        .find_utxo_at_tx_for_address(&txid, &change_address)
+        .is_some());
+}
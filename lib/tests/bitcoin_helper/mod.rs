@@ -204,7 +204,7 @@ impl Client {
     }
 
     pub fn find_vout_for_address(&self, txid: &Txid, address: &Address) -> OutPoint {
-        let tx = self.get_raw_transaction(&txid).unwrap();
+        let tx = self.get_raw_transaction(txid).unwrap();
 
         tx.output
             .iter()
@@ -260,6 +260,7 @@ pub struct Unspent {
 
 #[derive(Debug, Deserialize)]
 pub struct BlockchainInfo {
+    pub blocks: u32,
     pub mediantime: u64,
 }
 
@@ -0,0 +1,73 @@
+pub mod bitcoin_helper;
+
+use bitcoin_helper::new_tc_bitcoincore_client;
+use blockchain_contracts::bitcoin::{
+    multi_secret::MultiSecretHtlc,
+    witness::{PrimedInput, PrimedTransaction},
+    Identity,
+};
+use rust_bitcoin::{
+    consensus::encode::serialize_hex,
+    hashes::{sha256, Hash},
+    network::constants::Network,
+    secp256k1::{Secp256k1, SecretKey},
+    Amount,
+};
+use testcontainers::{clients::Cli, images::coblox_bitcoincore::BitcoinCore, Docker};
+
+#[test]
+fn redeem_htlc_with_two_secrets() {
+    let _ = pretty_env_logger::try_init();
+    let docker = Cli::default();
+
+    let container = docker.run(BitcoinCore::default());
+    let client = new_tc_bitcoincore_client(&container);
+    client.generate(101).unwrap();
+
+    let secp = Secp256k1::new();
+    let redeem_secret_key = SecretKey::from_slice(&[2u8; 32]).unwrap();
+    let refund_secret_key = SecretKey::from_slice(&[3u8; 32]).unwrap();
+    let secrets = vec![[4u8; 32], [5u8; 32]];
+
+    let htlc = MultiSecretHtlc::new(
+        2_000_000_000,
+        Identity::from_secret_key(&secp, &refund_secret_key),
+        Identity::from_secret_key(&secp, &redeem_secret_key),
+        secrets
+            .iter()
+            .map(|secret| sha256::Hash::hash(secret).into_inner())
+            .collect(),
+    )
+    .unwrap();
+
+    let htlc_address = htlc.compute_address(Network::Regtest);
+    let amount = Amount::from_sat(100_000_000);
+    let txid = client.send_to_address(&htlc_address, amount).unwrap();
+    client.generate(1).unwrap();
+    let vout = client.find_vout_for_address(&txid, &htlc_address);
+
+    let redeem_address = client.get_new_address().unwrap();
+    let redeem_tx = PrimedTransaction {
+        inputs: vec![PrimedInput::new(
+            vout,
+            amount,
+            htlc.unlock_with_secrets(&secp, redeem_secret_key, secrets)
+                .unwrap(),
+        )],
+        output_address: redeem_address.clone(),
+    }
+    .sign_with_rate(&secp, Amount::from_sat(2))
+    .unwrap();
+
+    let redeem_txid = client
+        .send_raw_transaction(serialize_hex(&redeem_tx))
+        .unwrap();
+    client.generate(1).unwrap();
+
+    assert!(
+        client
+            .find_utxo_at_tx_for_address(&redeem_txid, &redeem_address)
+            .is_some(),
+        "utxo should exist after redeeming htlc"
+    );
+}
@@ -0,0 +1,100 @@
+pub mod bitcoin_helper;
+
+use bitcoin_helper::{new_tc_bitcoincore_client, Client};
+use blockchain_contracts::bitcoin::{
+    submarine::SwapScript,
+    witness::{PrimedInput, PrimedTransaction, UnlockParameters},
+};
+use rust_bitcoin::{
+    consensus::encode::serialize_hex,
+    hashes::{sha256, Hash},
+    network::constants::Network,
+    secp256k1::{PublicKey, Secp256k1, SecretKey},
+    Amount,
+};
+use testcontainers::{clients::Cli, images::coblox_bitcoincore::BitcoinCore, Docker};
+
+const PREIMAGE: [u8; 32] = [7u8; 32];
+
+fn secret_key(byte: u8) -> SecretKey {
+    SecretKey::from_slice(&[byte; 32]).unwrap()
+}
+
+fn public_key(byte: u8) -> PublicKey {
+    PublicKey::from_secret_key(&Secp256k1::signing_only(), &secret_key(byte))
+}
+
+fn fund_and_spend(
+    client: &Client,
+    swap_script: SwapScript,
+    unlock: impl FnOnce(SwapScript) -> UnlockParameters,
+) -> Result<(), anyhow::Error> {
+    let address = swap_script.compute_address(Network::Regtest);
+    let amount = Amount::from_sat(100_000);
+    let txid = client.send_to_address(&address, amount)?;
+    client.generate(1)?;
+    let vout = client.find_vout_for_address(&txid, &address);
+
+    let output_address = client.get_new_address()?;
+    let transaction = PrimedTransaction {
+        inputs: vec![PrimedInput::new(vout, amount, unlock(swap_script))],
+        output_address: output_address.clone(),
+    }
+    .sign_with_fee(&Secp256k1::signing_only(), Amount::from_sat(1_000));
+
+    let spend_txid = client.send_raw_transaction(serialize_hex(&transaction))?;
+    client.generate(1)?;
+
+    assert!(client
+        .find_utxo_at_tx_for_address(&spend_txid, &output_address)
+        .is_some());
+
+    Ok(())
+}
+
+#[test]
+fn claim_reverse_swap_with_preimage() {
+    let docker = Cli::default();
+    let container = docker.run(BitcoinCore::default());
+    let client = new_tc_bitcoincore_client(&container);
+    client.generate(101).unwrap();
+
+    let timeout = client.get_blockchain_info().unwrap().blocks + 100;
+    let swap_script = SwapScript::reverse(
+        sha256::Hash::hash(&PREIMAGE).into_inner(),
+        public_key(1),
+        public_key(2),
+        timeout,
+    );
+
+    fund_and_spend(&client, swap_script, |swap_script| {
+        swap_script
+            .unlock_with_preimage(&Secp256k1::signing_only(), secret_key(1), PREIMAGE)
+            .unwrap()
+    })
+    .unwrap();
+}
+
+#[test]
+fn refund_submarine_swap_after_timeout() {
+    let docker = Cli::default();
+    let container = docker.run(BitcoinCore::default());
+    let client = new_tc_bitcoincore_client(&container);
+    client.generate(101).unwrap();
+
+    // The funding transaction is mined at the timeout height
+    let timeout = client.get_blockchain_info().unwrap().blocks + 1;
+    let swap_script = SwapScript::submarine(
+        sha256::Hash::hash(&PREIMAGE).into_inner(),
+        public_key(1),
+        public_key(2),
+        timeout,
+    );
+
+    fund_and_spend(&client, swap_script, |swap_script| {
+        swap_script
+            .unlock_after_timeout(&Secp256k1::signing_only(), secret_key(2))
+            .unwrap()
+    })
+    .unwrap();
+}
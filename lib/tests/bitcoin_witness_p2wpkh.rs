@@ -111,5 +111,5 @@ fn redeem_two_p2wpkh() {
         .value;
     let expected_amount = Amount::from_sat(input_amount.as_sat() * 2 - fee.as_sat()).as_sat();
 
-    assert_that(&actual_amount).is_equal_to(&expected_amount);
+    assert_that(&actual_amount).is_equal_to(expected_amount);
 }
@@ -0,0 +1,66 @@
+#![warn(unused_extern_crates, missing_debug_implementations, rust_2018_idioms)]
+#![forbid(unsafe_code)]
+
+pub mod evm_client;
+pub mod htlc_harness;
+
+use crate::{
+    evm_client::EvmClient,
+    htlc_harness::{new_account, Timestamp, SECRET_HASH},
+};
+use blockchain_contracts::ethereum::{
+    contract_address, herc20, heth, Address, Expiry, HashFunction, TokenQuantity,
+};
+use primitive_types::U256;
+
+#[test]
+fn precomputed_addresses_are_the_ones_of_deployed_htlcs() {
+    let _ = pretty_env_logger::try_init();
+
+    let (_, alice) =
+        new_account("63be4b0d638d44b5fee5b050ab0beeeae7b68cde3d829a3321f8009cdd76b992");
+    let (_, bob) = new_account("f8218ebf6e2626bd1415c18321496e0c5725f0e1d774c7c2eab69f7650ad6e82");
+
+    let mut client = EvmClient::new(Timestamp::now());
+    client.give_eth_to(alice, U256::from(1_000_000_000_000_000_000u64));
+
+    let heth_htlc = heth::Htlc::new(
+        Expiry::Timestamp(u32::MAX),
+        Address(alice.into()),
+        Address(bob.into()),
+        SECRET_HASH,
+        HashFunction::Sha256,
+    );
+    let heth_receipt = client.deploy_htlc(
+        alice,
+        heth_htlc.into(),
+        U256::from(1_000),
+        heth::Htlc::deploy_tx_gas_limit(),
+    );
+
+    let herc20_htlc = herc20::Htlc::new(
+        Expiry::Timestamp(u32::MAX),
+        Address(alice.into()),
+        Address(bob.into()),
+        SECRET_HASH,
+        HashFunction::Sha256,
+        Address([1u8; 20]),
+        TokenQuantity([2u8; 32]),
+    );
+    let herc20_receipt = client.deploy_htlc(
+        alice,
+        herc20_htlc.into(),
+        U256::zero(),
+        herc20::Htlc::deploy_tx_gas_limit(),
+    );
+
+    let heth_address = heth_receipt.contract_address.unwrap();
+    let herc20_address = herc20_receipt.contract_address.unwrap();
+    assert_eq!(heth_address.0, contract_address(Address(alice.into()), 0).0);
+    assert_eq!(
+        herc20_address.0,
+        contract_address(Address(alice.into()), 1).0
+    );
+    assert!(!client.get_contract_code(heth_address).is_empty());
+    assert!(!client.get_contract_code(herc20_address).is_empty());
+}
@@ -0,0 +1,126 @@
+#![warn(unused_extern_crates, missing_debug_implementations, rust_2018_idioms)]
+#![forbid(unsafe_code)]
+
+pub mod evm_client;
+pub mod htlc_harness;
+
+use crate::{
+    evm_client::EvmClient,
+    htlc_harness::{new_account, Timestamp, SECRET, SECRET_HASH},
+};
+use blockchain_contracts::ethereum::{
+    factory, heth, Address, Expiry, HashFunction, REDEEMED_LOG_MSG,
+};
+use primitive_types::{H160, H256, U256};
+
+#[test]
+fn factory_deploys_htlc_at_predicted_address() {
+    let _ = pretty_env_logger::try_init();
+
+    let (_, alice) =
+        new_account("63be4b0d638d44b5fee5b050ab0beeeae7b68cde3d829a3321f8009cdd76b992");
+    let (_, bob) = new_account("f8218ebf6e2626bd1415c18321496e0c5725f0e1d774c7c2eab69f7650ad6e82");
+
+    let mut client = EvmClient::new(Timestamp::now());
+    client.give_eth_to(alice, U256::from(1_000_000_000_000_000_000u64));
+
+    let factory_address = client
+        .deploy_htlc(
+            alice,
+            factory::CONTRACT.to_vec(),
+            U256::zero(),
+            factory::deploy_tx_gas_limit(),
+        )
+        .contract_address
+        .unwrap();
+
+    let htlc: Vec<u8> = heth::Htlc::new(
+        Expiry::Timestamp(u32::MAX),
+        Address(alice.into()),
+        Address(bob.into()),
+        SECRET_HASH,
+        HashFunction::Sha256,
+    )
+    .into();
+    let htlc_address = factory::htlc_address(
+        Address(factory_address.into()),
+        Address(alice.into()),
+        SECRET_HASH,
+        &htlc,
+    );
+
+    let deploy_receipt = client.send_data(
+        alice,
+        factory_address,
+        factory::deploy_htlc_tx_payload(SECRET_HASH, htlc),
+        heth::Htlc::deploy_tx_gas_limit() + factory::deploy_htlc_tx_gas_overhead(),
+    );
+    assert_eq!(deploy_receipt.return_data[12..], htlc_address.0);
+
+    let htlc_address = H160(htlc_address.0);
+    assert!(!client.get_contract_code(htlc_address).is_empty());
+
+    let transaction_receipt = client.send_data(
+        alice,
+        htlc_address,
+        SECRET.to_vec(),
+        heth::Htlc::redeem_tx_gas_limit(),
+    );
+    let topic: H256 = REDEEMED_LOG_MSG.parse().unwrap();
+    assert!(transaction_receipt.logs[0].topics.contains(&topic));
+}
+
+#[test]
+fn deployment_by_someone_else_does_not_take_the_predicted_address() {
+    let _ = pretty_env_logger::try_init();
+
+    let (_, alice) =
+        new_account("63be4b0d638d44b5fee5b050ab0beeeae7b68cde3d829a3321f8009cdd76b992");
+    let (_, bob) = new_account("f8218ebf6e2626bd1415c18321496e0c5725f0e1d774c7c2eab69f7650ad6e82");
+
+    let mut client = EvmClient::new(Timestamp::now());
+    client.give_eth_to(alice, U256::from(1_000_000_000_000_000_000u64));
+    client.give_eth_to(bob, U256::from(1_000_000_000_000_000_000u64));
+
+    let factory_address = client
+        .deploy_htlc(
+            alice,
+            factory::CONTRACT.to_vec(),
+            U256::zero(),
+            factory::deploy_tx_gas_limit(),
+        )
+        .contract_address
+        .unwrap();
+
+    let htlc: Vec<u8> = heth::Htlc::new(
+        Expiry::Timestamp(u32::MAX),
+        Address(alice.into()),
+        Address(bob.into()),
+        SECRET_HASH,
+        HashFunction::Sha256,
+    )
+    .into();
+    let htlc_address = factory::htlc_address(
+        Address(factory_address.into()),
+        Address(alice.into()),
+        SECRET_HASH,
+        &htlc,
+    );
+    let gas_limit = heth::Htlc::deploy_tx_gas_limit() + factory::deploy_htlc_tx_gas_overhead();
+
+    let front_run_receipt = client.send_data(
+        bob,
+        factory_address,
+        factory::deploy_htlc_tx_payload(SECRET_HASH, htlc.clone()),
+        gas_limit,
+    );
+    assert_ne!(front_run_receipt.return_data[12..], htlc_address.0);
+
+    let deploy_receipt = client.send_data(
+        alice,
+        factory_address,
+        factory::deploy_htlc_tx_payload(SECRET_HASH, htlc),
+        gas_limit,
+    );
+    assert_eq!(deploy_receipt.return_data[12..], htlc_address.0);
+}
@@ -0,0 +1,289 @@
+#![warn(unused_extern_crates, missing_debug_implementations, rust_2018_idioms)]
+#![forbid(unsafe_code)]
+
+pub mod evm_client;
+pub mod htlc_harness;
+
+use crate::htlc_harness::{
+    erc20_harness, ether_harness, setup, token_quantity, Erc20HarnessParams, EtherHarnessParams,
+    Token, SECRET, SECRET_HASH,
+};
+use blockchain_contracts::ethereum::{
+    gas::{self, GasEstimator, Hardfork, TokenContract},
+    herc20, herc20_full_balance, herc20_relayer_fee, herc20_safe, herc20_transfer_from, heth,
+    Address, DeployedHtlcCode, HashFunction, HtlcCode, TokenQuantity,
+};
+use primitive_types::U256;
+
+#[test]
+fn heth_transactions_succeed_with_estimated_gas() {
+    let harness_params = EtherHarnessParams::default();
+    let (alice, bob, htlc_address, mut client) = ether_harness(harness_params.clone());
+    let htlc = heth::Htlc::from_runtime_code(&client.get_contract_code(htlc_address)).unwrap();
+    let estimator = GasEstimator::new(Hardfork::Istanbul);
+
+    let deploy_receipt = client.deploy_htlc(
+        alice,
+        heth::Htlc::new(
+            htlc.expiry(),
+            htlc.refund_identity(),
+            htlc.redeem_identity(),
+            htlc.secret_hash(),
+            htlc.hash_function(),
+        )
+        .into(),
+        U256::from(1_000),
+        estimator.heth_deploy(&htlc).unwrap(),
+    );
+    assert!(deploy_receipt.is_success());
+
+    let redeem_receipt = client.send_data(
+        alice,
+        htlc_address,
+        SECRET.to_vec(),
+        estimator.heth_redeem(&htlc).unwrap(),
+    );
+    assert!(redeem_receipt.is_success());
+    assert_eq!(client.eth_balance_of(bob), harness_params.htlc_wei_value);
+
+    let refund_htlc = deploy_receipt.contract_address.unwrap();
+    client.set_block_timestamp(harness_params.htlc_refund_timestamp.plus(1));
+    let refund_receipt = client.send_data(
+        alice,
+        refund_htlc,
+        vec![],
+        estimator.heth_refund(&htlc).unwrap(),
+    );
+    assert!(refund_receipt.is_success());
+    assert_eq!(client.eth_balance_of(refund_htlc), U256::zero());
+}
+
+#[test]
+fn herc20_transactions_succeed_with_estimated_gas() {
+    let harness_params = Erc20HarnessParams::default();
+    let (alice, bob, htlc_address, token_contract, token_amount, mut client) =
+        erc20_harness(harness_params);
+    let htlc = herc20::Htlc::from_runtime_code(&client.get_contract_code(htlc_address)).unwrap();
+    let token = TokenContract {
+        runtime_code: client.get_contract_code(token_contract),
+        balances_slot: 0,
+    };
+    let estimator = GasEstimator::new(Hardfork::Istanbul);
+
+    let deploy_receipt = client.deploy_htlc(
+        alice,
+        herc20::Htlc::new(
+            htlc.expiry(),
+            htlc.refund_identity(),
+            htlc.redeem_identity(),
+            htlc.secret_hash(),
+            htlc.hash_function(),
+            htlc.token_contract_address(),
+            htlc.token_quantity(),
+        )
+        .into(),
+        U256::zero(),
+        estimator.herc20_deploy(&htlc).unwrap(),
+    );
+    assert!(deploy_receipt.is_success());
+
+    let mut token_quantity = [0u8; 32];
+    token_amount.to_big_endian(&mut token_quantity);
+    let fund_receipt = client.send_data(
+        alice,
+        token_contract,
+        herc20::Htlc::transfer_erc20_tx_payload(
+            TokenQuantity(token_quantity),
+            Address(htlc_address.0),
+        ),
+        estimator.herc20_fund(&htlc, &token).unwrap(),
+    );
+    assert!(fund_receipt.is_success());
+    assert_eq!(
+        client.token_balance_of(token_contract, htlc_address),
+        token_amount
+    );
+
+    let redeem_receipt = client.send_data(
+        alice,
+        htlc_address,
+        SECRET.to_vec(),
+        estimator.herc20_redeem(&htlc, &token).unwrap(),
+    );
+    assert!(redeem_receipt.is_success());
+    assert_eq!(client.token_balance_of(token_contract, bob), token_amount);
+}
+
+#[test]
+fn estimates_follow_the_gas_schedule_of_the_hardfork() {
+    let (_, _, htlc_address, client) = ether_harness(EtherHarnessParams::default());
+    let htlc = heth::Htlc::from_runtime_code(&client.get_contract_code(htlc_address)).unwrap();
+
+    // Berlin made the first access to an account more expensive
+    assert!(
+        GasEstimator::new(Hardfork::London)
+            .heth_refund(&htlc)
+            .unwrap()
+            > GasEstimator::new(Hardfork::Istanbul)
+                .heth_refund(&htlc)
+                .unwrap()
+    );
+}
+
+#[track_caller]
+fn assert_covers(estimate: Result<u64, gas::Error>, gas_limit: u64) {
+    let estimate = estimate.unwrap();
+
+    assert!(
+        estimate <= gas_limit,
+        "estimate {} exceeds the gas limit {}",
+        estimate,
+        gas_limit
+    );
+}
+
+#[test]
+fn herc20_estimates_are_below_the_hard_coded_gas_limits() {
+    let mut setup = setup();
+    let token_contract = setup.deploy_token(Token::Standard);
+    let token = TokenContract {
+        runtime_code: setup.client.get_contract_code(token_contract),
+        balances_slot: 0,
+    };
+    let (expiry, refund_identity, redeem_identity, token_contract) = (
+        setup.expiry.into(),
+        Address(setup.alice.into()),
+        Address(setup.bob.into()),
+        Address(token_contract.into()),
+    );
+
+    let htlc = herc20::Htlc::new(
+        expiry,
+        refund_identity,
+        redeem_identity,
+        SECRET_HASH,
+        HashFunction::Sha256,
+        token_contract,
+        token_quantity(400),
+    );
+    let transfer_from_htlc = herc20_transfer_from::Htlc::new(
+        expiry,
+        refund_identity,
+        redeem_identity,
+        SECRET_HASH,
+        token_contract,
+        token_quantity(400),
+    );
+    let safe_htlc = herc20_safe::Htlc::new(
+        expiry,
+        refund_identity,
+        redeem_identity,
+        SECRET_HASH,
+        token_contract,
+        token_quantity(400),
+    );
+    let full_balance_htlc = herc20_full_balance::Htlc::new(
+        expiry,
+        refund_identity,
+        redeem_identity,
+        SECRET_HASH,
+        token_contract,
+    );
+    let relayer_fee_htlc = herc20_relayer_fee::Htlc::new(
+        expiry,
+        refund_identity,
+        redeem_identity,
+        SECRET_HASH,
+        token_contract,
+        token_quantity(400),
+        token_quantity(10),
+    )
+    .unwrap();
+
+    for hardfork in &[
+        Hardfork::Istanbul,
+        Hardfork::Berlin,
+        Hardfork::London,
+        Hardfork::Merge,
+        Hardfork::Shanghai,
+    ] {
+        let estimator = GasEstimator::new(*hardfork);
+
+        assert_covers(
+            estimator.herc20_deploy(&htlc),
+            herc20::Htlc::deploy_tx_gas_limit(),
+        );
+        assert_covers(
+            estimator.herc20_fund(&htlc, &token),
+            herc20::Htlc::fund_tx_gas_limit(),
+        );
+        assert_covers(
+            estimator.herc20_redeem(&htlc, &token),
+            herc20::Htlc::redeem_tx_gas_limit(),
+        );
+        assert_covers(
+            estimator.herc20_refund(&htlc, &token),
+            herc20::Htlc::refund_tx_gas_limit(),
+        );
+
+        assert_covers(
+            estimator.herc20_transfer_from_approve(&transfer_from_htlc, &token),
+            herc20_transfer_from::Htlc::approve_tx_gas_limit(),
+        );
+        assert_covers(
+            estimator.herc20_transfer_from_deploy(&transfer_from_htlc, &token),
+            herc20_transfer_from::Htlc::deploy_tx_gas_limit(),
+        );
+
+        assert_covers(
+            estimator.herc20_safe_deploy(&safe_htlc),
+            herc20_safe::Htlc::deploy_tx_gas_limit(),
+        );
+        assert_covers(
+            estimator.herc20_safe_fund(&safe_htlc, &token),
+            herc20_safe::Htlc::fund_tx_gas_limit(),
+        );
+        assert_covers(
+            estimator.herc20_safe_redeem(&safe_htlc, &token),
+            herc20_safe::Htlc::redeem_tx_gas_limit(),
+        );
+        assert_covers(
+            estimator.herc20_safe_refund(&safe_htlc, &token),
+            herc20_safe::Htlc::refund_tx_gas_limit(),
+        );
+
+        assert_covers(
+            estimator.herc20_full_balance_deploy(&full_balance_htlc),
+            herc20_full_balance::Htlc::deploy_tx_gas_limit(),
+        );
+        assert_covers(
+            estimator.herc20_full_balance_fund(&full_balance_htlc, &token, token_quantity(400)),
+            herc20_full_balance::Htlc::fund_tx_gas_limit(),
+        );
+        assert_covers(
+            estimator.herc20_full_balance_redeem(&full_balance_htlc, &token, token_quantity(400)),
+            herc20_full_balance::Htlc::redeem_tx_gas_limit(),
+        );
+        assert_covers(
+            estimator.herc20_full_balance_refund(&full_balance_htlc, &token, token_quantity(400)),
+            herc20_full_balance::Htlc::refund_tx_gas_limit(),
+        );
+
+        assert_covers(
+            estimator.herc20_relayer_fee_deploy(&relayer_fee_htlc),
+            herc20_relayer_fee::Htlc::deploy_tx_gas_limit(),
+        );
+        assert_covers(
+            estimator.herc20_relayer_fee_fund(&relayer_fee_htlc, &token),
+            herc20_relayer_fee::Htlc::fund_tx_gas_limit(),
+        );
+        assert_covers(
+            estimator.herc20_relayer_fee_redeem(&relayer_fee_htlc, &token),
+            herc20_relayer_fee::Htlc::redeem_tx_gas_limit(),
+        );
+        assert_covers(
+            estimator.herc20_relayer_fee_refund(&relayer_fee_htlc, &token),
+            herc20_relayer_fee::Htlc::refund_tx_gas_limit(),
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! A `LocalChain` on which the contract tests run without a node.
+//!
+//! Transactions are executed one at a time, each one in its own block mined
+//! at the current block timestamp, with the Istanbul gas schedule.
+
+use crate::htlc_harness::Timestamp;
+use blockchain_contracts::ethereum::{
+    contract_address,
+    gas::Hardfork,
+    local_chain::{LocalChain, Transaction},
+    Address,
+};
+use evm::{backend::Log, ExitReason};
+use primitive_types::{H160, U256};
+
+const ERC20_TOKEN_CONTRACT_CODE: &str = include_str!("erc20_token_contract.asm.hex");
+/// Returns nothing from `transfer` and reverts on failure, like USDT.
+const NO_RETURN_ERC20_TOKEN_CONTRACT_CODE: &str =
+    include_str!("no_return_erc20_token_contract.asm.hex");
+/// Returns `false` from `transfer` on failure instead of reverting.
+const FALSE_RETURN_ERC20_TOKEN_CONTRACT_CODE: &str =
+    include_str!("false_return_erc20_token_contract.asm.hex");
+/// Keeps a tenth of every transferred amount as a fee.
+const FEE_ON_TRANSFER_ERC20_TOKEN_CONTRACT_CODE: &str =
+    include_str!("fee_on_transfer_erc20_token_contract.asm.hex");
+
+#[derive(Debug)]
+pub struct EvmClient {
+    chain: LocalChain,
+}
+
+#[derive(Clone, Debug)]
+pub struct TransactionReceipt {
+    pub exit_reason: ExitReason,
+    pub return_data: Vec<u8>,
+    pub logs: Vec<Log>,
+    pub gas_used: u64,
+    pub contract_address: Option<H160>,
+}
+
+impl TransactionReceipt {
+    pub fn is_success(&self) -> bool {
+        self.exit_reason.is_succeed()
+    }
+
+    pub fn is_reverted(&self) -> bool {
+        self.exit_reason.is_revert()
+    }
+}
+
+impl EvmClient {
+    pub fn new(block_timestamp: Timestamp) -> Self {
+        let mut chain = LocalChain::new(Hardfork::Istanbul);
+        chain.set_block_timestamp(U256::from(u64::from(block_timestamp)));
+
+        EvmClient { chain }
+    }
+
+    /// Mines the next transaction in a block with the given timestamp, e.g.
+    /// to let an HTLC expire without waiting for it.
+    pub fn set_block_timestamp(&mut self, timestamp: Timestamp) {
+        self.chain
+            .set_block_timestamp(U256::from(u64::from(timestamp)));
+    }
+
+    /// Mines the next transaction in the block with the given number.
+    pub fn set_block_number(&mut self, block_number: u32) {
+        self.chain.set_block_number(U256::from(block_number));
+    }
+
+    pub fn give_eth_to(&mut self, to: H160, wei_amount: U256) {
+        self.chain.give_eth_to(to, wei_amount);
+    }
+
+    pub fn eth_balance_of(&self, address: H160) -> U256 {
+        self.chain.eth_balance_of(address)
+    }
+
+    pub fn nonce_of(&self, address: H160) -> u64 {
+        self.chain.nonce_of(address)
+    }
+
+    pub fn get_contract_code(&self, address: H160) -> Vec<u8> {
+        self.chain.code_of(address)
+    }
+
+    pub fn deploy_htlc(
+        &mut self,
+        from: H160,
+        data: Vec<u8>,
+        value: U256,
+        gas_limit: u64,
+    ) -> TransactionReceipt {
+        let address = H160(contract_address(Address(from.0), self.nonce_of(from)).0);
+
+        let mut receipt = self.transact(
+            Transaction::Create {
+                from,
+                value,
+                init_code: data,
+            },
+            gas_limit,
+        );
+        if receipt.is_success() {
+            receipt.contract_address = Some(address);
+        }
+
+        log::debug!("Transaction Receipt: {:?}", receipt);
+
+        receipt
+    }
+
+    pub fn send_data(
+        &mut self,
+        from: H160,
+        to: H160,
+        data: Vec<u8>,
+        gas_limit: u64,
+    ) -> TransactionReceipt {
+        let receipt = self.transact(Transaction::Call { from, to, data }, gas_limit);
+
+        log::debug!("Transaction Receipt: {:?}", receipt);
+
+        receipt
+    }
+
+    pub fn deploy_erc20_token_contract(&mut self, from: H160) -> H160 {
+        self.deploy_token_contract(from, ERC20_TOKEN_CONTRACT_CODE)
+    }
+
+    pub fn deploy_no_return_erc20_token_contract(&mut self, from: H160) -> H160 {
+        self.deploy_token_contract(from, NO_RETURN_ERC20_TOKEN_CONTRACT_CODE)
+    }
+
+    pub fn deploy_false_return_erc20_token_contract(&mut self, from: H160) -> H160 {
+        self.deploy_token_contract(from, FALSE_RETURN_ERC20_TOKEN_CONTRACT_CODE)
+    }
+
+    pub fn deploy_fee_on_transfer_erc20_token_contract(&mut self, from: H160) -> H160 {
+        self.deploy_token_contract(from, FEE_ON_TRANSFER_ERC20_TOKEN_CONTRACT_CODE)
+    }
+
+    fn deploy_token_contract(&mut self, from: H160, code: &str) -> H160 {
+        let receipt = self.deploy_htlc(
+            from,
+            hex::decode(code.trim()).unwrap(),
+            U256::zero(),
+            4_000_000,
+        );
+
+        receipt
+            .contract_address
+            .expect("ERC20 token contract deployment failed")
+    }
+
+    pub fn mint_tokens(&mut self, from: H160, contract: H160, amount: U256, to: H160) -> u64 {
+        let function_identifier = "40c10f19";
+        let address = format!("000000000000000000000000{}", hex::encode(to));
+        let amount = format!("{:0>64}", format!("{:x}", amount));
+
+        let payload = format!("{}{}{}", function_identifier, address, amount);
+
+        let receipt = self.send_data(
+            from,
+            contract,
+            hex::decode(payload).unwrap(),
+            500_000, // This is for test purposes only
+        );
+        assert!(receipt.is_success(), "minting tokens failed");
+
+        receipt.gas_used
+    }
+
+    pub fn token_balance_of(&self, contract: H160, address: H160) -> U256 {
+        let function_identifier = "70a08231";
+        let address_hex = format!("000000000000000000000000{}", hex::encode(address));
+
+        let payload = format!("{}{}", function_identifier, address_hex);
+
+        let outcome = self.chain.call(
+            &Transaction::Call {
+                from: address,
+                to: contract,
+                data: hex::decode(payload).unwrap(),
+            },
+            500_000,
+        );
+
+        U256::from_big_endian(&outcome.return_data)
+    }
+
+    fn transact(&mut self, transaction: Transaction, gas_limit: u64) -> TransactionReceipt {
+        let outcome = self.chain.transact(&transaction, gas_limit);
+        let next_block = self.chain.block_number() + U256::one();
+        self.chain.set_block_number(next_block);
+
+        TransactionReceipt {
+            exit_reason: outcome.exit_reason,
+            return_data: outcome.return_data,
+            logs: outcome.logs,
+            gas_used: outcome.gas_used,
+            contract_address: None,
+        }
+    }
+}
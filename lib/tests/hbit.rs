@@ -2,9 +2,8 @@
 #![forbid(unsafe_code)]
 
 pub mod bitcoin_helper;
-pub mod ethereum_helper;
+pub mod evm_client;
 pub mod htlc_harness;
-pub mod parity_client;
 
 use crate::bitcoin_helper::RpcError;
 use crate::{
@@ -15,13 +14,13 @@ use bitcoin_helper::new_tc_bitcoincore_client;
 use blockchain_contracts::bitcoin::{
     hbit::Htlc,
     witness::{PrimedInput, PrimedTransaction, UnlockParameters, Witness},
+    Identity,
 };
 use rust_bitcoin::Txid;
 use rust_bitcoin::{
     consensus::encode::serialize_hex,
-    hashes::{hash160, Hash},
     network::constants::Network,
-    secp256k1::{self, PublicKey, Secp256k1, SecretKey},
+    secp256k1::{self, SecretKey},
     Address, Amount, OutPoint, PrivateKey,
 };
 use spectral::prelude::*;
@@ -41,11 +40,9 @@ fn unlock_with_custom_size_secret(
 ) -> UnlockParameters {
     let placeholder_secret = [0u8; 32];
     // First, unlock the HTLC with a placeholder secret
-    let parameters = htlc.unlock_with_secret(
-        &*crate::ethereum_helper::SECP,
-        secret_key,
-        placeholder_secret,
-    );
+    let parameters = htlc
+        .unlock_with_secret(&*crate::htlc_harness::SECP, secret_key, placeholder_secret)
+        .unwrap();
 
     let UnlockParameters {
         mut witness,
@@ -54,12 +51,11 @@ fn unlock_with_custom_size_secret(
         prev_script,
     } = parameters;
 
-    // Secret for the secret in the witness stack (it is the only data) and replace
-    // it with our custom size secret
+    // Search for the secret in the witness stack (it is the only preimage) and
+    // replace it with our custom size secret
     for w in &mut witness {
-        if let Witness::Data(ref mut placeholder_secret) = w {
-            placeholder_secret.clear();
-            placeholder_secret.extend_from_slice(&custom_size_secret.0);
+        if let Witness::Preimage(_) = w {
+            *w = Witness::Data(custom_size_secret.0.clone());
         }
     }
 
@@ -72,13 +68,6 @@ fn unlock_with_custom_size_secret(
     }
 }
 
-fn pubkey_hash<C: secp256k1::Signing>(
-    secp: &Secp256k1<C>,
-    secret_key: &SecretKey,
-) -> hash160::Hash {
-    hash160::Hash::hash(&PublicKey::from_secret_key(&secp, &secret_key).serialize())
-}
-
 fn fund_htlc(
     client: &Client,
     secret_hash: [u8; 32],
@@ -100,8 +89,8 @@ fn fund_htlc(
         PrivateKey::from_str("cNZUJxVXghSri4dUaNW8ES3KiFyDoWVffLYDz7KMcHmKhLdFyZPx")
             .unwrap()
             .key;
-    let redeem_pubkey_hash = pubkey_hash(&secp, &redeem_secret_key);
-    let refund_pubkey_hash = pubkey_hash(&secp, &refund_secret_key);
+    let redeem_identity = Identity::from_secret_key(&secp, &redeem_secret_key);
+    let refund_identity = Identity::from_secret_key(&secp, &refund_secret_key);
 
     let current_time = client.get_blockchain_info().unwrap().mediantime;
     let current_time = u32::try_from(current_time).unwrap();
@@ -110,8 +99,8 @@ fn fund_htlc(
 
     let htlc = Htlc::new(
         refund_timestamp.into(),
-        refund_pubkey_hash,
-        redeem_pubkey_hash,
+        refund_identity,
+        redeem_identity,
         secret_hash,
     );
 
@@ -153,11 +142,12 @@ fn redeem_htlc_with_secret() {
         inputs: vec![PrimedInput::new(
             vout,
             input_amount,
-            htlc.unlock_with_secret(&crate::ethereum_helper::SECP, secret_key, *SECRET),
+            htlc.unlock_with_secret(&crate::htlc_harness::SECP, secret_key, *SECRET)
+                .unwrap(),
         )],
         output_address: alice_addr.clone(),
     }
-    .sign_with_fee(&crate::ethereum_helper::SECP, fee);
+    .sign_with_fee(&crate::htlc_harness::SECP, fee);
 
     let redeem_tx_hex = serialize_hex(&redeem_tx);
 
@@ -192,11 +182,12 @@ fn refund_htlc() {
         inputs: vec![PrimedInput::new(
             vout,
             input_amount,
-            htlc.unlock_after_timeout(&crate::ethereum_helper::SECP, secret_key),
+            htlc.unlock_after_timeout(&crate::htlc_harness::SECP, secret_key)
+                .unwrap(),
         )],
         output_address: alice_addr.clone(),
     }
-    .sign_with_fee(&crate::ethereum_helper::SECP, fee);
+    .sign_with_fee(&crate::htlc_harness::SECP, fee);
 
     let refund_tx_hex = serialize_hex(&refund_tx);
 
@@ -262,7 +253,7 @@ fn redeem_htlc_with_long_secret() {
         )],
         output_address: alice_addr,
     }
-    .sign_with_fee(&crate::ethereum_helper::SECP, fee);
+    .sign_with_fee(&crate::htlc_harness::SECP, fee);
 
     let redeem_tx_hex = serialize_hex(&redeem_tx);
 
@@ -309,7 +300,7 @@ fn redeem_htlc_with_short_secret() {
         )],
         output_address: alice_addr,
     }
-    .sign_with_fee(&crate::ethereum_helper::SECP, fee);
+    .sign_with_fee(&crate::htlc_harness::SECP, fee);
 
     let redeem_tx_hex = serialize_hex(&redeem_tx);
 
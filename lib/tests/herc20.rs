@@ -1,36 +1,26 @@
 #![warn(unused_extern_crates, missing_debug_implementations, rust_2018_idioms)]
 #![forbid(unsafe_code)]
 
-pub mod ethereum_helper;
+pub mod evm_client;
 pub mod htlc_harness;
-pub mod parity_client;
 
-use crate::{
-    ethereum_helper::transaction::UnsignedTransaction,
-    htlc_harness::{
-        erc20_harness, sleep_until, CustomSizeSecret, Erc20HarnessParams, Timestamp, SECRET,
-    },
-};
-
-use crate::parity_client::ParityClient;
+use crate::htlc_harness::{erc20_harness, CustomSizeSecret, Erc20HarnessParams, Timestamp, SECRET};
 use blockchain_contracts::ethereum::herc20::Htlc;
 use blockchain_contracts::ethereum::Address;
+use blockchain_contracts::ethereum::HashFunction;
 use blockchain_contracts::ethereum::TokenQuantity;
 use blockchain_contracts::ethereum::INVALID_SECRET;
 use blockchain_contracts::ethereum::REDEEMED_LOG_MSG;
 use blockchain_contracts::ethereum::REFUNDED_LOG_MSG;
 use blockchain_contracts::ethereum::TOO_EARLY;
-use serde_json::json;
+use evm_client::{EvmClient, TransactionReceipt};
+use primitive_types::{H160, H256, U256};
 use spectral::prelude::*;
-use testcontainers::clients::Cli;
-use web3::error::Error::Rpc;
-use web3::types::{Bytes, TransactionReceipt, H256, U256};
 
 #[test]
 fn given_erc20_token_should_deploy_erc20_htlc_and_fund_htlc() {
-    let docker = Cli::default();
-    let (alice, bob, htlc_address, token_contract, token_amount, client, _handle, _container) =
-        erc20_harness(&docker, Erc20HarnessParams::default());
+    let (alice, bob, htlc_address, token_contract, token_amount, mut client) =
+        erc20_harness(Erc20HarnessParams::default());
 
     assert_eq!(
         client.token_balance_of(token_contract, htlc_address),
@@ -43,22 +33,14 @@ fn given_erc20_token_should_deploy_erc20_htlc_and_fund_htlc() {
     assert_eq!(client.token_balance_of(token_contract, bob), U256::from(0));
 
     // Fund erc20 htlc
-    let tx = client.sign_and_send(|nonce, gas_price| UnsignedTransaction {
-        nonce,
-        gas_price,
-        gas_limit: U256::from(100_000),
-        to: Some(token_contract),
-        value: U256::from(0),
-        data: Some(
-            Htlc::transfer_erc20_tx_payload(
-                TokenQuantity(token_amount.into()),
-                Address(htlc_address.into()),
-            )
-            .into(),
-        ),
-    });
-
-    let transaction_receipt = client.receipt(tx);
+    let transaction_receipt = fund_htlc(
+        &mut client,
+        alice,
+        token_contract,
+        token_amount,
+        htlc_address,
+    );
+
     log::debug!("used gas ERC20 fund {:?}", transaction_receipt.gas_used);
 
     // Check htlc funding
@@ -74,9 +56,10 @@ fn given_erc20_token_should_deploy_erc20_htlc_and_fund_htlc() {
 
     // Send correct secret to contract
     let transaction_receipt = client.send_data(
+        alice,
         htlc_address,
-        Some(Bytes(SECRET.to_vec())),
-        Htlc::redeem_tx_gas_limit().into(),
+        SECRET.to_vec(),
+        Htlc::redeem_tx_gas_limit(),
     );
     log::debug!("used gas ERC20 redeem {:?}", transaction_receipt.gas_used);
 
@@ -96,25 +79,17 @@ fn given_erc20_token_should_deploy_erc20_htlc_and_fund_htlc() {
 
 #[test]
 fn given_funded_erc20_htlc_when_redeemed_with_secret_then_tokens_are_transferred() {
-    let docker = Cli::default();
-    let (alice, bob, htlc_address, token_contract, token_amount, client, _handle, _container) =
-        erc20_harness(&docker, Erc20HarnessParams::default());
+    let (alice, bob, htlc_address, token_contract, token_amount, mut client) =
+        erc20_harness(Erc20HarnessParams::default());
 
     // fund erc20 htlc
-    client.sign_and_send(|nonce, gas_price| UnsignedTransaction {
-        nonce,
-        gas_price,
-        gas_limit: U256::from(100_000),
-        to: Some(token_contract),
-        value: U256::from(0),
-        data: Some(
-            Htlc::transfer_erc20_tx_payload(
-                TokenQuantity(token_amount.into()),
-                Address(htlc_address.into()),
-            )
-            .into(),
-        ),
-    });
+    fund_htlc(
+        &mut client,
+        alice,
+        token_contract,
+        token_amount,
+        htlc_address,
+    );
 
     assert_eq!(
         client.token_balance_of(token_contract, htlc_address),
@@ -128,9 +103,10 @@ fn given_funded_erc20_htlc_when_redeemed_with_secret_then_tokens_are_transferred
 
     // Send correct secret to contract
     let transaction_receipt = client.send_data(
+        alice,
         htlc_address,
-        Some(Bytes(SECRET.to_vec())),
-        U256::from(Htlc::redeem_tx_gas_limit()),
+        SECRET.to_vec(),
+        Htlc::redeem_tx_gas_limit(),
     );
     log::debug!("used gas ERC20 redeemed {:?}", transaction_receipt.gas_used);
 
@@ -150,26 +126,18 @@ fn given_funded_erc20_htlc_when_redeemed_with_secret_then_tokens_are_transferred
 
 #[test]
 fn given_deployed_erc20_htlc_when_refunded_after_expiry_time_then_tokens_are_refunded() {
-    let docker = Cli::default();
     let harness_params = Erc20HarnessParams::default();
-    let (alice, bob, htlc_address, token_contract, token_amount, client, _handle, _container) =
-        erc20_harness(&docker, harness_params.clone());
+    let (alice, bob, htlc_address, token_contract, token_amount, mut client) =
+        erc20_harness(harness_params.clone());
 
     // Fund erc20 htlc
-    client.sign_and_send(|nonce, gas_price| UnsignedTransaction {
-        nonce,
-        gas_price,
-        gas_limit: U256::from(100_000),
-        to: Some(token_contract),
-        value: U256::from(0),
-        data: Some(
-            Htlc::transfer_erc20_tx_payload(
-                TokenQuantity(token_amount.into()),
-                Address(htlc_address.into()),
-            )
-            .into(),
-        ),
-    });
+    fund_htlc(
+        &mut client,
+        alice,
+        token_contract,
+        token_amount,
+        htlc_address,
+    );
 
     assert_eq!(
         client.token_balance_of(token_contract, htlc_address),
@@ -182,9 +150,9 @@ fn given_deployed_erc20_htlc_when_refunded_after_expiry_time_then_tokens_are_ref
     );
 
     // Wait for the contract to expire
-    sleep_until(harness_params.htlc_refund_timestamp);
+    client.set_block_timestamp(harness_params.htlc_refund_timestamp.plus(1));
     let transaction_receipt =
-        client.send_data(htlc_address, None, Htlc::refund_tx_gas_limit().into());
+        client.send_data(alice, htlc_address, vec![], Htlc::refund_tx_gas_limit());
     log::debug!("used gas ERC20 refund {:?}", transaction_receipt.gas_used);
 
     assert_eq!(
@@ -198,33 +166,94 @@ fn given_deployed_erc20_htlc_when_refunded_after_expiry_time_then_tokens_are_ref
     assert_eq!(client.token_balance_of(token_contract, bob), U256::from(0));
 }
 
+#[test]
+fn given_keccak256_hashlock_when_redeemed_with_secret_then_tokens_are_transferred() {
+    let (alice, bob, htlc_address, token_contract, token_amount, mut client) =
+        erc20_harness(Erc20HarnessParams::default().with_hash_function(HashFunction::Keccak256));
+    fund_htlc(
+        &mut client,
+        alice,
+        token_contract,
+        token_amount,
+        htlc_address,
+    );
+
+    let transaction_receipt = client.send_data(
+        alice,
+        htlc_address,
+        b"I'm a h4x0r".to_vec(),
+        500_000, // This is for test purposes only
+    );
+    assert_return_data(&transaction_receipt, INVALID_SECRET);
+
+    let transaction_receipt = client.send_data(
+        alice,
+        htlc_address,
+        SECRET.to_vec(),
+        Htlc::redeem_tx_gas_limit(),
+    );
+    log::debug!(
+        "used gas ERC20 keccak256 redeem {:?}",
+        transaction_receipt.gas_used
+    );
+
+    assert!(transaction_receipt.is_success());
+    assert_eq!(
+        client.token_balance_of(token_contract, bob),
+        U256::from(400)
+    );
+}
+
+#[test]
+fn given_block_height_expiry_when_refunded_after_expiry_block_then_tokens_are_refunded() {
+    let harness_params = Erc20HarnessParams {
+        htlc_refund_block_height: Some(100),
+        ..Default::default()
+    };
+    let (alice, _, htlc_address, token_contract, token_amount, mut client) =
+        erc20_harness(harness_params.clone());
+    fund_htlc(
+        &mut client,
+        alice,
+        token_contract,
+        token_amount,
+        htlc_address,
+    );
+
+    // The block timestamp has no bearing on the expiry
+    client.set_block_timestamp(harness_params.htlc_refund_timestamp.plus(1));
+    let transaction_receipt =
+        client.send_data(alice, htlc_address, vec![], Htlc::refund_tx_gas_limit());
+    assert_return_data(&transaction_receipt, TOO_EARLY);
+
+    client.set_block_number(101);
+    let transaction_receipt =
+        client.send_data(alice, htlc_address, vec![], Htlc::refund_tx_gas_limit());
+    log::debug!("used gas ERC20 refund {:?}", transaction_receipt.gas_used);
+
+    assert!(transaction_receipt.is_success());
+    assert_eq!(
+        client.token_balance_of(token_contract, alice),
+        U256::from(1000)
+    );
+}
+
 #[test]
 fn given_deployed_erc20_htlc_when_expiry_time_not_yet_reached_should_revert_tx_with_error() {
-    let docker = Cli::default();
-    let (alice, bob, htlc_address, token_contract, token_amount, client, _handle, _container) =
-        erc20_harness(
-            &docker,
-            Erc20HarnessParams {
-                htlc_refund_timestamp: Timestamp::now().plus(1_000_000),
-                ..Default::default()
-            },
-        );
+    let (alice, bob, htlc_address, token_contract, token_amount, mut client) =
+        erc20_harness(Erc20HarnessParams {
+            htlc_refund_timestamp: Timestamp::now().plus(1_000_000),
+            ..Default::default()
+        });
 
     // fund erc20 htlc
-    client.sign_and_send(|nonce, gas_price| UnsignedTransaction {
-        nonce,
-        gas_price,
-        gas_limit: U256::from(100_000),
-        to: Some(token_contract),
-        value: U256::from(0),
-        data: Some(
-            Htlc::transfer_erc20_tx_payload(
-                TokenQuantity(token_amount.into()),
-                Address(htlc_address.into()),
-            )
-            .into(),
-        ),
-    });
+    fund_htlc(
+        &mut client,
+        alice,
+        token_contract,
+        token_amount,
+        htlc_address,
+    );
 
     assert_eq!(
         client.token_balance_of(token_contract, htlc_address),
@@ -238,7 +267,7 @@ fn given_deployed_erc20_htlc_when_expiry_time_not_yet_reached_should_revert_tx_w
 
     // Don't wait for the timeout and don't send a secret
     let transaction_receipt =
-        client.send_data(htlc_address, None, Htlc::refund_tx_gas_limit().into());
+        client.send_data(alice, htlc_address, vec![], Htlc::refund_tx_gas_limit());
     log::debug!(
         "used gas ERC20 refund too early {:?}",
         transaction_receipt.gas_used
@@ -254,36 +283,25 @@ fn given_deployed_erc20_htlc_when_expiry_time_not_yet_reached_should_revert_tx_w
         U256::from(600)
     );
 
-    assert_return_data(&client, transaction_receipt, TOO_EARLY);
+    assert_return_data(&transaction_receipt, TOO_EARLY);
 }
 
 #[test]
 fn given_not_enough_tokens_when_redeemed_token_balances_dont_change() {
-    let docker = Cli::default();
-    let (alice, bob, htlc_address, token_contract, token_amount, client, _handle, _container) =
-        erc20_harness(
-            &docker,
-            Erc20HarnessParams {
-                alice_initial_tokens: U256::from(200),
-                ..Default::default()
-            },
-        );
+    let (alice, bob, htlc_address, token_contract, token_amount, mut client) =
+        erc20_harness(Erc20HarnessParams {
+            alice_initial_tokens: U256::from(200),
+            ..Default::default()
+        });
 
     // fund erc20 htlc
-    client.sign_and_send(|nonce, gas_price| UnsignedTransaction {
-        nonce,
-        gas_price,
-        gas_limit: U256::from(100_000),
-        to: Some(token_contract),
-        value: U256::from(0),
-        data: Some(
-            Htlc::transfer_erc20_tx_payload(
-                TokenQuantity(token_amount.into()),
-                Address(htlc_address.into()),
-            )
-            .into(),
-        ),
-    });
+    fund_htlc(
+        &mut client,
+        alice,
+        token_contract,
+        token_amount,
+        htlc_address,
+    );
 
     assert_eq!(
         client.token_balance_of(token_contract, htlc_address),
@@ -297,9 +315,10 @@ fn given_not_enough_tokens_when_redeemed_token_balances_dont_change() {
 
     // Send correct secret to contract
     let transaction_receipt = client.send_data(
+        alice,
         htlc_address,
-        Some(Bytes(SECRET.to_vec())),
-        Htlc::redeem_tx_gas_limit().into(),
+        SECRET.to_vec(),
+        Htlc::redeem_tx_gas_limit(),
     );
     log::debug!(
         "used gas ERC20 redeemed not enough token {:?}",
@@ -315,42 +334,35 @@ fn given_not_enough_tokens_when_redeemed_token_balances_dont_change() {
         U256::from(200)
     );
     assert_eq!(client.token_balance_of(token_contract, bob), U256::from(0));
-    assert_eq!(client.get_contract_code(htlc_address), Bytes::default());
+    assert_eq!(client.get_contract_code(htlc_address), Vec::<u8>::new());
 }
 
 #[test]
 fn given_htlc_and_redeem_should_emit_redeem_log_msg_with_secret() {
-    let docker = Cli::default();
-    let (_alice, _bob, htlc_address, token_contract, token_amount, client, _handle, _container) =
-        erc20_harness(&docker, Erc20HarnessParams::default());
+    let (alice, _bob, htlc_address, token_contract, token_amount, mut client) =
+        erc20_harness(Erc20HarnessParams::default());
 
     // Fund erc20 htlc
-    client.sign_and_send(|nonce, gas_price| UnsignedTransaction {
-        nonce,
-        gas_price,
-        gas_limit: U256::from(100_000),
-        to: Some(token_contract),
-        value: U256::from(0),
-        data: Some(
-            Htlc::transfer_erc20_tx_payload(
-                TokenQuantity(token_amount.into()),
-                Address(htlc_address.into()),
-            )
-            .into(),
-        ),
-    });
+    fund_htlc(
+        &mut client,
+        alice,
+        token_contract,
+        token_amount,
+        htlc_address,
+    );
 
     // Send correct secret to contract
     let transaction_receipt = client.send_data(
+        alice,
         htlc_address,
-        Some(Bytes(SECRET.to_vec())),
-        500_000.into(), // This is for test purposes only
+        SECRET.to_vec(),
+        500_000, // This is for test purposes only
     );
     log::debug!("used gas ERC20 redeem {:?}", transaction_receipt.gas_used);
 
     // Should contain 2 logs: 1 for token transfer 1 for redeeming the htlc
     assert_that(&transaction_receipt.logs.len()).is_equal_to(2);
-    assert_that(&transaction_receipt.logs[0].data).is_equal_to(Bytes(SECRET.to_vec()));
+    assert_that(&transaction_receipt.logs[0].data).is_equal_to(SECRET.to_vec());
 
     let redeem_topic: H256 = REDEEMED_LOG_MSG.parse().unwrap();
     let refund_topic: H256 = REFUNDED_LOG_MSG.parse().unwrap();
@@ -367,34 +379,27 @@ fn given_htlc_and_redeem_should_emit_redeem_log_msg_with_secret() {
 
 #[test]
 fn given_htlc_and_refund_should_emit_refund_log_msg() {
-    let docker = Cli::default();
     let harness_params = Erc20HarnessParams::default();
-    let (_alice, _bob, htlc_address, token_contract, token_amount, client, _handle, _container) =
-        erc20_harness(&docker, harness_params.clone());
+    let (alice, _bob, htlc_address, token_contract, token_amount, mut client) =
+        erc20_harness(harness_params.clone());
 
     // Fund erc20 htlc
-    client.sign_and_send(|nonce, gas_price| UnsignedTransaction {
-        nonce,
-        gas_price,
-        gas_limit: U256::from(100_000),
-        to: Some(token_contract),
-        value: U256::from(0),
-        data: Some(
-            Htlc::transfer_erc20_tx_payload(
-                TokenQuantity(token_amount.into()),
-                Address(htlc_address.into()),
-            )
-            .into(),
-        ),
-    });
+    fund_htlc(
+        &mut client,
+        alice,
+        token_contract,
+        token_amount,
+        htlc_address,
+    );
 
     // Wait for the contract to expire
-    sleep_until(harness_params.htlc_refund_timestamp);
+    client.set_block_timestamp(harness_params.htlc_refund_timestamp.plus(1));
     // Send correct secret to contract
     let transaction_receipt = client.send_data(
+        alice,
         htlc_address,
-        None,
-        500_000.into(), // This is for test purposes only
+        vec![],
+        500_000, // This is for test purposes only
     );
 
     log::debug!("used gas ERC20 refund {:?}", transaction_receipt.gas_used);
@@ -414,38 +419,27 @@ fn given_htlc_and_refund_should_emit_refund_log_msg() {
     assert_that(&topics).has_length(4);
     assert_that(&topics).does_not_contain(redeem_topic);
     assert_that(&topics).contains(refund_topic);
-    assert_that(&transaction_receipt.logs[0].data).is_equal_to(Bytes(vec![]));
+    assert_that(&transaction_receipt.logs[0].data).is_equal_to(vec![]);
 }
 
 #[test]
 fn given_funded_erc20_htlc_when_redeemed_with_short_secret_should_revert_with_error() {
-    let docker = Cli::default();
     let secret = CustomSizeSecret(vec![
         1u8, 2u8, 3u8, 4u8, 6u8, 6u8, 7u8, 9u8, 10u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
         0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
     ]);
 
-    let (alice, bob, htlc_address, token_contract, token_amount, client, _handle, _container) =
-        erc20_harness(
-            &docker,
-            Erc20HarnessParams::default().with_secret_hash(secret.hash()),
-        );
+    let (alice, bob, htlc_address, token_contract, token_amount, mut client) =
+        erc20_harness(Erc20HarnessParams::default().with_secret_hash(secret.hash()));
 
     // Fund erc20 htlc
-    client.sign_and_send(|nonce, gas_price| UnsignedTransaction {
-        nonce,
-        gas_price,
-        gas_limit: U256::from(100_000),
-        to: Some(token_contract),
-        value: U256::from(0),
-        data: Some(
-            Htlc::transfer_erc20_tx_payload(
-                TokenQuantity(token_amount.into()),
-                Address(htlc_address.into()),
-            )
-            .into(),
-        ),
-    });
+    fund_htlc(
+        &mut client,
+        alice,
+        token_contract,
+        token_amount,
+        htlc_address,
+    );
 
     assert_eq!(
         client.token_balance_of(token_contract, htlc_address),
@@ -459,9 +453,10 @@ fn given_funded_erc20_htlc_when_redeemed_with_short_secret_should_revert_with_er
 
     // Send short secret to contract
     let transaction_receipt = client.send_data(
+        alice,
         htlc_address,
-        Some(Bytes(vec![1u8, 2u8, 3u8, 4u8, 6u8, 6u8, 7u8, 9u8, 10u8])),
-        Htlc::redeem_tx_gas_limit().into(),
+        vec![1u8, 2u8, 3u8, 4u8, 6u8, 6u8, 7u8, 9u8, 10u8],
+        Htlc::redeem_tx_gas_limit(),
     );
 
     log::debug!(
@@ -479,39 +474,28 @@ fn given_funded_erc20_htlc_when_redeemed_with_short_secret_should_revert_with_er
     );
     assert_eq!(client.token_balance_of(token_contract, bob), U256::from(0));
 
-    assert_return_data(&client, transaction_receipt, INVALID_SECRET);
+    assert_return_data(&transaction_receipt, INVALID_SECRET);
 }
 
 #[test]
 fn given_correct_zero_secret_htlc_should_redeem() {
-    let docker = Cli::default();
     let secret_vec = vec![
         0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
         0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
     ];
     let secret = CustomSizeSecret(secret_vec.clone());
 
-    let (alice, bob, htlc_address, token_contract, token_amount, client, _handle, _container) =
-        erc20_harness(
-            &docker,
-            Erc20HarnessParams::default().with_secret_hash(secret.hash()),
-        );
+    let (alice, bob, htlc_address, token_contract, token_amount, mut client) =
+        erc20_harness(Erc20HarnessParams::default().with_secret_hash(secret.hash()));
 
     // Fund erc20 htlc
-    client.sign_and_send(|nonce, gas_price| UnsignedTransaction {
-        nonce,
-        gas_price,
-        gas_limit: U256::from(100_000),
-        to: Some(token_contract),
-        value: U256::from(0),
-        data: Some(
-            Htlc::transfer_erc20_tx_payload(
-                TokenQuantity(token_amount.into()),
-                Address(htlc_address.into()),
-            )
-            .into(),
-        ),
-    });
+    fund_htlc(
+        &mut client,
+        alice,
+        token_contract,
+        token_amount,
+        htlc_address,
+    );
 
     assert_eq!(
         client.token_balance_of(token_contract, htlc_address),
@@ -524,11 +508,8 @@ fn given_correct_zero_secret_htlc_should_redeem() {
     assert_eq!(client.token_balance_of(token_contract, bob), U256::from(0));
 
     // Send short secret to contract
-    let transaction_receipt = client.send_data(
-        htlc_address,
-        Some(Bytes(secret_vec)),
-        Htlc::redeem_tx_gas_limit().into(),
-    );
+    let transaction_receipt =
+        client.send_data(alice, htlc_address, secret_vec, Htlc::redeem_tx_gas_limit());
 
     log::debug!("used gas ERC20 redeem {:?}", transaction_receipt.gas_used);
 
@@ -548,33 +529,22 @@ fn given_correct_zero_secret_htlc_should_redeem() {
 
 #[test]
 fn given_short_zero_secret_htlc_should_revert_tx_with_error() {
-    let docker = Cli::default();
     let secret = CustomSizeSecret(vec![
         0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
         0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
     ]);
 
-    let (alice, bob, htlc_address, token_contract, token_amount, client, _handle, _container) =
-        erc20_harness(
-            &docker,
-            Erc20HarnessParams::default().with_secret_hash(secret.hash()),
-        );
+    let (alice, bob, htlc_address, token_contract, token_amount, mut client) =
+        erc20_harness(Erc20HarnessParams::default().with_secret_hash(secret.hash()));
 
     // Fund erc20 htlc
-    client.sign_and_send(|nonce, gas_price| UnsignedTransaction {
-        nonce,
-        gas_price,
-        gas_limit: U256::from(100_000),
-        to: Some(token_contract),
-        value: U256::from(0),
-        data: Some(
-            Htlc::transfer_erc20_tx_payload(
-                TokenQuantity(token_amount.into()),
-                Address(htlc_address.into()),
-            )
-            .into(),
-        ),
-    });
+    fund_htlc(
+        &mut client,
+        alice,
+        token_contract,
+        token_amount,
+        htlc_address,
+    );
 
     assert_eq!(
         client.token_balance_of(token_contract, htlc_address),
@@ -588,12 +558,13 @@ fn given_short_zero_secret_htlc_should_revert_tx_with_error() {
 
     // Send short secret to contract
     let transaction_receipt = client.send_data(
+        alice,
         htlc_address,
-        Some(Bytes(vec![
+        vec![
             0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
             0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
-        ])),
-        Htlc::redeem_tx_gas_limit().into(),
+        ],
+        Htlc::redeem_tx_gas_limit(),
     );
 
     log::debug!(
@@ -611,35 +582,27 @@ fn given_short_zero_secret_htlc_should_revert_tx_with_error() {
     );
     assert_eq!(client.token_balance_of(token_contract, bob), U256::from(0));
 
-    assert_return_data(&client, transaction_receipt, INVALID_SECRET);
+    assert_return_data(&transaction_receipt, INVALID_SECRET);
 }
 
 #[test]
 fn given_invalid_secret_htlc_should_revert_tx_with_error() {
-    let docker = Cli::default();
     let secret_vec = vec![
         0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
         0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
     ];
 
-    let (alice, bob, htlc_address, token_contract, token_amount, client, _handle, _container) =
-        erc20_harness(&docker, Erc20HarnessParams::default());
+    let (alice, bob, htlc_address, token_contract, token_amount, mut client) =
+        erc20_harness(Erc20HarnessParams::default());
 
     // Fund erc20 htlc
-    client.sign_and_send(|nonce, gas_price| UnsignedTransaction {
-        nonce,
-        gas_price,
-        gas_limit: U256::from(100_000),
-        to: Some(token_contract),
-        value: U256::from(0),
-        data: Some(
-            Htlc::transfer_erc20_tx_payload(
-                TokenQuantity(token_amount.into()),
-                Address(htlc_address.into()),
-            )
-            .into(),
-        ),
-    });
+    fund_htlc(
+        &mut client,
+        alice,
+        token_contract,
+        token_amount,
+        htlc_address,
+    );
 
     assert_eq!(
         client.token_balance_of(token_contract, htlc_address),
@@ -652,9 +615,10 @@ fn given_invalid_secret_htlc_should_revert_tx_with_error() {
     assert_eq!(client.token_balance_of(token_contract, bob), U256::from(0));
 
     let transaction_receipt = client.send_data(
+        alice,
         htlc_address,
-        Some(Bytes(secret_vec)),
-        500_000.into(), // This is for test purposes only
+        secret_vec,
+        500_000, // This is for test purposes only
     );
     log::debug!(
         "used gas ERC20 invalid secret {:?}",
@@ -668,26 +632,32 @@ fn given_invalid_secret_htlc_should_revert_tx_with_error() {
     assert_eq!(client.token_balance_of(token_contract, bob), U256::from(0));
     assert_eq!(client.token_balance_of(token_contract, bob), U256::from(0));
 
-    assert_return_data(&client, transaction_receipt, INVALID_SECRET);
+    assert_return_data(&transaction_receipt, INVALID_SECRET);
 }
 
-fn assert_return_data(
-    client: &ParityClient,
-    transaction_receipt: TransactionReceipt,
-    error_code: &str,
-) {
-    let result = client.get_return_data(transaction_receipt);
-    let return_data = result.err().unwrap();
-    let json = json!(format!("{}0x{}", "Reverted ", error_code));
-    match return_data {
-        Rpc(e) => {
-            asserting(&"contains VM message")
-                .that(&e.message)
-                .contains("VM execution error.");
-            asserting(&"contains revert reason")
-                .that(&e.data.unwrap())
-                .is_equal_to(json);
-        }
-        _ => assert_that(&true).is_false(),
-    };
+fn fund_htlc(
+    client: &mut EvmClient,
+    alice: H160,
+    token_contract: H160,
+    token_amount: U256,
+    htlc_address: H160,
+) -> TransactionReceipt {
+    let mut token_quantity = [0u8; 32];
+    token_amount.to_big_endian(&mut token_quantity);
+
+    client.send_data(
+        alice,
+        token_contract,
+        Htlc::transfer_erc20_tx_payload(TokenQuantity(token_quantity), Address(htlc_address.0)),
+        100_000,
+    )
+}
+
+fn assert_return_data(transaction_receipt: &TransactionReceipt, error_code: &str) {
+    asserting("transaction is reverted")
+        .that(&transaction_receipt.is_reverted())
+        .is_true();
+    asserting("contains revert reason")
+        .that(&hex::encode(&transaction_receipt.return_data))
+        .is_equal_to(error_code.to_string());
 }
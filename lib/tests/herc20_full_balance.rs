@@ -0,0 +1,137 @@
+#![warn(unused_extern_crates, missing_debug_implementations, rust_2018_idioms)]
+#![forbid(unsafe_code)]
+
+pub mod evm_client;
+pub mod htlc_harness;
+
+use crate::{
+    evm_client::TransactionReceipt,
+    htlc_harness::{setup, token_quantity, Setup, Token, SECRET_HASH},
+};
+use blockchain_contracts::ethereum::{herc20_full_balance::Htlc, Address, TokenQuantity};
+use primitive_types::{H160, U256};
+
+#[derive(Debug)]
+struct FullBalanceHtlc {
+    setup: Setup,
+    token_contract: H160,
+    htlc_address: H160,
+}
+
+fn deploy(token: Token) -> FullBalanceHtlc {
+    let mut setup = setup();
+    let token_contract = setup.deploy_token(token);
+
+    let htlc = Htlc::new(
+        setup.expiry.into(),
+        Address(setup.alice.into()),
+        Address(setup.bob.into()),
+        SECRET_HASH,
+        Address(token_contract.into()),
+    );
+    let htlc_address = setup.deploy_htlc(htlc.into(), U256::zero(), Htlc::deploy_tx_gas_limit());
+
+    FullBalanceHtlc {
+        setup,
+        token_contract,
+        htlc_address,
+    }
+}
+
+impl FullBalanceHtlc {
+    fn fund(&mut self, amount: u64) {
+        let receipt = self.setup.client.send_data(
+            self.setup.alice,
+            self.token_contract,
+            Htlc::transfer_erc20_tx_payload(
+                token_quantity(amount),
+                Address(self.htlc_address.into()),
+            ),
+            Htlc::fund_tx_gas_limit(),
+        );
+        assert!(receipt.is_success());
+    }
+
+    fn redeem(&mut self) -> TransactionReceipt {
+        self.setup.redeem(
+            self.setup.bob,
+            self.htlc_address,
+            Htlc::redeem_tx_gas_limit(),
+        )
+    }
+
+    fn refund(&mut self) -> TransactionReceipt {
+        self.setup.refund(
+            self.setup.alice,
+            self.htlc_address,
+            Htlc::refund_tx_gas_limit(),
+        )
+    }
+
+    fn token_balance_of(&self, address: H160) -> U256 {
+        self.setup
+            .client
+            .token_balance_of(self.token_contract, address)
+    }
+
+    fn htlc_balance(&self) -> TokenQuantity {
+        let mut balance = [0u8; 32];
+        self.token_balance_of(self.htlc_address)
+            .to_big_endian(&mut balance);
+        TokenQuantity(balance)
+    }
+}
+
+#[test]
+fn given_htlc_funded_in_excess_when_redeemed_then_whole_balance_is_transferred() {
+    let mut htlc = deploy(Token::Standard);
+    htlc.fund(400);
+    htlc.fund(100);
+
+    assert!(Htlc::is_funded(htlc.htlc_balance(), token_quantity(400)));
+
+    assert!(htlc.redeem().is_success());
+    assert_eq!(htlc.token_balance_of(htlc.htlc_address), U256::from(0));
+    assert_eq!(htlc.token_balance_of(htlc.setup.bob), U256::from(500));
+    assert!(htlc
+        .setup
+        .client
+        .get_contract_code(htlc.htlc_address)
+        .is_empty());
+}
+
+#[test]
+fn given_htlc_funded_in_excess_when_refunded_then_whole_balance_is_transferred() {
+    let mut htlc = deploy(Token::Standard);
+    htlc.fund(400);
+    htlc.fund(100);
+
+    assert!(htlc.refund().is_success());
+    assert_eq!(htlc.token_balance_of(htlc.htlc_address), U256::from(0));
+    assert_eq!(htlc.token_balance_of(htlc.setup.alice), U256::from(1000));
+}
+
+#[test]
+fn given_fee_on_transfer_token_when_redeemed_then_received_balance_is_transferred() {
+    let mut htlc = deploy(Token::FeeOnTransfer);
+    htlc.fund(400);
+
+    // A tenth of the amount is kept by the token
+    assert_eq!(htlc.token_balance_of(htlc.htlc_address), U256::from(360));
+    assert!(!Htlc::is_funded(htlc.htlc_balance(), token_quantity(400)));
+    assert!(Htlc::is_funded(htlc.htlc_balance(), token_quantity(360)));
+
+    assert!(htlc.redeem().is_success());
+    assert_eq!(htlc.token_balance_of(htlc.htlc_address), U256::from(0));
+    assert_eq!(htlc.token_balance_of(htlc.setup.bob), U256::from(324));
+}
+
+#[test]
+fn given_fee_on_transfer_token_when_refunded_then_received_balance_is_transferred() {
+    let mut htlc = deploy(Token::FeeOnTransfer);
+    htlc.fund(400);
+
+    assert!(htlc.refund().is_success());
+    assert_eq!(htlc.token_balance_of(htlc.htlc_address), U256::from(0));
+    assert_eq!(htlc.token_balance_of(htlc.setup.alice), U256::from(924));
+}
@@ -0,0 +1,146 @@
+#![warn(unused_extern_crates, missing_debug_implementations, rust_2018_idioms)]
+#![forbid(unsafe_code)]
+
+pub mod evm_client;
+pub mod htlc_harness;
+
+use crate::{
+    evm_client::TransactionReceipt,
+    htlc_harness::{setup, token_quantity, Setup, Token, SECRET_HASH},
+};
+use blockchain_contracts::ethereum::{herc20_relayer_fee::Htlc, Address, TRANSFER_FAILED};
+use primitive_types::{H160, U256};
+
+#[derive(Debug)]
+struct FundedHtlc {
+    setup: Setup,
+    token_contract: H160,
+    htlc: Htlc,
+    htlc_address: H160,
+}
+
+fn funded_htlc(quantity: u64, relayer_fee: u64) -> FundedHtlc {
+    let mut setup = setup();
+    let token_contract = setup.deploy_token(Token::Standard);
+
+    let htlc = Htlc::new(
+        setup.expiry.into(),
+        Address(setup.alice.into()),
+        Address(setup.bob.into()),
+        SECRET_HASH,
+        Address(token_contract.into()),
+        token_quantity(quantity),
+        token_quantity(relayer_fee),
+    )
+    .unwrap();
+    let htlc_address = setup.deploy_htlc(
+        htlc.clone().into(),
+        U256::zero(),
+        Htlc::deploy_tx_gas_limit(),
+    );
+
+    let receipt = setup.client.send_data(
+        setup.alice,
+        token_contract,
+        Htlc::transfer_erc20_tx_payload(htlc.token_quantity(), Address(htlc_address.into())),
+        Htlc::fund_tx_gas_limit(),
+    );
+    assert!(receipt.is_success());
+
+    FundedHtlc {
+        setup,
+        token_contract,
+        htlc,
+        htlc_address,
+    }
+}
+
+impl FundedHtlc {
+    fn redeem(&mut self, from: H160) -> TransactionReceipt {
+        self.setup
+            .redeem(from, self.htlc_address, Htlc::redeem_tx_gas_limit())
+    }
+
+    fn token_balance_of(&self, address: H160) -> U256 {
+        self.setup
+            .client
+            .token_balance_of(self.token_contract, address)
+    }
+}
+
+#[test]
+fn given_relayer_without_ether_when_redeemed_then_fee_is_paid_to_relayer() {
+    let mut htlc = funded_htlc(400, 30);
+    assert_eq!(
+        htlc.setup.client.eth_balance_of(htlc.setup.relayer),
+        U256::zero()
+    );
+
+    assert!(htlc.redeem(htlc.setup.relayer).is_success());
+    assert_eq!(htlc.token_balance_of(htlc.setup.relayer), U256::from(30));
+    assert_eq!(
+        htlc.token_balance_of(htlc.setup.bob),
+        U256::from_big_endian(&htlc.htlc.net_token_quantity().0)
+    );
+    assert_eq!(htlc.token_balance_of(htlc.setup.bob), U256::from(370));
+    assert_eq!(htlc.token_balance_of(htlc.htlc_address), U256::zero());
+    assert!(htlc
+        .setup
+        .client
+        .get_contract_code(htlc.htlc_address)
+        .is_empty());
+}
+
+#[test]
+fn given_relayer_starving_the_transfer_to_the_redeemer_then_redeem_reverts_without_fee() {
+    let mut htlc = funded_htlc(400, 30);
+
+    // The transfer to Bob is left the gas above the 100_000 kept for the
+    // transfer of the fee, too little for it to succeed
+    let receipt = htlc
+        .setup
+        .redeem(htlc.setup.relayer, htlc.htlc_address, 130_000);
+
+    assert!(receipt.is_reverted());
+    assert_eq!(hex::encode(&receipt.return_data), TRANSFER_FAILED);
+    assert_eq!(htlc.token_balance_of(htlc.setup.relayer), U256::zero());
+    assert_eq!(htlc.token_balance_of(htlc.setup.bob), U256::zero());
+    assert_eq!(htlc.token_balance_of(htlc.htlc_address), U256::from(400));
+    assert!(!htlc
+        .setup
+        .client
+        .get_contract_code(htlc.htlc_address)
+        .is_empty());
+}
+
+#[test]
+fn given_redeemer_redeeming_themselves_then_whole_quantity_is_received() {
+    let mut htlc = funded_htlc(400, 30);
+
+    assert!(htlc.redeem(htlc.setup.bob).is_success());
+    assert_eq!(htlc.token_balance_of(htlc.setup.bob), U256::from(400));
+}
+
+#[test]
+fn given_no_relayer_fee_when_redeemed_then_whole_quantity_is_received() {
+    let mut htlc = funded_htlc(400, 0);
+
+    assert!(htlc.redeem(htlc.setup.relayer).is_success());
+    assert_eq!(htlc.token_balance_of(htlc.setup.relayer), U256::zero());
+    assert_eq!(htlc.token_balance_of(htlc.setup.bob), U256::from(400));
+}
+
+#[test]
+fn given_expired_htlc_when_refunded_then_no_fee_is_paid() {
+    let mut htlc = funded_htlc(400, 30);
+    let receipt = htlc.setup.refund(
+        htlc.setup.relayer,
+        htlc.htlc_address,
+        Htlc::refund_tx_gas_limit(),
+    );
+
+    assert!(receipt.is_success());
+    assert_eq!(htlc.token_balance_of(htlc.setup.relayer), U256::zero());
+    assert_eq!(htlc.token_balance_of(htlc.setup.alice), U256::from(1000));
+    assert_eq!(htlc.token_balance_of(htlc.htlc_address), U256::zero());
+}
@@ -0,0 +1,235 @@
+#![warn(unused_extern_crates, missing_debug_implementations, rust_2018_idioms)]
+#![forbid(unsafe_code)]
+
+pub mod evm_client;
+pub mod htlc_harness;
+
+use crate::{
+    evm_client::TransactionReceipt,
+    htlc_harness::{setup, token_quantity, Setup, Token, SECRET_HASH},
+};
+use blockchain_contracts::ethereum::{
+    herc20, herc20_safe::Htlc, Address, Expiry, HashFunction, REDEEMED_LOG_MSG, REFUNDED_LOG_MSG,
+    TOO_EARLY, TRANSFER_FAILED,
+};
+use primitive_types::{H160, H256, U256};
+
+#[derive(Debug)]
+struct SafeHtlcs {
+    setup: Setup,
+    token_contract: H160,
+}
+
+fn safe_htlcs(token: Token) -> SafeHtlcs {
+    let mut setup = setup();
+    let token_contract = setup.deploy_token(token);
+
+    SafeHtlcs {
+        setup,
+        token_contract,
+    }
+}
+
+impl SafeHtlcs {
+    fn htlc(&self, token_contract: H160) -> Htlc {
+        Htlc::new(
+            self.setup.expiry.into(),
+            Address(self.setup.alice.into()),
+            Address(self.setup.bob.into()),
+            SECRET_HASH,
+            Address(token_contract.into()),
+            token_quantity(400),
+        )
+    }
+
+    fn deploy(&mut self, init_code: Vec<u8>) -> H160 {
+        self.setup
+            .deploy_htlc(init_code, U256::zero(), Htlc::deploy_tx_gas_limit())
+    }
+
+    fn fund(&mut self, htlc_address: H160, amount: u64) {
+        let receipt = self.setup.client.send_data(
+            self.setup.alice,
+            self.token_contract,
+            Htlc::transfer_erc20_tx_payload(token_quantity(amount), Address(htlc_address.into())),
+            Htlc::fund_tx_gas_limit(),
+        );
+        assert!(receipt.is_success());
+    }
+
+    fn redeem(&mut self, htlc_address: H160) -> TransactionReceipt {
+        self.setup
+            .redeem(self.setup.bob, htlc_address, Htlc::redeem_tx_gas_limit())
+    }
+
+    fn refund(&mut self, htlc_address: H160) -> TransactionReceipt {
+        self.setup
+            .refund(self.setup.alice, htlc_address, Htlc::refund_tx_gas_limit())
+    }
+
+    fn token_balance_of(&self, address: H160) -> U256 {
+        self.setup
+            .client
+            .token_balance_of(self.token_contract, address)
+    }
+}
+
+fn assert_transfer_failed(receipt: &TransactionReceipt) {
+    assert!(receipt.is_reverted());
+    assert_eq!(hex::encode(&receipt.return_data), TRANSFER_FAILED);
+}
+
+fn assert_log_message(receipt: &TransactionReceipt, message: &str) {
+    let topic = H256::from_slice(&hex::decode(message).unwrap());
+    assert!(receipt.logs.iter().any(|log| log.topics == vec![topic]));
+}
+
+fn given_funded_htlc_when_redeemed_then_tokens_are_transferred(token: Token) {
+    let mut htlcs = safe_htlcs(token);
+    let htlc_address = htlcs.deploy(htlcs.htlc(htlcs.token_contract).into());
+    htlcs.fund(htlc_address, 400);
+
+    let receipt = htlcs.redeem(htlc_address);
+
+    assert!(receipt.is_success());
+    assert_log_message(&receipt, REDEEMED_LOG_MSG);
+    assert_eq!(htlcs.token_balance_of(htlc_address), U256::from(0));
+    assert_eq!(htlcs.token_balance_of(htlcs.setup.bob), U256::from(400));
+    assert!(htlcs
+        .setup
+        .client
+        .get_contract_code(htlc_address)
+        .is_empty());
+}
+
+#[test]
+fn given_token_returning_true_when_redeemed_then_tokens_are_transferred() {
+    given_funded_htlc_when_redeemed_then_tokens_are_transferred(Token::Standard)
+}
+
+#[test]
+fn given_token_returning_nothing_when_redeemed_then_tokens_are_transferred() {
+    given_funded_htlc_when_redeemed_then_tokens_are_transferred(Token::NoReturn)
+}
+
+#[test]
+fn given_token_returning_bool_when_redeemed_then_tokens_are_transferred() {
+    given_funded_htlc_when_redeemed_then_tokens_are_transferred(Token::FalseReturn)
+}
+
+#[test]
+fn given_token_returning_nothing_when_refunded_then_tokens_are_transferred() {
+    let mut htlcs = safe_htlcs(Token::NoReturn);
+    let htlc_address = htlcs.deploy(htlcs.htlc(htlcs.token_contract).into());
+    htlcs.fund(htlc_address, 400);
+
+    let receipt = htlcs.refund(htlc_address);
+
+    assert!(receipt.is_success());
+    assert_log_message(&receipt, REFUNDED_LOG_MSG);
+    assert_eq!(htlcs.token_balance_of(htlcs.setup.alice), U256::from(1000));
+}
+
+#[test]
+fn given_reverting_transfer_when_redeemed_then_redeem_reverts_until_funded() {
+    let mut htlcs = safe_htlcs(Token::NoReturn);
+    let htlc_address = htlcs.deploy(htlcs.htlc(htlcs.token_contract).into());
+    htlcs.fund(htlc_address, 300);
+
+    assert_transfer_failed(&htlcs.redeem(htlc_address));
+    assert!(!htlcs
+        .setup
+        .client
+        .get_contract_code(htlc_address)
+        .is_empty());
+    assert_eq!(htlcs.token_balance_of(htlc_address), U256::from(300));
+
+    htlcs.fund(htlc_address, 100);
+
+    assert!(htlcs.redeem(htlc_address).is_success());
+    assert_eq!(htlcs.token_balance_of(htlcs.setup.bob), U256::from(400));
+}
+
+#[test]
+fn given_transfer_returning_false_when_refunded_then_refund_reverts_until_funded() {
+    let mut htlcs = safe_htlcs(Token::FalseReturn);
+    let htlc_address = htlcs.deploy(htlcs.htlc(htlcs.token_contract).into());
+    htlcs.fund(htlc_address, 300);
+
+    assert_transfer_failed(&htlcs.refund(htlc_address));
+    assert!(!htlcs
+        .setup
+        .client
+        .get_contract_code(htlc_address)
+        .is_empty());
+    assert_eq!(htlcs.token_balance_of(htlc_address), U256::from(300));
+
+    htlcs.fund(htlc_address, 100);
+
+    assert!(htlcs.refund(htlc_address).is_success());
+    assert_eq!(htlcs.token_balance_of(htlcs.setup.alice), U256::from(1000));
+}
+
+#[test]
+fn given_token_address_without_code_when_redeemed_then_redeem_reverts() {
+    let mut htlcs = safe_htlcs(Token::Standard);
+    let htlc_address = htlcs.deploy(htlcs.htlc(htlcs.setup.bob).into());
+
+    assert_transfer_failed(&htlcs.redeem(htlc_address));
+    assert!(!htlcs
+        .setup
+        .client
+        .get_contract_code(htlc_address)
+        .is_empty());
+}
+
+#[test]
+fn given_transfer_returning_false_when_herc20_htlc_redeemed_then_tokens_are_stuck() {
+    let mut htlcs = safe_htlcs(Token::FalseReturn);
+    let htlc = herc20::Htlc::new(
+        htlcs.setup.expiry.into(),
+        Address(htlcs.setup.alice.into()),
+        Address(htlcs.setup.bob.into()),
+        SECRET_HASH,
+        HashFunction::Sha256,
+        Address(htlcs.token_contract.into()),
+        token_quantity(400),
+    );
+    let htlc_address = htlcs.deploy(htlc.into());
+    htlcs.fund(htlc_address, 300);
+
+    assert!(htlcs.redeem(htlc_address).is_success());
+    assert!(htlcs
+        .setup
+        .client
+        .get_contract_code(htlc_address)
+        .is_empty());
+    assert_eq!(htlcs.token_balance_of(htlc_address), U256::from(300));
+    assert_eq!(htlcs.token_balance_of(htlcs.setup.bob), U256::from(0));
+}
+
+#[test]
+fn given_block_height_expiry_when_refunded_after_expiry_block_then_tokens_are_refunded() {
+    let mut htlcs = safe_htlcs(Token::NoReturn);
+    let htlc = Htlc::new(
+        Expiry::BlockHeight(100),
+        Address(htlcs.setup.alice.into()),
+        Address(htlcs.setup.bob.into()),
+        SECRET_HASH,
+        Address(htlcs.token_contract.into()),
+        token_quantity(400),
+    );
+    let htlc_address = htlcs.deploy(htlc.into());
+    htlcs.fund(htlc_address, 400);
+
+    // The block timestamp has no bearing on the expiry
+    let receipt = htlcs.refund(htlc_address);
+    assert!(receipt.is_reverted());
+    assert_eq!(hex::encode(&receipt.return_data), TOO_EARLY);
+
+    htlcs.setup.client.set_block_number(101);
+    let receipt = htlcs.refund(htlc_address);
+
+    assert!(receipt.is_success());
+    assert_eq!(htlcs.token_balance_of(htlcs.setup.alice), U256::from(1000));
+}
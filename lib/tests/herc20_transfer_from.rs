@@ -0,0 +1,153 @@
+#![warn(unused_extern_crates, missing_debug_implementations, rust_2018_idioms)]
+#![forbid(unsafe_code)]
+
+pub mod evm_client;
+pub mod htlc_harness;
+
+use crate::{
+    evm_client::EvmClient,
+    htlc_harness::{setup, token_quantity, Token, SECRET, SECRET_HASH},
+};
+use blockchain_contracts::ethereum::{
+    contract_address, herc20, herc20_transfer_from::Htlc, Address, DeployedHtlcCode,
+};
+use primitive_types::{H160, U256};
+
+fn htlc_setup() -> (H160, H160, H160, Htlc, EvmClient) {
+    let mut setup = setup();
+    let token_contract = setup.deploy_token(Token::Standard);
+
+    let htlc = Htlc::new(
+        setup.expiry.into(),
+        Address(setup.alice.into()),
+        Address(setup.bob.into()),
+        SECRET_HASH,
+        Address(token_contract.into()),
+        token_quantity(400),
+    );
+
+    (setup.alice, setup.bob, token_contract, htlc, setup.client)
+}
+
+#[test]
+fn given_approved_tokens_when_deployed_then_htlc_is_funded_and_redeemable() {
+    let (alice, bob, token_contract, htlc, mut client) = htlc_setup();
+
+    // The HTLC is deployed by the transaction following the approval
+    let htlc_address = contract_address(Address(alice.into()), client.nonce_of(alice) + 1);
+    let approve_receipt = client.send_data(
+        alice,
+        token_contract,
+        Htlc::approve_erc20_tx_payload(token_quantity(400), htlc_address),
+        Htlc::approve_tx_gas_limit(),
+    );
+    assert!(approve_receipt.is_success());
+    log::debug!("used gas ERC20 approve {:?}", approve_receipt.gas_used);
+
+    let deploy_receipt = client.deploy_htlc(
+        alice,
+        htlc.into(),
+        U256::zero(),
+        Htlc::deploy_tx_gas_limit(),
+    );
+    assert!(deploy_receipt.is_success());
+    log::debug!("used gas ERC20 deploy {:?}", deploy_receipt.gas_used);
+
+    let htlc_address = H160(htlc_address.0);
+    assert_eq!(deploy_receipt.contract_address, Some(htlc_address));
+    assert!(herc20::Htlc::from_runtime_code(&client.get_contract_code(htlc_address)).is_ok());
+    assert_eq!(
+        client.token_balance_of(token_contract, htlc_address),
+        U256::from(400)
+    );
+    assert_eq!(
+        client.token_balance_of(token_contract, alice),
+        U256::from(600)
+    );
+
+    let redeem_receipt = client.send_data(
+        alice,
+        htlc_address,
+        SECRET.to_vec(),
+        Htlc::redeem_tx_gas_limit(),
+    );
+    assert!(redeem_receipt.is_success());
+    assert_eq!(
+        client.token_balance_of(token_contract, bob),
+        U256::from(400)
+    );
+}
+
+#[test]
+fn given_no_approval_when_deployed_then_deployment_fails() {
+    let (alice, _bob, token_contract, htlc, mut client) = htlc_setup();
+
+    let deploy_receipt = client.deploy_htlc(
+        alice,
+        htlc.into(),
+        U256::zero(),
+        Htlc::deploy_tx_gas_limit(),
+    );
+
+    assert!(!deploy_receipt.is_success());
+    assert_eq!(deploy_receipt.contract_address, None);
+    assert_eq!(
+        client.token_balance_of(token_contract, alice),
+        U256::from(1000)
+    );
+}
+
+#[test]
+fn given_approval_of_fewer_tokens_when_deployed_then_deployment_fails() {
+    let (alice, _bob, token_contract, htlc, mut client) = htlc_setup();
+
+    let htlc_address = contract_address(Address(alice.into()), client.nonce_of(alice) + 1);
+    client.send_data(
+        alice,
+        token_contract,
+        Htlc::approve_erc20_tx_payload(token_quantity(399), htlc_address),
+        Htlc::approve_tx_gas_limit(),
+    );
+
+    let deploy_receipt = client.deploy_htlc(
+        alice,
+        htlc.into(),
+        U256::zero(),
+        Htlc::deploy_tx_gas_limit(),
+    );
+
+    assert!(!deploy_receipt.is_success());
+    assert!(client.get_contract_code(H160(htlc_address.0)).is_empty());
+    assert_eq!(
+        client.token_balance_of(token_contract, alice),
+        U256::from(1000)
+    );
+}
+
+#[test]
+fn given_token_address_without_code_when_deployed_then_deployment_fails() {
+    let setup = setup();
+    let (alice, bob, mut client) = (setup.alice, setup.bob, setup.client);
+
+    // A call to an account without code succeeds and returns nothing
+    let htlc = Htlc::new(
+        setup.expiry.into(),
+        Address(alice.into()),
+        Address(bob.into()),
+        SECRET_HASH,
+        Address(bob.into()),
+        token_quantity(400),
+    );
+    let htlc_address = contract_address(Address(alice.into()), client.nonce_of(alice));
+
+    let deploy_receipt = client.deploy_htlc(
+        alice,
+        htlc.into(),
+        U256::zero(),
+        Htlc::deploy_tx_gas_limit(),
+    );
+
+    assert!(!deploy_receipt.is_success());
+    assert_eq!(deploy_receipt.contract_address, None);
+    assert!(client.get_contract_code(H160(htlc_address.0)).is_empty());
+}
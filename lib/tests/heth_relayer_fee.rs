@@ -0,0 +1,126 @@
+#![warn(unused_extern_crates, missing_debug_implementations, rust_2018_idioms)]
+#![forbid(unsafe_code)]
+
+pub mod evm_client;
+pub mod htlc_harness;
+
+use crate::{
+    evm_client::TransactionReceipt,
+    htlc_harness::{setup, Setup, SECRET, SECRET_HASH},
+};
+use blockchain_contracts::ethereum::{heth_relayer_fee::Htlc, Address, REDEEMED_LOG_MSG};
+use evm::backend::Log;
+use primitive_types::{H160, H256, U256};
+use spectral::prelude::*;
+
+const RELAYER_FEE: u128 = 1_000_000_000_000_000;
+
+#[derive(Debug)]
+struct FundedHtlc {
+    setup: Setup,
+    htlc: Htlc,
+    htlc_address: H160,
+}
+
+fn funded_htlc(wei_amount: u128) -> FundedHtlc {
+    let mut setup = setup();
+    setup
+        .client
+        .give_eth_to(setup.alice, U256::from(wei_amount));
+
+    let htlc = Htlc::new(
+        setup.expiry.into(),
+        Address(setup.alice.into()),
+        Address(setup.bob.into()),
+        SECRET_HASH,
+        RELAYER_FEE,
+    );
+    let htlc_address = setup.deploy_htlc(
+        htlc.clone().into(),
+        U256::from(wei_amount),
+        Htlc::deploy_tx_gas_limit(),
+    );
+
+    FundedHtlc {
+        setup,
+        htlc,
+        htlc_address,
+    }
+}
+
+impl FundedHtlc {
+    fn redeem(&mut self, from: H160) -> TransactionReceipt {
+        self.setup
+            .redeem(from, self.htlc_address, Htlc::redeem_tx_gas_limit())
+    }
+
+    fn eth_balance_of(&self, address: H160) -> U256 {
+        self.setup.client.eth_balance_of(address)
+    }
+}
+
+#[test]
+fn given_relayer_without_ether_when_redeemed_then_fee_is_paid_to_relayer() {
+    let mut htlc = funded_htlc(400_000_000_000_000_000);
+    assert_eq!(htlc.eth_balance_of(htlc.setup.relayer), U256::zero());
+
+    let receipt = htlc.redeem(htlc.setup.relayer);
+
+    assert!(receipt.is_success());
+    let net_amount = htlc
+        .htlc
+        .net_redeem_amount(400_000_000_000_000_000)
+        .unwrap();
+    assert_eq!(
+        htlc.eth_balance_of(htlc.setup.relayer),
+        U256::from(RELAYER_FEE)
+    );
+    assert_eq!(htlc.eth_balance_of(htlc.setup.bob), U256::from(net_amount));
+    assert_eq!(htlc.eth_balance_of(htlc.htlc_address), U256::zero());
+
+    let topic: H256 = REDEEMED_LOG_MSG.parse().unwrap();
+    let Log { topics, data, .. } = &receipt.logs[0];
+    assert_that(topics).contains(topic);
+    assert_that(data).is_equal_to(SECRET.to_vec());
+}
+
+#[test]
+fn given_redeemer_redeeming_themselves_then_whole_value_is_received() {
+    let mut htlc = funded_htlc(400_000_000_000_000_000);
+
+    assert!(htlc.redeem(htlc.setup.bob).is_success());
+    assert_eq!(
+        htlc.eth_balance_of(htlc.setup.bob),
+        U256::from(400_000_000_000_000_000u64)
+    );
+}
+
+#[test]
+fn given_value_below_the_fee_when_redeemed_then_redeemer_receives_everything() {
+    let mut htlc = funded_htlc(RELAYER_FEE - 1);
+    assert_eq!(htlc.htlc.net_redeem_amount(RELAYER_FEE - 1), None);
+
+    assert!(htlc.redeem(htlc.setup.relayer).is_success());
+    assert_eq!(htlc.eth_balance_of(htlc.setup.relayer), U256::zero());
+    assert_eq!(
+        htlc.eth_balance_of(htlc.setup.bob),
+        U256::from(RELAYER_FEE - 1)
+    );
+}
+
+#[test]
+fn given_expired_htlc_when_refunded_then_no_fee_is_paid() {
+    let mut htlc = funded_htlc(400_000_000_000_000_000);
+    let receipt = htlc.setup.refund(
+        htlc.setup.relayer,
+        htlc.htlc_address,
+        Htlc::refund_tx_gas_limit(),
+    );
+
+    assert!(receipt.is_success());
+    assert_eq!(htlc.eth_balance_of(htlc.setup.relayer), U256::zero());
+    assert_eq!(
+        htlc.eth_balance_of(htlc.setup.alice),
+        U256::from(400_000_000_000_000_000u64)
+    );
+}
@@ -1,22 +1,19 @@
 use crate::{
-    ethereum_helper::{tc_web3_client, InMemoryWallet},
-    htlc_harness::{new_account, timestamp::Timestamp, SECRET_HASH},
-    parity_client::ParityClient,
+    evm_client::EvmClient,
+    htlc_harness::{new_account, timestamp::Timestamp, SECRET, SECRET_HASH},
 };
 use blockchain_contracts::ethereum::herc20::Htlc;
-use blockchain_contracts::ethereum::TokenQuantity;
-use std::sync::Arc;
-use testcontainers::{images::parity_parity::ParityEthereum, Container, Docker};
-use web3::{
-    transports::EventLoopHandle,
-    types::{Address, U256},
-};
+use blockchain_contracts::ethereum::{Expiry, HashFunction, TokenQuantity};
+use primitive_types::{H160, U256};
 
 #[derive(Debug, Clone)]
 pub struct Erc20HarnessParams {
     pub alice_initial_wei: U256,
     pub htlc_refund_timestamp: Timestamp,
+    /// Expires the HTLC at this block number instead of the refund timestamp
+    pub htlc_refund_block_height: Option<u32>,
     pub htlc_secret_hash: [u8; 32],
+    pub htlc_hash_function: HashFunction,
     pub alice_initial_tokens: U256,
     pub htlc_token_value: U256,
 }
@@ -24,9 +21,11 @@ pub struct Erc20HarnessParams {
 impl Default for Erc20HarnessParams {
     fn default() -> Self {
         Self {
-            alice_initial_wei: "1000000000000000000".into(),
+            alice_initial_wei: U256::from(1_000_000_000_000_000_000u64),
             htlc_refund_timestamp: Timestamp::now().plus(10),
+            htlc_refund_block_height: None,
             htlc_secret_hash: SECRET_HASH,
+            htlc_hash_function: HashFunction::Sha256,
             alice_initial_tokens: U256::from(1000),
             htlc_token_value: U256::from(400),
         }
@@ -40,67 +39,66 @@ impl Erc20HarnessParams {
             ..self
         }
     }
+
+    /// Locks the HTLC with the hash of `SECRET` by `hash_function`.
+    pub fn with_hash_function(self, hash_function: HashFunction) -> Self {
+        Self {
+            htlc_secret_hash: hash_function.secret_hash(SECRET),
+            htlc_hash_function: hash_function,
+            ..self
+        }
+    }
 }
 
-pub fn erc20_harness<D: Docker>(
-    docker: &D,
-    params: Erc20HarnessParams,
-) -> (
-    Address,
-    Address,
-    Address,
-    Address,
-    U256,
-    ParityClient,
-    EventLoopHandle,
-    Container<'_, D, ParityEthereum>,
-) {
+pub fn erc20_harness(params: Erc20HarnessParams) -> (H160, H160, H160, H160, U256, EvmClient) {
     let _ = pretty_env_logger::try_init();
 
-    let (alice_secret_key, alice) =
+    let (_, alice) =
         new_account("63be4b0d638d44b5fee5b050ab0beeeae7b68cde3d829a3321f8009cdd76b992");
     let (_, bob) = new_account("f8218ebf6e2626bd1415c18321496e0c5725f0e1d774c7c2eab69f7650ad6e82");
 
-    let container = docker.run(ParityEthereum::default());
-
-    let (event_loop, web3) = tc_web3_client::new(&container);
-    let web3 = Arc::new(web3);
+    let mut client = EvmClient::new(Timestamp::now());
 
-    let alice_client =
-        ParityClient::new(Arc::new(InMemoryWallet::new(alice_secret_key, 1)), web3, 0);
+    client.give_eth_to(alice, params.alice_initial_wei);
 
-    alice_client.give_eth_to(alice, params.alice_initial_wei);
+    let token_contract = client.deploy_erc20_token_contract(alice);
 
-    let token_contract = alice_client.deploy_erc20_token_contract();
+    client.mint_tokens(alice, token_contract, params.alice_initial_tokens, alice);
 
-    alice_client.mint_tokens(token_contract, params.alice_initial_tokens, alice);
+    let mut token_quantity = [0u8; 32];
+    params.htlc_token_value.to_big_endian(&mut token_quantity);
 
+    let expiry = params
+        .htlc_refund_block_height
+        .map_or_else(|| params.htlc_refund_timestamp.into(), Expiry::BlockHeight);
     let erc20_htlc = Htlc::new(
-        params.htlc_refund_timestamp.into(),
+        expiry,
         blockchain_contracts::ethereum::Address(alice.into()),
         blockchain_contracts::ethereum::Address(bob.into()),
         params.htlc_secret_hash,
+        params.htlc_hash_function,
         blockchain_contracts::ethereum::Address(token_contract.into()),
-        TokenQuantity(params.htlc_token_value.into()),
+        TokenQuantity(token_quantity),
     );
 
-    let tx_id = alice_client.deploy_htlc(
+    let transaction_receipt = client.deploy_htlc(
+        alice,
         erc20_htlc.into(),
-        U256::from(0),
-        Htlc::deploy_tx_gas_limit().into(),
+        U256::zero(),
+        Htlc::deploy_tx_gas_limit(),
     );
-
-    let transaction_receipt = alice_client.receipt(tx_id);
     log::debug!("used gas ERC20 deploy {:?}", transaction_receipt.gas_used);
 
+    let htlc = transaction_receipt
+        .contract_address
+        .expect("ERC20 HTLC deployment failed");
+
     (
         alice,
         bob,
-        alice_client.get_contract_address(tx_id),
+        htlc,
         token_contract,
         params.htlc_token_value,
-        alice_client,
-        event_loop,
-        container,
+        client,
     )
 }
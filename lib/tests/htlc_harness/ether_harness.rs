@@ -1,31 +1,30 @@
 use crate::{
-    ethereum_helper::{tc_web3_client, InMemoryWallet},
-    htlc_harness::{new_account, timestamp::Timestamp, SECRET_HASH},
-    parity_client::ParityClient,
-};
-use blockchain_contracts::ethereum::heth::Htlc;
-use std::sync::Arc;
-use testcontainers::{images::parity_parity::ParityEthereum, Container, Docker};
-use web3::{
-    transports::EventLoopHandle,
-    types::{Address, U256},
+    evm_client::EvmClient,
+    htlc_harness::{new_account, timestamp::Timestamp, SECRET, SECRET_HASH},
 };
+use blockchain_contracts::ethereum::{heth::Htlc, Expiry, HashFunction};
+use primitive_types::{H160, U256};
 
 #[derive(Debug, Clone)]
 pub struct EtherHarnessParams {
     pub alice_initial_wei: U256,
     pub htlc_refund_timestamp: Timestamp,
+    /// Expires the HTLC at this block number instead of the refund timestamp
+    pub htlc_refund_block_height: Option<u32>,
     pub htlc_secret_hash: [u8; 32],
+    pub htlc_hash_function: HashFunction,
     pub htlc_wei_value: U256,
 }
 
 impl Default for EtherHarnessParams {
     fn default() -> Self {
         Self {
-            alice_initial_wei: U256::from("1000000000000000000"),
+            alice_initial_wei: U256::from(1_000_000_000_000_000_000u64),
             htlc_refund_timestamp: Timestamp::now().plus(10),
+            htlc_refund_block_height: None,
             htlc_secret_hash: SECRET_HASH,
-            htlc_wei_value: U256::from("0400000000000000000"),
+            htlc_hash_function: HashFunction::Sha256,
+            htlc_wei_value: U256::from(400_000_000_000_000_000u64),
         }
     }
 }
@@ -37,56 +36,49 @@ impl EtherHarnessParams {
             ..self
         }
     }
+
+    /// Locks the HTLC with the hash of `SECRET` by `hash_function`.
+    pub fn with_hash_function(self, hash_function: HashFunction) -> Self {
+        Self {
+            htlc_secret_hash: hash_function.secret_hash(SECRET),
+            htlc_hash_function: hash_function,
+            ..self
+        }
+    }
 }
 
-pub fn ether_harness<D: Docker>(
-    docker: &D,
-    params: EtherHarnessParams,
-) -> (
-    Address,
-    Address,
-    Address,
-    ParityClient,
-    EventLoopHandle,
-    Container<'_, D, ParityEthereum>,
-) {
+pub fn ether_harness(params: EtherHarnessParams) -> (H160, H160, H160, EvmClient) {
     let _ = pretty_env_logger::try_init();
 
-    let (alice_secret_key, alice) =
+    let (_, alice) =
         new_account("63be4b0d638d44b5fee5b050ab0beeeae7b68cde3d829a3321f8009cdd76b992");
     let (_, bob) = new_account("f8218ebf6e2626bd1415c18321496e0c5725f0e1d774c7c2eab69f7650ad6e82");
 
-    let container = docker.run(ParityEthereum::default());
+    let mut client = EvmClient::new(Timestamp::now());
 
-    let (event_loop, web3) = tc_web3_client::new(&container);
-    let web3 = Arc::new(web3);
+    client.give_eth_to(alice, params.alice_initial_wei);
 
-    let alice_client =
-        ParityClient::new(Arc::new(InMemoryWallet::new(alice_secret_key, 1)), web3, 0);
-
-    alice_client.give_eth_to(alice, params.alice_initial_wei);
-
-    let tx_id = alice_client.deploy_htlc(
+    let expiry = params
+        .htlc_refund_block_height
+        .map_or_else(|| params.htlc_refund_timestamp.into(), Expiry::BlockHeight);
+    let transaction_receipt = client.deploy_htlc(
+        alice,
         Htlc::new(
-            params.htlc_refund_timestamp.into(),
+            expiry,
             blockchain_contracts::ethereum::Address(alice.into()),
             blockchain_contracts::ethereum::Address(bob.into()),
             params.htlc_secret_hash,
+            params.htlc_hash_function,
         )
         .into(),
         params.htlc_wei_value,
-        Htlc::deploy_tx_gas_limit().into(),
+        Htlc::deploy_tx_gas_limit(),
     );
-
-    let transaction_receipt = alice_client.receipt(tx_id);
     log::debug!("used gas Ether deploy {:?}", transaction_receipt.gas_used);
 
-    (
-        alice,
-        bob,
-        alice_client.get_contract_address(tx_id),
-        alice_client,
-        event_loop,
-        container,
-    )
+    let htlc = transaction_receipt
+        .contract_address
+        .expect("Ether HTLC deployment failed");
+
+    (alice, bob, htlc, client)
 }
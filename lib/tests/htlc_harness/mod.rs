@@ -1,25 +1,32 @@
 use crypto::{digest::Digest, sha2::Sha256};
 use hex::FromHexError;
 use hex_literal::hex;
-use std::{str::FromStr, thread::sleep, time::Duration};
-use web3::types::Address as EthereumAddress;
+use lazy_static::lazy_static;
+use primitive_types::H160;
+use std::str::FromStr;
 
 mod erc20_harness;
 mod ether_harness;
+mod setup;
 mod timestamp;
 
 pub use self::{
     erc20_harness::{erc20_harness, Erc20HarnessParams},
     ether_harness::{ether_harness, EtherHarnessParams},
+    setup::{setup, token_quantity, Setup, Token, HTLC_TIMEOUT_SECS},
     timestamp::Timestamp,
 };
-use crate::ethereum_helper::{to_ethereum_address::ToEthereumAddress, SECP};
-use rust_bitcoin::secp256k1::{PublicKey, SecretKey};
+use blockchain_contracts::ethereum::Address;
+use rust_bitcoin::secp256k1::{self, PublicKey, Secp256k1, SecretKey};
 
-pub fn new_account(secret_key: &str) -> (SecretKey, EthereumAddress) {
+lazy_static! {
+    pub static ref SECP: Secp256k1<secp256k1::All> = Secp256k1::new();
+}
+
+pub fn new_account(secret_key: &str) -> (SecretKey, H160) {
     let secret_key = SecretKey::from_str(secret_key).unwrap();
     let public_key = PublicKey::from_secret_key(&*SECP, &secret_key);
-    (secret_key, public_key.to_ethereum_address())
+    (secret_key, H160(Address::from_public_key(&public_key).0))
 }
 
 pub const SECRET: &[u8; 32] = b"hello world, you are beautiful!!";
@@ -49,14 +56,3 @@ impl FromStr for CustomSizeSecret {
         Ok(CustomSizeSecret(secret))
     }
 }
-
-fn diff(first: Timestamp, second: Timestamp) -> u32 {
-    u32::from(first).saturating_sub(u32::from(second))
-}
-
-pub fn sleep_until(timestamp: Timestamp) {
-    let duration = diff(timestamp, Timestamp::now());
-    let buffer = 2;
-
-    sleep(Duration::from_secs((duration + buffer).into()));
-}
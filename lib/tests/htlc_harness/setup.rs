@@ -0,0 +1,109 @@
+use crate::{
+    evm_client::{EvmClient, TransactionReceipt},
+    htlc_harness::{new_account, timestamp::Timestamp, SECRET},
+};
+use blockchain_contracts::ethereum::TokenQuantity;
+use primitive_types::{H160, U256};
+
+pub const HTLC_TIMEOUT_SECS: u32 = 10;
+
+/// The ERC20 token contracts an HTLC can be funded with.
+#[derive(Clone, Copy, Debug)]
+pub enum Token {
+    Standard,
+    /// Returns nothing from `transfer`
+    NoReturn,
+    /// Returns `false` from `transfer` instead of reverting
+    FalseReturn,
+    /// Keeps a tenth of every amount transferred
+    FeeOnTransfer,
+}
+
+/// A chain with Alice, who deploys the HTLCs, Bob, who redeems them, and a
+/// relayer without Ether who may redeem them for Bob.
+#[derive(Debug)]
+pub struct Setup {
+    pub alice: H160,
+    pub bob: H160,
+    pub relayer: H160,
+    /// The expiry of the HTLCs, `HTLC_TIMEOUT_SECS` after the current block
+    pub expiry: Timestamp,
+    pub client: EvmClient,
+}
+
+pub fn setup() -> Setup {
+    let _ = pretty_env_logger::try_init();
+
+    let (_, alice) =
+        new_account("63be4b0d638d44b5fee5b050ab0beeeae7b68cde3d829a3321f8009cdd76b992");
+    let (_, bob) = new_account("f8218ebf6e2626bd1415c18321496e0c5725f0e1d774c7c2eab69f7650ad6e82");
+    let (_, relayer) =
+        new_account("3f0b5c1e4d6a7e8f9a0b1c2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c0d1e2f");
+
+    let now = Timestamp::now();
+
+    Setup {
+        alice,
+        bob,
+        relayer,
+        expiry: now.plus(HTLC_TIMEOUT_SECS),
+        client: EvmClient::new(now),
+    }
+}
+
+impl Setup {
+    /// Deploys a token contract of which Alice owns 1000 tokens.
+    pub fn deploy_token(&mut self, token: Token) -> H160 {
+        let token_contract = match token {
+            Token::Standard => self.client.deploy_erc20_token_contract(self.alice),
+            Token::NoReturn => self
+                .client
+                .deploy_no_return_erc20_token_contract(self.alice),
+            Token::FalseReturn => self
+                .client
+                .deploy_false_return_erc20_token_contract(self.alice),
+            Token::FeeOnTransfer => self
+                .client
+                .deploy_fee_on_transfer_erc20_token_contract(self.alice),
+        };
+        self.client
+            .mint_tokens(self.alice, token_contract, U256::from(1000), self.alice);
+
+        token_contract
+    }
+
+    pub fn deploy_htlc(&mut self, init_code: Vec<u8>, value: U256, gas_limit: u64) -> H160 {
+        let receipt = self
+            .client
+            .deploy_htlc(self.alice, init_code, value, gas_limit);
+        log::debug!("used gas deploy {:?}", receipt.gas_used);
+
+        receipt.contract_address.expect("HTLC deployment failed")
+    }
+
+    pub fn redeem(&mut self, from: H160, htlc_address: H160, gas_limit: u64) -> TransactionReceipt {
+        let receipt = self
+            .client
+            .send_data(from, htlc_address, SECRET.to_vec(), gas_limit);
+        log::debug!("used gas redeem {:?}", receipt.gas_used);
+
+        receipt
+    }
+
+    /// Refunds the HTLC once it expired.
+    pub fn refund(&mut self, from: H160, htlc_address: H160, gas_limit: u64) -> TransactionReceipt {
+        self.client.set_block_timestamp(self.expiry.plus(1));
+        let receipt = self
+            .client
+            .send_data(from, htlc_address, Vec::new(), gas_limit);
+        log::debug!("used gas refund {:?}", receipt.gas_used);
+
+        receipt
+    }
+}
+
+pub fn token_quantity(amount: u64) -> TokenQuantity {
+    let mut token_quantity = [0u8; 32];
+    U256::from(amount).to_big_endian(&mut token_quantity);
+    TokenQuantity(token_quantity)
+}
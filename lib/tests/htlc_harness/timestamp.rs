@@ -1,3 +1,4 @@
+use blockchain_contracts::ethereum::Expiry;
 use serde::{Deserialize, Serialize};
 use std::time::SystemTime;
 
@@ -18,7 +19,7 @@ impl Timestamp {
     }
 
     pub fn plus(self, seconds: u32) -> Self {
-        Self(self.0.checked_add(seconds).unwrap_or(std::u32::MAX))
+        Self(self.0.saturating_add(seconds))
     }
 }
 
@@ -45,3 +46,9 @@ impl From<Timestamp> for i64 {
         i64::from(item.0)
     }
 }
+
+impl From<Timestamp> for Expiry {
+    fn from(item: Timestamp) -> Self {
+        Expiry::Timestamp(item.0)
+    }
+}
@@ -0,0 +1,33 @@
+{
+    // The call data is the salt followed by the init code of the HTLC
+
+    // Salt the deployment with the caller too, so that nobody else can
+    // deploy an HTLC at the address the caller expects
+    mstore(0, caller)
+    mstore(32, calldataload(0))
+    keccak256(0, 64)
+
+    // Load the init code into memory
+    calldatacopy(0, 32, sub(calldatasize, 32))
+
+    // Deploy the HTLC with the value of the call and the salt left on the
+    // stack
+    sub(calldatasize, 32)
+    0
+    callvalue
+    create2
+
+    // The address is zero if the deployment failed
+    dup1
+    iszero
+    failed
+    jumpi
+
+    // Return the address of the HTLC
+    0
+    mstore
+    return(0, 32)
+
+failed:
+    revert(0, 0)
+}
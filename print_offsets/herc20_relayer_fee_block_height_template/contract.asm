@@ -0,0 +1,183 @@
+{
+    // Load received secret size
+    calldatasize
+
+    // Check if secret is zero length
+    iszero
+
+    // If secret is zero length, jump to branch that checks if expiry time has been reached
+    check_expiry
+    jumpi
+
+    // Load expected secret size
+    32
+
+    // Load received secret size
+    calldatasize
+
+    // Compare secret size
+    eq
+    iszero
+
+    // If passed secret is wrong size, jump to exit contract
+    invalid_secret
+    jumpi
+
+    // Load secret into memory
+    calldatacopy(0, 0, 32)
+
+    // Hash secret with SHA-256 (pre-compiled contract 0x02)
+    call(72, 0x02, 0, 0, 32, 33, 32)
+
+    // Placeholder for correct secret hash
+    0x1000000000000000000000000000000000000000000000000000000000000001
+
+    // Load hashed secret from memory
+    mload(33)
+
+    // Compare hashed secret with existing one
+    eq
+
+    // Combine `eq` result with `call` result
+    and
+
+    // Jump to redeem if hashes match
+    redeem
+    jumpi
+
+    // continue to invalid secret if no match
+invalid_secret:
+    // return "invalidSecret" = 0x696e76616c69645365637265740000000000000000000000000000000000000000
+    mstore(0, "invalidSecret")
+    revert(0, 32)
+
+check_expiry:
+    // Number of the current block
+    number
+
+    // Placeholder for refund block height 
+    0x20000002
+
+    // Compare refund block height with current block number
+    lt
+
+    // Jump to refund if time is expired
+    refund
+    jumpi
+
+    // return "tooEarly" = 0x746f6f4561726c79000000000000000000000000000000000000000000000000
+    mstore(0, "tooEarly")
+    revert(0, 32)
+
+
+/*
+    memory  layout
+    0 secret
+    32 hash return value
+    ->
+    0 transfer pointer
+    32 to
+    64 amount
+    96 transfer return
+    ->
+    96 relayer fee transfer pointer
+    128 caller
+    160 relayer fee
+    192 relayer fee transfer return
+*/
+redeem:
+    // log ascii to hex of "redeemed"
+    // 0x72656465656d6564000000000000000000000000000000000000000000000000
+    log1(0, 32, "redeemed")
+    mstore(32,0x3000000000000000000000000000000000000003) // redeem address
+    mstore(64, 0x8000000000000000000000000000000000000000000000000000000000000008) // Net amount
+    1 // Pay the relayer fee
+    finishTransferTokens
+    jump
+
+refund:
+    // log ascii to hex of "refunded"
+    // 0x726566756e646564000000000000000000000000000000000000000000000000
+    log1(0, 0, "refunded")
+    mstore(32, 0x4000000000000000000000000000000000000004) // refund address
+    mstore(64, 0x5000000000000000000000000000000000000000000000000000000000000005) // Amount
+    0 // No relayer fee
+    finishTransferTokens
+    jump
+
+finishTransferTokens:
+    mstore(0, 0xa9059cbb) // first 4bytes of keccak256("transfer(address,uint256)")
+
+    // Token contract address, kept on the stack to check its code size and
+    // for the relayer fee transfer
+    0x6000000000000000000000000000000000000006
+
+    32  // return size
+    96  // return location
+    68  // = 2*32+4
+    28  // = 32-4
+    0   // Ether to transfer
+    dup6 // Token Contract address
+    sub(gas, 100000)
+    call
+
+    // Revert before paying the fee if the transfer reverted, e.g. because
+    // the caller left it too little gas
+    iszero
+    transferFailed
+    jumpi
+
+    // Tokens like USDT return nothing
+    returndatasize
+    iszero
+    checkTokenCode
+    jumpi
+
+    // Revert unless a boolean `true` was returned
+    lt(returndatasize, 32)
+    transferFailed
+    jumpi
+    iszero(mload(96))
+    transferFailed
+    jumpi
+    transferChecked
+    jump
+
+checkTokenCode:
+    // A call to an account without code succeeds without return data
+    dup1
+    extcodesize
+    iszero
+    transferFailed
+    jumpi
+
+transferChecked:
+    swap1
+    iszero
+    transferred
+    jumpi
+
+    // Pay the relayer fee to the caller, who may not be the redeemer
+    mstore(96, 0xa9059cbb)
+    mstore(128, caller)
+    mstore(160, 0x7000000000000000000000000000000000000000000000000000000000000007) // Relayer fee
+
+    32  // return size
+    192 // return location
+    68  // = 2*32+4
+    124 // = 128-4
+    0   // Ether to transfer
+    dup6 // Token Contract address
+    sub(gas, 100000)
+    call
+    pop
+
+transferred:
+    pop
+    selfdestruct(mload(32))
+
+transferFailed:
+    // return "transferFailed" = 0x7472616e736665724661696c6564000000000000000000000000000000000000
+    mstore(0, "transferFailed")
+    revert(0, 32)
+}
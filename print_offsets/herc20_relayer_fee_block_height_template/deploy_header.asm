@@ -0,0 +1,4 @@
+{
+    codecopy(0, 0x1001, 0x2002)
+    return(0, 0x3003)
+}
@@ -0,0 +1,54 @@
+{
+    // Pull the tokens the deployer approved the HTLC to spend
+    mstore(0, 0x23b872dd) // first 4bytes of keccak256("transferFrom(address,address,uint256)")
+    mstore(32, caller)
+    mstore(64, address)
+    mstore(96, 0x8000000000000000000000000000000000000000000000000000000000000008) // Amount
+
+    // Token contract address, kept on the stack to check its code size
+    0x9000000000000000000000000000000000000009
+
+    32  // return size
+    128 // return location
+    100 // = 3*32+4
+    28  // = 32-4
+    0   // Ether to transfer
+    dup6 // Token Contract address
+    gas
+    call
+
+    // Fail the deployment if the call failed
+    iszero
+    failed
+    jumpi
+
+    // Tokens like USDT return nothing
+    returndatasize
+    iszero
+    checkTokenCode
+    jumpi
+
+    // Fail the deployment unless a boolean `true` was returned
+    lt(returndatasize, 32)
+    failed
+    jumpi
+    iszero(mload(128))
+    failed
+    jumpi
+    funded
+    jump
+
+checkTokenCode:
+    // A call to an account without code succeeds without return data
+    extcodesize
+    iszero
+    failed
+    jumpi
+
+funded:
+    codecopy(0, 0x1001, 0x2002)
+    return(0, 0x3003)
+
+failed:
+    revert(0, 0)
+}
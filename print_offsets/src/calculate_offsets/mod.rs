@@ -31,7 +31,7 @@ pub fn placeholder_offsets<C: Contract>(contract: C) -> Result<Vec<Offset>> {
 
 fn calc_offset(placeholder: &Placeholder, contract: &[u8]) -> Result<Offset> {
     let decoded_placeholder = hex::decode(placeholder.replace_pattern.as_str())?;
-    let start_pos = find_subsequence(&contract[..], &decoded_placeholder[..])
+    let start_pos = find_subsequence(contract, &decoded_placeholder[..])
         .with_context(|| format!("failed to find placeholder {}", placeholder.name))?;
     let end_pos = start_pos + decoded_placeholder.len();
 
@@ -57,6 +57,6 @@ fn check_bin_in_path(bin: &str) {
             bin,
             std::env::var("PATH")
         );
-        panic!(msg);
+        panic!("{}", msg);
     }
 }
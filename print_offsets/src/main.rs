@@ -23,8 +23,32 @@ use anyhow::Result;
 use std::path::Path;
 
 const HETH_TEMPLATE_FOLDER: &str = "./print_offsets/heth_template/";
+const HETH_BLOCK_HEIGHT_TEMPLATE_FOLDER: &str = "./print_offsets/heth_block_height_template/";
 const HERC20_TEMPLATE_FOLDER: &str = "./print_offsets/herc20_template/";
+const HERC20_BLOCK_HEIGHT_TEMPLATE_FOLDER: &str = "./print_offsets/herc20_block_height_template/";
+const HERC20_FULL_BALANCE_TEMPLATE_FOLDER: &str = "./print_offsets/herc20_full_balance_template/";
+const HERC20_FULL_BALANCE_BLOCK_HEIGHT_TEMPLATE_FOLDER: &str =
+    "./print_offsets/herc20_full_balance_block_height_template/";
+const HERC20_RELAYER_FEE_TEMPLATE_FOLDER: &str = "./print_offsets/herc20_relayer_fee_template/";
+const HERC20_RELAYER_FEE_BLOCK_HEIGHT_TEMPLATE_FOLDER: &str =
+    "./print_offsets/herc20_relayer_fee_block_height_template/";
+const HERC20_SAFE_TEMPLATE_FOLDER: &str = "./print_offsets/herc20_safe_template/";
+const HERC20_SAFE_BLOCK_HEIGHT_TEMPLATE_FOLDER: &str =
+    "./print_offsets/herc20_safe_block_height_template/";
+const HERC20_TRANSFER_FROM_TEMPLATE_FOLDER: &str = "./print_offsets/herc20_transfer_from_template/";
+const HERC20_TRANSFER_FROM_BLOCK_HEIGHT_TEMPLATE_FOLDER: &str =
+    "./print_offsets/herc20_transfer_from_block_height_template/";
+const HETH_RELAYER_FEE_TEMPLATE_FOLDER: &str = "./print_offsets/heth_relayer_fee_template/";
+const HETH_RELAYER_FEE_BLOCK_HEIGHT_TEMPLATE_FOLDER: &str =
+    "./print_offsets/heth_relayer_fee_block_height_template/";
+const HETH_KECCAK256_TEMPLATE_FOLDER: &str = "./print_offsets/heth_keccak256_template/";
+const HETH_KECCAK256_BLOCK_HEIGHT_TEMPLATE_FOLDER: &str =
+    "./print_offsets/heth_keccak256_block_height_template/";
+const HERC20_KECCAK256_TEMPLATE_FOLDER: &str = "./print_offsets/herc20_keccak256_template/";
+const HERC20_KECCAK256_BLOCK_HEIGHT_TEMPLATE_FOLDER: &str =
+    "./print_offsets/herc20_keccak256_block_height_template/";
 const HBIT_TEMPLATE_FOLDER: &str = "./print_offsets/hbit_template/";
+const FACTORY_TEMPLATE_FOLDER: &str = "./print_offsets/factory_template/";
 
 #[allow(clippy::print_stdout)]
 fn main() -> Result<()> {
@@ -36,10 +60,84 @@ fn main() -> Result<()> {
         "{}",
         generate_markdown::<EthereumContract, &str>(HETH_TEMPLATE_FOLDER)?
     );
+    println!(
+        "{}",
+        generate_markdown::<EthereumContract, &str>(HETH_BLOCK_HEIGHT_TEMPLATE_FOLDER)?
+    );
     println!(
         "{}",
         generate_markdown::<EthereumContract, &str>(HERC20_TEMPLATE_FOLDER)?
     );
+    println!(
+        "{}",
+        generate_markdown::<EthereumContract, &str>(HERC20_BLOCK_HEIGHT_TEMPLATE_FOLDER)?
+    );
+    println!(
+        "{}",
+        generate_markdown::<EthereumContract, &str>(HETH_KECCAK256_TEMPLATE_FOLDER)?
+    );
+    println!(
+        "{}",
+        generate_markdown::<EthereumContract, &str>(HETH_KECCAK256_BLOCK_HEIGHT_TEMPLATE_FOLDER)?
+    );
+    println!(
+        "{}",
+        generate_markdown::<EthereumContract, &str>(HERC20_KECCAK256_TEMPLATE_FOLDER)?
+    );
+    println!(
+        "{}",
+        generate_markdown::<EthereumContract, &str>(HERC20_KECCAK256_BLOCK_HEIGHT_TEMPLATE_FOLDER)?
+    );
+    println!(
+        "{}",
+        generate_markdown::<EthereumContract, &str>(HERC20_FULL_BALANCE_TEMPLATE_FOLDER)?
+    );
+    println!(
+        "{}",
+        generate_markdown::<EthereumContract, &str>(
+            HERC20_FULL_BALANCE_BLOCK_HEIGHT_TEMPLATE_FOLDER
+        )?
+    );
+    println!(
+        "{}",
+        generate_markdown::<EthereumContract, &str>(HETH_RELAYER_FEE_TEMPLATE_FOLDER)?
+    );
+    println!(
+        "{}",
+        generate_markdown::<EthereumContract, &str>(HETH_RELAYER_FEE_BLOCK_HEIGHT_TEMPLATE_FOLDER)?
+    );
+    println!(
+        "{}",
+        generate_markdown::<EthereumContract, &str>(HERC20_RELAYER_FEE_TEMPLATE_FOLDER)?
+    );
+    println!(
+        "{}",
+        generate_markdown::<EthereumContract, &str>(
+            HERC20_RELAYER_FEE_BLOCK_HEIGHT_TEMPLATE_FOLDER
+        )?
+    );
+    println!(
+        "{}",
+        generate_markdown::<EthereumContract, &str>(HERC20_SAFE_TEMPLATE_FOLDER)?
+    );
+    println!(
+        "{}",
+        generate_markdown::<EthereumContract, &str>(HERC20_SAFE_BLOCK_HEIGHT_TEMPLATE_FOLDER)?
+    );
+    println!(
+        "{}",
+        generate_markdown::<EthereumContract, &str>(HERC20_TRANSFER_FROM_TEMPLATE_FOLDER)?
+    );
+    println!(
+        "{}",
+        generate_markdown::<EthereumContract, &str>(
+            HERC20_TRANSFER_FROM_BLOCK_HEIGHT_TEMPLATE_FOLDER
+        )?
+    );
+    println!(
+        "{}",
+        generate_markdown::<EthereumContract, &str>(FACTORY_TEMPLATE_FOLDER)?
+    );
 
     Ok(())
 }
@@ -60,7 +158,10 @@ fn generate_markdown<C: Contract, S: AsRef<Path>>(template_folder: S) -> Result<
 #[cfg(test)]
 mod tests {
     use super::*;
-    use blockchain_contracts::ethereum::{herc20, heth};
+    use blockchain_contracts::ethereum::{
+        factory, herc20, herc20_full_balance, herc20_relayer_fee, herc20_safe,
+        herc20_transfer_from, heth, heth_relayer_fee,
+    };
 
     #[test]
     fn heth_contract_template_matches_template_in_calculate_offsets() -> Result<()> {
@@ -72,6 +173,17 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn heth_block_height_contract_template_matches_template_in_calculate_offsets() -> Result<()> {
+        let contract =
+            EthereumContract::compile(Path::new("..").join(HETH_BLOCK_HEIGHT_TEMPLATE_FOLDER))?;
+        assert_eq!(
+            heth::BLOCK_HEIGHT_CONTRACT_TEMPLATE.to_vec(),
+            contract.metadata().contract,
+        );
+        Ok(())
+    }
+
     #[test]
     fn herc20_contract_template_matches_template_in_calculate_offsets() -> Result<()> {
         let contract = EthereumContract::compile(Path::new("..").join(HERC20_TEMPLATE_FOLDER))?;
@@ -81,4 +193,191 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn herc20_block_height_contract_template_matches_template_in_calculate_offsets() -> Result<()> {
+        let contract =
+            EthereumContract::compile(Path::new("..").join(HERC20_BLOCK_HEIGHT_TEMPLATE_FOLDER))?;
+        assert_eq!(
+            herc20::BLOCK_HEIGHT_CONTRACT_TEMPLATE.to_vec(),
+            contract.metadata().contract,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn heth_keccak256_contract_template_matches_template_in_calculate_offsets() -> Result<()> {
+        let contract =
+            EthereumContract::compile(Path::new("..").join(HETH_KECCAK256_TEMPLATE_FOLDER))?;
+        assert_eq!(
+            heth::KECCAK256_CONTRACT_TEMPLATE.to_vec(),
+            contract.metadata().contract,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn heth_keccak256_block_height_contract_template_matches_template_in_calculate_offsets(
+    ) -> Result<()> {
+        let contract = EthereumContract::compile(
+            Path::new("..").join(HETH_KECCAK256_BLOCK_HEIGHT_TEMPLATE_FOLDER),
+        )?;
+        assert_eq!(
+            heth::KECCAK256_BLOCK_HEIGHT_CONTRACT_TEMPLATE.to_vec(),
+            contract.metadata().contract,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn herc20_keccak256_contract_template_matches_template_in_calculate_offsets() -> Result<()> {
+        let contract =
+            EthereumContract::compile(Path::new("..").join(HERC20_KECCAK256_TEMPLATE_FOLDER))?;
+        assert_eq!(
+            herc20::KECCAK256_CONTRACT_TEMPLATE.to_vec(),
+            contract.metadata().contract,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn herc20_keccak256_block_height_contract_template_matches_template_in_calculate_offsets(
+    ) -> Result<()> {
+        let contract = EthereumContract::compile(
+            Path::new("..").join(HERC20_KECCAK256_BLOCK_HEIGHT_TEMPLATE_FOLDER),
+        )?;
+        assert_eq!(
+            herc20::KECCAK256_BLOCK_HEIGHT_CONTRACT_TEMPLATE.to_vec(),
+            contract.metadata().contract,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn herc20_full_balance_contract_template_matches_template_in_calculate_offsets() -> Result<()> {
+        let contract =
+            EthereumContract::compile(Path::new("..").join(HERC20_FULL_BALANCE_TEMPLATE_FOLDER))?;
+        assert_eq!(
+            herc20_full_balance::CONTRACT_TEMPLATE.to_vec(),
+            contract.metadata().contract,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn herc20_full_balance_block_height_contract_template_matches_template_in_calculate_offsets(
+    ) -> Result<()> {
+        let contract = EthereumContract::compile(
+            Path::new("..").join(HERC20_FULL_BALANCE_BLOCK_HEIGHT_TEMPLATE_FOLDER),
+        )?;
+        assert_eq!(
+            herc20_full_balance::BLOCK_HEIGHT_CONTRACT_TEMPLATE.to_vec(),
+            contract.metadata().contract,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn heth_relayer_fee_contract_template_matches_template_in_calculate_offsets() -> Result<()> {
+        let contract =
+            EthereumContract::compile(Path::new("..").join(HETH_RELAYER_FEE_TEMPLATE_FOLDER))?;
+        assert_eq!(
+            heth_relayer_fee::CONTRACT_TEMPLATE.to_vec(),
+            contract.metadata().contract,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn heth_relayer_fee_block_height_contract_template_matches_template_in_calculate_offsets(
+    ) -> Result<()> {
+        let contract = EthereumContract::compile(
+            Path::new("..").join(HETH_RELAYER_FEE_BLOCK_HEIGHT_TEMPLATE_FOLDER),
+        )?;
+        assert_eq!(
+            heth_relayer_fee::BLOCK_HEIGHT_CONTRACT_TEMPLATE.to_vec(),
+            contract.metadata().contract,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn herc20_relayer_fee_contract_template_matches_template_in_calculate_offsets() -> Result<()> {
+        let contract =
+            EthereumContract::compile(Path::new("..").join(HERC20_RELAYER_FEE_TEMPLATE_FOLDER))?;
+        assert_eq!(
+            herc20_relayer_fee::CONTRACT_TEMPLATE.to_vec(),
+            contract.metadata().contract,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn herc20_relayer_fee_block_height_contract_template_matches_template_in_calculate_offsets(
+    ) -> Result<()> {
+        let contract = EthereumContract::compile(
+            Path::new("..").join(HERC20_RELAYER_FEE_BLOCK_HEIGHT_TEMPLATE_FOLDER),
+        )?;
+        assert_eq!(
+            herc20_relayer_fee::BLOCK_HEIGHT_CONTRACT_TEMPLATE.to_vec(),
+            contract.metadata().contract,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn herc20_safe_contract_template_matches_template_in_calculate_offsets() -> Result<()> {
+        let contract =
+            EthereumContract::compile(Path::new("..").join(HERC20_SAFE_TEMPLATE_FOLDER))?;
+        assert_eq!(
+            herc20_safe::CONTRACT_TEMPLATE.to_vec(),
+            contract.metadata().contract,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn herc20_safe_block_height_contract_template_matches_template_in_calculate_offsets(
+    ) -> Result<()> {
+        let contract = EthereumContract::compile(
+            Path::new("..").join(HERC20_SAFE_BLOCK_HEIGHT_TEMPLATE_FOLDER),
+        )?;
+        assert_eq!(
+            herc20_safe::BLOCK_HEIGHT_CONTRACT_TEMPLATE.to_vec(),
+            contract.metadata().contract,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn herc20_transfer_from_contract_template_matches_template_in_calculate_offsets() -> Result<()>
+    {
+        let contract =
+            EthereumContract::compile(Path::new("..").join(HERC20_TRANSFER_FROM_TEMPLATE_FOLDER))?;
+        assert_eq!(
+            herc20_transfer_from::CONTRACT_TEMPLATE.to_vec(),
+            contract.metadata().contract,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn herc20_transfer_from_block_height_contract_template_matches_template_in_calculate_offsets(
+    ) -> Result<()> {
+        let contract = EthereumContract::compile(
+            Path::new("..").join(HERC20_TRANSFER_FROM_BLOCK_HEIGHT_TEMPLATE_FOLDER),
+        )?;
+        assert_eq!(
+            herc20_transfer_from::BLOCK_HEIGHT_CONTRACT_TEMPLATE.to_vec(),
+            contract.metadata().contract,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn factory_contract_matches_template_in_calculate_offsets() -> Result<()> {
+        let contract = EthereumContract::compile(Path::new("..").join(FACTORY_TEMPLATE_FOLDER))?;
+        assert_eq!(factory::CONTRACT.to_vec(), contract.metadata().contract);
+        Ok(())
+    }
 }